@@ -0,0 +1,475 @@
+use crate::config::SparrowConfig;
+use crate::optimizer::checkpoint::{Checkpoint, Checkpointer};
+use crate::optimizer::groups::{ItemGroup, JsonItemGroup};
+use crate::optimizer::listener::{OptimizationListener, SolutionListener};
+use crate::optimizer::mirror::MirrorVariants;
+use crate::optimizer::portfolio::SharedBest;
+use crate::optimizer::strip::StripMode;
+use crate::optimizer::trace::Trace;
+use crate::optimizer::{optimize, Budget, InitialState, RunOptions, RunStats, Target, Terminator};
+use crate::util::io;
+use crate::util::io::json_solution::{build_sp_problem, compose_json_solution, expand_by_margin, JsonSolution};
+use crate::util::io::layout_to_svg::s_layout_to_svg;
+use crate::util::io::sink::SolutionSink;
+use crate::util::io::svg_util::SvgDrawOptions;
+use crate::util::io::to_sp_instance;
+use crate::util::validation::{validate_solution, Violation};
+use jagua_rs::entities::general::Instance;
+use jagua_rs::entities::strip_packing::{SPInstance, SPSolution};
+use jagua_rs::io::json_instance::JsonInstance;
+use jagua_rs::io::parser::Parser;
+use log::{info, warn, Level};
+use ordered_float::OrderedFloat;
+use rand::prelude::SmallRng;
+use rand::SeedableRng;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Time limit used when no budget is configured at all
+pub const DEFAULT_TIME_LIMIT: Duration = Duration::from_secs(600);
+
+/// High-level entry point for using sparrow as a library.
+/// Nothing is written to disk unless explicitly requested (sinks or checkpoints).
+///
+/// ```no_run
+/// # use sparrow::api::Sparrow;
+/// # use std::time::Duration;
+/// # let json_instance = sparrow::util::io::read_json_instance(std::path::Path::new("data/input/swim.json"));
+/// let result = Sparrow::builder()
+///     .json_instance(json_instance)
+///     .seed(42)
+///     .time_limit(Duration::from_secs(60))
+///     .build()
+///     .expect("invalid run configuration")
+///     .run();
+/// println!("density: {:.3}%", result.stats.final_density * 100.0);
+/// ```
+pub struct Sparrow {
+    pub instance: SPInstance,
+    pub name: String,
+    /// Distance the strip of the instance was shrunk by on every side to respect the bin margin
+    pub margin_inset: f32,
+    seed: Option<u64>,
+    target: Option<Target>,
+    handle_ctrlc: bool,
+    options: RunOptions,
+}
+
+impl Sparrow {
+    pub fn builder() -> SparrowBuilder {
+        SparrowBuilder::default()
+    }
+
+    pub fn config(&self) -> &SparrowConfig {
+        &self.options.config
+    }
+
+    /// Groups of items which are placed as a single unit
+    pub fn groups(&self) -> &[ItemGroup] {
+        &self.options.groups
+    }
+
+    /// Mirrored variants of the items which allow mirroring, part of `instance`
+    pub fn mirrors(&self) -> &MirrorVariants {
+        &self.options.mirrors
+    }
+
+    pub fn run(self) -> SparrowResult {
+        let mut terminator = match self.handle_ctrlc {
+            true => Terminator::new_with_ctrlc_handler(),
+            false => Terminator::new_without_ctrlc(),
+        };
+        terminator.set_target(self.target);
+        self.run_with(terminator)
+    }
+
+    fn run_with(self, terminator: Terminator) -> SparrowResult {
+        let seed = match self.seed {
+            Some(seed) => {
+                info!("[API] using seed: {}", seed);
+                seed
+            }
+            None => {
+                let seed = rand::random();
+                warn!("[API] no seed provided, using: {}", seed);
+                seed
+            }
+        };
+
+        let (config, mirrors) = (self.options.config, self.options.mirrors.clone());
+        let result = optimize(self.instance.clone(), SmallRng::seed_from_u64(seed), terminator, self.options);
+
+        SparrowResult {
+            solution: result.solution,
+            instance: self.instance,
+            mirrors,
+            name: self.name,
+            config,
+            margin_inset: self.margin_inset,
+            seed,
+            target_met: result.target_met,
+            stats: result.stats,
+            trace: result.trace,
+        }
+    }
+}
+
+/// Runs independent optimizations of the same instance concurrently and returns the best result.
+/// With `share_best`, runs which fail to separate while another run is ahead restart from its best solution.
+/// All runs stop as soon as one of them meets the target (of the first run), Ctrl-C is handled for all runs at once.
+pub fn run_portfolio(runs: Vec<Sparrow>, share_best: bool) -> SparrowResult {
+    assert!(!runs.is_empty(), "a portfolio needs at least one run");
+    let mut terminator = match runs.iter().any(|run| run.handle_ctrlc) {
+        true => Terminator::new_with_ctrlc_handler(),
+        false => Terminator::new_without_ctrlc(),
+    };
+    //clones of the terminator share the target, every run gets its own budget counters
+    terminator.set_target(runs[0].target);
+    let shared_best = share_best.then(SharedBest::new);
+    info!("[API] running a portfolio of {} runs (sharing the best solution: {})", runs.len(), share_best);
+
+    let results = std::thread::scope(|s| {
+        let handles = runs.into_iter()
+            .map(|mut run| {
+                run.options.shared_best = shared_best.clone();
+                let terminator = terminator.clone();
+                s.spawn(move || run.run_with(terminator))
+            })
+            .collect::<Vec<_>>();
+        handles.into_iter()
+            .map(|handle| handle.join().expect("portfolio run panicked"))
+            .collect::<Vec<_>>()
+    });
+
+    for (i, result) in results.iter().enumerate() {
+        info!("[API] portfolio run {} (seed: {}) finished at {:.3} ({:.3}%)", i, result.seed, result.solution.strip_width, result.stats.final_density * 100.0);
+    }
+    results.into_iter()
+        .min_by_key(|result| (OrderedFloat(result.config.strip_mode.solution_objective(&result.solution)), OrderedFloat(-result.stats.final_density)))
+        .unwrap()
+}
+
+/// Best solution of a run together with its statistics
+pub struct SparrowResult {
+    /// Solution within the usable area of the strip, see [`SparrowResult::json_solution`] for the full strip
+    pub solution: SPSolution,
+    pub instance: SPInstance,
+    pub mirrors: MirrorVariants,
+    pub name: String,
+    pub config: SparrowConfig,
+    pub margin_inset: f32,
+    /// Seed the run was started with, can be used to reproduce it
+    pub seed: u64,
+    pub target_met: bool,
+    pub stats: RunStats,
+    /// Improvements and periodic samples of the run, see [`Trace::to_csv`]
+    pub trace: Trace,
+}
+
+impl SparrowResult {
+    /// Solution on the full strip, including the bin margin
+    pub fn json_solution(&self) -> JsonSolution {
+        let mut json_sol = compose_json_solution(&self.solution, &self.instance, &self.mirrors, &self.name);
+        if self.margin_inset > 0.0 {
+            expand_by_margin(&mut json_sol, self.margin_inset);
+        }
+        json_sol
+    }
+
+    /// Exact check of [`SparrowResult::json_solution`] against the item separation and bin margin of the configuration
+    pub fn validate(&self) -> Vec<Violation> {
+        let spacing = self.config.min_item_separation.unwrap_or(0.0);
+        let margin = self.config.bin_margin.unwrap_or(0.0);
+        validate_solution(&self.json_solution(), &self.instance, spacing, margin)
+    }
+
+    /// Writes the solution to `final_{name}.svg` and `final_{name}.json` in the folder.
+    /// The SVG only shows the usable area of the strip (within the bin margin).
+    pub fn export(&self, folder: &Path, draw_options: SvgDrawOptions) {
+        let svg = s_layout_to_svg(&self.solution.layout_snapshot, &self.instance, &self.mirrors, draw_options, "final");
+        io::write_svg(&svg, &folder.join(format!("final_{}.svg", self.name)), Level::Info);
+        io::write_json(&self.json_solution(), &folder.join(format!("final_{}.json", self.name)), Level::Info);
+    }
+}
+
+enum InstanceSource {
+    Json(JsonInstance),
+    Parsed(SPInstance),
+}
+
+#[derive(Default)]
+pub struct SparrowBuilder {
+    instance: Option<InstanceSource>,
+    name: Option<String>,
+    config: Option<SparrowConfig>,
+    seed: Option<u64>,
+    time_limit: Option<Duration>,
+    phase_time_limits: Option<(Duration, Duration)>,
+    eval_budget: Option<usize>,
+    iter_budget: Option<usize>,
+    target: Option<Target>,
+    groups: Vec<JsonItemGroup>,
+    mirrored_items: Vec<usize>,
+    initial_solution: Option<JsonSolution>,
+    resume: Option<Checkpoint>,
+    checkpoint: Option<(PathBuf, Duration)>,
+    sinks: Vec<Box<dyn SolutionSink>>,
+    listeners: Vec<Box<dyn OptimizationListener>>,
+    handle_ctrlc: bool,
+}
+
+impl SparrowBuilder {
+    /// Instance to optimize, parsed with the parameters of the configuration
+    pub fn json_instance(mut self, json_instance: JsonInstance) -> Self {
+        self.name.get_or_insert(json_instance.name.clone());
+        self.instance = Some(InstanceSource::Json(json_instance));
+        self
+    }
+
+    /// Instance to optimize, already parsed
+    pub fn instance(mut self, instance: SPInstance) -> Self {
+        self.instance = Some(InstanceSource::Parsed(instance));
+        self
+    }
+
+    /// Name of the instance, used in the exported solutions
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn config(mut self, config: SparrowConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Overrides the seed of the configuration
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Total time limit, split across the phases according to the configured time ratios
+    pub fn time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    /// Separate time limits for the exploration and compression phase
+    pub fn phase_time_limits(mut self, explore: Duration, compress: Duration) -> Self {
+        self.phase_time_limits = Some((explore, compress));
+        self
+    }
+
+    /// Total number of sample evaluations, split across the phases like the time limit
+    pub fn eval_budget(mut self, n_evals: usize) -> Self {
+        self.eval_budget = Some(n_evals);
+        self
+    }
+
+    /// Total number of separator iterations, split across the phases like the time limit
+    pub fn iter_budget(mut self, n_iters: usize) -> Self {
+        self.iter_budget = Some(n_iters);
+        self
+    }
+
+    pub fn target(mut self, target: Target) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Groups of items which keep their relative placement, see [`ItemGroup`]
+    pub fn groups(mut self, groups: Vec<JsonItemGroup>) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    /// Items which may also be placed mirrored, see [`MirrorVariants`] (only for a [`SparrowBuilder::json_instance`])
+    pub fn mirrored_items(mut self, item_ids: Vec<usize>) -> Self {
+        self.mirrored_items = item_ids;
+        self
+    }
+
+    /// Warm start from an existing solution instead of constructing one
+    pub fn initial_solution(mut self, json_solution: JsonSolution) -> Self {
+        self.initial_solution = Some(json_solution);
+        self
+    }
+
+    /// Resume a previous run, the remaining budgets of the checkpoint replace the configured ones
+    pub fn resume(mut self, checkpoint: Checkpoint) -> Self {
+        self.resume = Some(checkpoint);
+        self
+    }
+
+    /// Periodically write a checkpoint of the run to `path`
+    pub fn checkpoint(mut self, path: impl Into<PathBuf>, interval: Duration) -> Self {
+        self.checkpoint = Some((path.into(), interval));
+        self
+    }
+
+    /// Registers a destination for the intermediate and final solutions, can be called multiple times
+    pub fn sink(mut self, sink: impl SolutionSink + 'static) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    /// Called with every new best feasible solution found during the run
+    pub fn on_solution(self, on_solution: impl FnMut(&SPSolution) + Send + 'static) -> Self {
+        self.listener(SolutionListener(on_solution))
+    }
+
+    /// Registers a listener for the events of the run, can be called multiple times
+    pub fn listener(mut self, listener: impl OptimizationListener + 'static) -> Self {
+        self.listeners.push(Box::new(listener));
+        self
+    }
+
+    /// Install a Ctrl-C handler which moves the run to the next phase (can only be done once per process)
+    pub fn handle_ctrlc(mut self, handle_ctrlc: bool) -> Self {
+        self.handle_ctrlc = handle_ctrlc;
+        self
+    }
+
+    pub fn build(self) -> Result<Sparrow, BuildError> {
+        let config = self.config.unwrap_or_default();
+
+        //warm starts and checkpoints assume the strip height of the instance
+        let unsupported_start = match (self.initial_solution.is_some(), self.resume.is_some() || self.checkpoint.is_some()) {
+            (_, true) if config.strip_mode != StripMode::Horizontal => Some("checkpoints"),
+            (true, _) if config.strip_mode != StripMode::Horizontal => Some("warm starts"),
+            _ => None,
+        };
+        if let Some(start) = unsupported_start {
+            return Err(BuildError::UnsupportedStripMode { strip_mode: config.strip_mode, feature: start });
+        }
+
+        let (instance, mirrors) = match self.instance.ok_or(BuildError::MissingInstance)? {
+            InstanceSource::Json(json_instance) => {
+                let (json_instance, mirrors) = match self.mirrored_items.is_empty() {
+                    true => (json_instance, MirrorVariants::default()),
+                    false => MirrorVariants::new(&json_instance, &self.mirrored_items),
+                };
+                let parser = Parser::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+                let any_instance = parser.parse(&json_instance);
+                (to_sp_instance(any_instance.as_ref()).ok_or(BuildError::NotStripPacking)?, mirrors)
+            }
+            InstanceSource::Parsed(_) if !self.mirrored_items.is_empty() => return Err(BuildError::MirroringParsedInstance),
+            InstanceSource::Parsed(instance) => (instance, MirrorVariants::default()),
+        };
+        //the part of the bin margin not already covered by the item separation is reserved by shrinking the strip
+        let margin_inset = config.bin_margin
+            .map_or(0.0, |margin| (margin - config.min_item_separation.unwrap_or(0.0) / 2.0).max(0.0));
+        let instance = match margin_inset > 0.0 {
+            true => {
+                info!("[API] shrinking the strip by {:.3} on every side for the bin margin", margin_inset);
+                SPInstance::new(instance.items().to_vec(), instance.strip_height - 2.0 * margin_inset)
+            }
+            false => instance,
+        };
+        let name = self.name.unwrap_or_else(|| "instance".to_string());
+        info!("[API] loaded instance {} with #{} items", name, instance.total_item_qty());
+        let groups = self.groups.iter()
+            .map(|json_group| ItemGroup::from_json(json_group, &instance))
+            .collect::<Vec<_>>();
+        if !groups.is_empty() {
+            info!("[API] {} groups of items are placed as a single unit", groups.len());
+        }
+
+        let time_limits = match (self.time_limit, self.phase_time_limits) {
+            (_, Some(phase_time_limits)) => Some(phase_time_limits),
+            (Some(tl), None) => Some((tl.mul_f32(config.expl_cfg.time_ratio), tl.mul_f32(config.cmpr_cfg.time_ratio))),
+            (None, None) if self.eval_budget.is_some() || self.iter_budget.is_some() => None,
+            (None, None) => {
+                warn!("[API] no time limit or budget specified, defaulting to {}s", DEFAULT_TIME_LIMIT.as_secs());
+                Some((DEFAULT_TIME_LIMIT.mul_f32(config.expl_cfg.time_ratio), DEFAULT_TIME_LIMIT.mul_f32(config.cmpr_cfg.time_ratio)))
+            }
+        };
+
+        //evaluation and iteration budgets are split across the phases with the same ratio as the time
+        let split = |n: usize| {
+            let n_explore = (n as f32 * config.expl_cfg.time_ratio) as usize;
+            (n_explore, n - n_explore)
+        };
+        let (explore_evals, compress_evals) = self.eval_budget.map(split).unzip();
+        let (explore_iters, compress_iters) = self.iter_budget.map(split).unzip();
+        let (explore_dur, compress_dur) = time_limits.unzip();
+
+        let explore_budget = Budget { time: explore_dur, evals: explore_evals, iterations: explore_iters };
+        let compress_budget = Budget { time: compress_dur, evals: compress_evals, iterations: compress_iters };
+
+        info!("[API] configured budgets, exploration: {:?}, compression: {:?}", explore_budget, compress_budget);
+
+        if explore_budget.is_deterministic() && compress_budget.is_deterministic() {
+            info!("[API] no time limit, running in deterministic mode");
+            if self.checkpoint.is_some() || self.resume.is_some() {
+                warn!("[API] checkpoints are taken at wall-clock intervals, runs using them are not reproducible");
+            }
+        }
+
+        let init = match (self.resume, self.initial_solution) {
+            (Some(checkpoint), _) => InitialState::Checkpoint(checkpoint),
+            (None, Some(mut json_sol)) => {
+                //exported solutions include the bin margin
+                expand_by_margin(&mut json_sol, -margin_inset);
+                InitialState::Solution(build_sp_problem(&json_sol, &instance, &mirrors, config.cde_config))
+            }
+            (None, None) => InitialState::Construct,
+        };
+
+        let checkpointer = match self.checkpoint {
+            Some((path, interval)) => Checkpointer::new(path, interval, name.clone()),
+            None => Checkpointer::disabled(),
+        };
+
+        let options = RunOptions {
+            config,
+            explore_budget,
+            compress_budget,
+            groups,
+            mirrors,
+            init,
+            checkpointer,
+            sink: Box::new(self.sinks),
+            listener: Box::new(self.listeners),
+            shared_best: None,
+        };
+
+        Ok(Sparrow {
+            instance,
+            name,
+            seed: self.seed.or(config.rng_seed.map(|seed| seed as u64)),
+            margin_inset,
+            target: self.target,
+            handle_ctrlc: self.handle_ctrlc,
+            options,
+        })
+    }
+}
+
+/// Reasons why a [`SparrowBuilder`] cannot build a [`Sparrow`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildError {
+    /// Neither [`SparrowBuilder::json_instance`] nor [`SparrowBuilder::instance`] was called
+    MissingInstance,
+    /// The JSON instance could not be parsed as a strip packing instance
+    NotStripPacking,
+    /// Warm starts and checkpoints are not supported by every [`StripMode`]
+    UnsupportedStripMode { strip_mode: StripMode, feature: &'static str },
+    /// Mirrored variants are added to the JSON instance, an already parsed instance cannot be extended
+    MirroringParsedInstance,
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::MissingInstance => write!(f, "no instance provided"),
+            BuildError::NotStripPacking => write!(f, "instance is not a strip packing instance"),
+            BuildError::UnsupportedStripMode { strip_mode, feature } => write!(f, "{} are not supported in strip mode {:?}", feature, strip_mode),
+            BuildError::MirroringParsedInstance => write!(f, "mirrored items require a JSON instance"),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
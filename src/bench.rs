@@ -1,42 +1,61 @@
 extern crate core;
 
-use jagua_rs::io::parser::Parser;
+use clap::Parser as Clap;
+use itertools::Itertools;
 use ordered_float::OrderedFloat;
 use rand::prelude::SmallRng;
-use rand::{Rng, RngCore, SeedableRng};
+use rand::{Rng, SeedableRng};
+use sparrow::api::{Sparrow, SparrowResult};
 use sparrow::config::*;
-use sparrow::optimizer::lbf::LBFBuilder;
-use sparrow::optimizer::separator::Separator;
-use sparrow::optimizer::{compression_phase, exploration_phase, Terminator};
 use sparrow::util::io;
+use sparrow::util::io::InstanceFile;
+use sparrow::util::io::cli::{BenchCli, BenchCommand, BenchRunArgs, IntermediateExport};
 use sparrow::util::io::layout_to_svg::s_layout_to_svg;
-use std::env::args;
+use sparrow::util::io::report::{BenchReport, RunRecord};
+use sparrow::util::io::sink::{JsonDirSink, SvgDirSink};
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
-use std::time::{Duration, Instant};
-use jagua_rs::geometry::geo_traits::Shape;
-use sparrow::util::io::to_sp_instance;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-fn main() {
-    //the input file is the first argument
-    let input_file_path = args().nth(1).expect("first argument must be the input file");
-    let time_limit: Duration = args().nth(2).expect("second argument must be the time limit [s]")
-        .parse::<u64>().map(|s| Duration::from_secs(s))
-        .expect("second argument must be the time limit [s]");
-    let n_runs_total = args().nth(3).expect("third argument must be the number of runs")
-        .parse().expect("third argument must be the number of runs");
+/// Directory of the instances bundled with the repository
+const BUNDLED_INSTANCES_DIR: &str = "data/input";
 
-    fs::create_dir_all(OUTPUT_DIR).expect("could not create output directory");
+fn main() {
+    let cli = BenchCli::parse();
+    match cli.command {
+        Some(BenchCommand::Compare { baseline, candidate, alpha }) => {
+            compare_reports(&BenchReport::read(Path::new(&baseline)), &BenchReport::read(Path::new(&candidate)), alpha)
+        }
+        None => run_bench(cli.run),
+    }
+}
 
-    let json_instance = io::read_json_instance(Path::new(&input_file_path));
+fn run_bench(args: BenchRunArgs) {
+    assert!(args.runs > 0, "the number of runs should be positive");
+    let output_dir = Path::new(&args.output_dir);
+    fs::create_dir_all(output_dir).expect("could not create output directory");
 
-    println!("[BENCH] git commit hash: {}", get_git_commit_hash());
+    let git_hash = get_git_commit_hash();
+    println!("[BENCH] git commit hash: {}", git_hash);
     println!("[BENCH] system time: {}", jiff::Timestamp::now());
 
-    let mut rng = match RNG_SEED {
+    let mut config = match &args.config_file {
+        Some(config_file_path) => {
+            println!("[BENCH] loading config from: {}", config_file_path);
+            io::read_sparrow_config(Path::new(config_file_path))
+        }
+        None => DEFAULT_SPARROW_CONFIG,
+    };
+    if let Some(n_workers) = args.workers {
+        config.expl_cfg.separator_config.n_workers = n_workers;
+        config.cmpr_cfg.separator_config.n_workers = n_workers;
+    }
+
+    let mut rng = match args.seed.or(config.rng_seed.map(|seed| seed as u64)) {
         Some(seed) => {
             println!("[BENCH] using provided seed: {}", seed);
-            SmallRng::seed_from_u64(seed as u64)
+            SmallRng::seed_from_u64(seed)
         }
         None => {
             let seed = rand::random();
@@ -45,89 +64,177 @@ fn main() {
         }
     };
 
-    let n_runs_per_iter = (num_cpus::get_physical() / SEP_CFG_EXPLORE.n_workers).min(n_runs_total);
-    let n_batches = (n_runs_total as f32 / n_runs_per_iter as f32).ceil() as usize;
+    let time_limit = Duration::from_secs(args.time_limit);
+    let instance_files = resolve_instances(&args.instances, args.bundled).iter()
+        .map(|path| io::read_instance_file(path))
+        .collect::<Vec<_>>();
+    let records = run_suite(&instance_files, &config, time_limit, args.runs, args.intermediate, &mut rng, output_dir);
 
-    println!(
-        "[BENCH] starting bench for {} ({}x{} runs across {} cores, {:?} timelimit)",
-        json_instance.name, n_batches, n_runs_per_iter, num_cpus::get_physical(), time_limit
-    );
+    for instance_file in instance_files.iter() {
+        let name = &instance_file.json_instance.name;
+        let instance_records = records.iter().filter(|r| &r.instance == name).collect::<Vec<_>>();
+        print_instance_stats(name, &instance_records);
+    }
+    print_summary_table(&instance_files.iter().map(|i| i.json_instance.name.clone()).collect::<Vec<_>>(), &records);
+
+    let report = BenchReport {
+        git_hash: git_hash.clone(),
+        timestamp: jiff::Timestamp::now().to_string(),
+        time_limit_secs: args.time_limit,
+        n_workers: config.expl_cfg.separator_config.n_workers,
+        runs: records,
+    };
+    report.write(output_dir, &format!("bench_report_{}", &git_hash[..git_hash.len().min(8)]));
+
+    println!("[BENCH] system time: {}", jiff::Timestamp::now());
+}
+
+/// Resolves the paths of the instances to benchmark.
+/// Every source is either a JSON instance, a glob pattern, a directory (all instances in it)
+/// or a manifest file (`.txt`, one source per line relative to the manifest, `#` for comments).
+fn resolve_instances(sources: &[String], bundled: bool) -> Vec<PathBuf> {
+    let sources = sources.iter().map(PathBuf::from)
+        .chain(bundled.then(|| PathBuf::from(BUNDLED_INSTANCES_DIR)))
+        .collect::<Vec<_>>();
+    let paths = sources.iter()
+        .flat_map(|source| resolve_source(source))
+        .unique()
+        .collect::<Vec<_>>();
+    assert!(!paths.is_empty(), "no instances found in: {:?}", sources);
+    paths
+}
 
-    let parser = Parser::new(CDE_CONFIG, SIMPL_TOLERANCE, MIN_ITEM_SEPARATION);
-    let any_instance = parser.parse(&json_instance);
-    let instance = to_sp_instance(any_instance.as_ref()).expect("Expected SPInstance");
+fn resolve_source(source: &Path) -> Vec<PathBuf> {
+    if source.is_dir() {
+        //other JSON files (e.g. configurations) are skipped
+        fs::read_dir(source)
+            .unwrap_or_else(|err| panic!("could not read instance directory: {}, {}", source.display(), err))
+            .map(|entry| entry.expect("could not read directory entry").path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json") && is_instance_file(path))
+            .sorted()
+            .collect()
+    } else if source.extension().is_some_and(|ext| ext == "txt") {
+        let manifest_dir = source.parent().unwrap_or(Path::new(""));
+        fs::read_to_string(source)
+            .unwrap_or_else(|err| panic!("could not read manifest file: {}, {}", source.display(), err))
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .flat_map(|line| resolve_source(&manifest_dir.join(line)))
+            .collect()
+    } else {
+        let pattern = source.to_string_lossy();
+        glob::glob(&pattern)
+            .unwrap_or_else(|err| panic!("invalid instance pattern: {}, {}", pattern, err))
+            .map(|entry| entry.unwrap_or_else(|err| panic!("could not read instance path: {}", err)))
+            .collect()
+    }
+}
+
+fn is_instance_file(path: &Path) -> bool {
+    fs::read_to_string(path).ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .is_some_and(|value| value.get("Items").is_some())
+}
 
-    let mut final_solutions = vec![];
+/// Runs all instances `n_runs` times.
+/// The runs of all instances are scheduled in batches, as many runs in parallel as the physical cores allow for the number of workers.
+fn run_suite(instance_files: &[InstanceFile], config: &SparrowConfig, time_limit: Duration, n_runs: usize, intermediate: IntermediateExport, rng: &mut SmallRng, output_dir: &Path) -> Vec<RunRecord> {
+    let config = *config;
+    //seeds are drawn upfront, so they do not depend on the batching
+    let jobs = instance_files.iter()
+        .flat_map(|instance_file| (0..n_runs).map(move |run| (instance_file, run)))
+        .map(|(instance_file, run)| (instance_file, run, rng.random::<u64>()))
+        .collect::<Vec<_>>();
 
-    let dummy_terminator = Terminator::new_without_ctrlc();
+    let n_runs_per_iter = (num_cpus::get_physical() / config.expl_cfg.separator_config.n_workers).clamp(1, jobs.len());
+    let n_batches = jobs.len().div_ceil(n_runs_per_iter);
 
-    for i in 0..n_batches {
+    println!(
+        "[BENCH] starting bench for {} instances ({}x{} runs across {} cores, {:?} timelimit)",
+        instance_files.len(), n_batches, n_runs_per_iter, num_cpus::get_physical(), time_limit
+    );
+
+    let mut records = vec![];
+    let mut best_results: HashMap<String, SparrowResult> = HashMap::new();
+
+    for (i, batch) in jobs.chunks(n_runs_per_iter).enumerate() {
         println!("[BENCH] batch {}/{}", i + 1, n_batches);
         println!("[BENCH] system time: {}", jiff::Timestamp::now());
-        let mut iter_solutions = vec![None; n_runs_per_iter];
+        let mut iter_results = (0..batch.len()).map(|_| None).collect::<Vec<_>>();
         rayon::scope(|s| {
-            for (j, sol_slice) in iter_solutions.iter_mut().enumerate() {
-                let bench_idx = i * n_runs_per_iter + j;
-                let output_folder_path = format!("{OUTPUT_DIR}/bench_{}_sols_{}", bench_idx, json_instance.name);
-                let instance = instance.clone();
-                let mut rng = SmallRng::seed_from_u64(rng.random());
-                let mut terminator = dummy_terminator.clone();
+            for (&(instance_file, run, seed), result_slice) in batch.iter().zip(iter_results.iter_mut()) {
+                let output_folder_path = output_dir.join(format!("bench_{}_sols_{}", run, instance_file.json_instance.name));
+                let instance_file = instance_file.clone();
 
                 s.spawn(move |_| {
-                    let mut next_rng = || SmallRng::seed_from_u64(rng.next_u64());
-                    let builder = LBFBuilder::new(instance.clone(), CDE_CONFIG, next_rng(), LBF_SAMPLE_CONFIG).construct();
-                    let mut expl_separator = Separator::new(builder.instance, builder.prob, next_rng(), output_folder_path, 0, SEP_CFG_EXPLORE);
-
-                    terminator.set_timeout_from_now(time_limit.mul_f32(EXPLORE_TIME_RATIO));
-                    let solutions = exploration_phase(&instance, &mut expl_separator, &terminator);
-                    let final_explore_sol = solutions.last().expect("no solutions found during exploration");
-
-                    let start_comp = Instant::now();
-
-                    terminator.set_timeout_from_now(time_limit.mul_f32(COMPRESS_TIME_RATIO)).reset_ctrlc();
-                    let mut cmpr_separator = Separator::new(expl_separator.instance, expl_separator.prob, next_rng(), expl_separator.output_svg_folder, expl_separator.svg_counter, SEP_CFG_COMPRESS);
-                    let cmpr_sol = compression_phase(&instance, &mut cmpr_separator, final_explore_sol, &terminator);
-
-                    println!("[BENCH] [id:{:>3}] finished, expl: {:.3}% ({}s), cmpr: {:.3}% (+{:.3}%) ({}s)",
-                             bench_idx,
-                             final_explore_sol.density(&instance) * 100.0, time_limit.mul_f32(EXPLORE_TIME_RATIO).as_secs(),
-                             cmpr_sol.density(&instance) * 100.0,
-                             cmpr_sol.density(&instance) * 100.0 - final_explore_sol.density(&instance) * 100.0,
-                             start_comp.elapsed().as_secs()
+                    let name = instance_file.json_instance.name.clone();
+                    let mut builder = Sparrow::builder()
+                        .json_instance(instance_file.json_instance)
+                        .mirrored_items(instance_file.mirrored_items)
+                        .groups(instance_file.groups)
+                        .config(config)
+                        .seed(seed)
+                        .time_limit(time_limit);
+                    match intermediate {
+                        IntermediateExport::Svg => builder = builder.sink(SvgDirSink::new(output_folder_path, config.draw_options)),
+                        IntermediateExport::Json => builder = builder.sink(JsonDirSink::new(output_folder_path, name)),
+                        IntermediateExport::None => {}
+                    }
+                    let result = builder
+                        .build()
+                        .unwrap_or_else(|err| panic!("invalid run configuration: {}", err))
+                        .run();
+                    let stats = &result.stats;
+
+                    println!("[BENCH] [{}] [id:{:>3}] finished, expl: {:.3}% ({}s), cmpr: {:.3}% (+{:.3}%) ({}s)",
+                             result.name,
+                             run,
+                             stats.explore_density * 100.0, stats.explore_time.as_secs(),
+                             stats.final_density * 100.0,
+                             stats.final_density * 100.0 - stats.explore_density * 100.0,
+                             stats.compress_time.as_secs()
                     );
 
                     io::write_svg(
-                        &s_layout_to_svg(&cmpr_sol.layout_snapshot, &instance, DRAW_OPTIONS, &*format!("final_bench_{}", bench_idx)),
-                        Path::new(&format!("{OUTPUT_DIR}/final_bench_{}.svg", bench_idx)),
+                        &s_layout_to_svg(&result.solution.layout_snapshot, &result.instance, &result.mirrors, config.draw_options, &*format!("final_bench_{}", run)),
+                        &output_dir.join(format!("final_bench_{}_{}.svg", result.name, run)),
                         log::Level::Info,
                     );
 
-                    *sol_slice = Some(cmpr_sol);
+                    result.trace.write_csv(&output_dir.join(format!("trace_bench_{}_{}.csv", result.name, run)), log::Level::Info);
+
+                    *result_slice = Some((RunRecord::new(run, &result), result));
                 })
             }
         });
-        final_solutions.extend(iter_solutions.into_iter().flatten());
+        for (record, result) in iter_results.into_iter().flatten() {
+            //only the best result of every instance is kept in memory
+            let is_best = best_results.get(&record.instance).is_none_or(|best| result.stats.final_density > best.stats.final_density);
+            if is_best {
+                best_results.insert(record.instance.clone(), result);
+            }
+            records.push(record);
+        }
     }
 
-    //print statistics about the solutions, print best, worst, median and average
-    let (final_widths, final_usages): (Vec<f32>, Vec<f32>) = final_solutions
-        .iter()
-        .map(|s| {
-            let width = s.layout_snapshot.bin.outer_orig.bbox().width();
-            let usage = s.layout_snapshot.density(&instance);
-            (width, usage * 100.0)
-        })
-        .unzip();
+    for (name, best_result) in best_results.iter() {
+        io::write_svg(
+            &s_layout_to_svg(&best_result.solution.layout_snapshot, &best_result.instance, &best_result.mirrors, config.draw_options, "final_best"),
+            &output_dir.join(format!("final_best_{}.svg", name)),
+            log::Level::Info,
+        );
+    }
 
-    let best_final_solution = final_solutions.iter().max_by_key(|s| OrderedFloat(s.density(&instance))).unwrap();
+    records
+}
 
-    io::write_svg(
-        &s_layout_to_svg(&best_final_solution.layout_snapshot, &instance, DRAW_OPTIONS, "final_best"),
-        Path::new(format!("{OUTPUT_DIR}/final_best_{}.svg", json_instance.name).as_str()),
-        log::Level::Info,
-    );
+fn print_instance_stats(name: &str, records: &[&RunRecord]) {
+    //print statistics about the solutions, print best, worst, median and average
+    let final_widths = records.iter().map(|r| r.width).collect::<Vec<_>>();
+    let final_usages = records.iter().map(|r| r.density * 100.0).collect::<Vec<_>>();
 
-    println!("==== BENCH FINISHED ====");
+    println!("==== BENCH FINISHED: {} ====", name);
 
     println!("widths:\n{:?}", &final_widths);
     println!("usages:\n{:?}", &final_usages);
@@ -149,9 +256,92 @@ fn main() {
     println!("avg:    {:.3}", calculate_average(&final_usages));
     println!("stddev: {:.3}", calculate_stddev(&final_usages));
     println!("======================");
-    println!("[BENCH] system time: {}", jiff::Timestamp::now());
 }
 
+/// Prints one row per instance with the usage statistics of its runs
+fn print_summary_table(names: &[String], records: &[RunRecord]) {
+    println!("==== SUITE SUMMARY (usage %) ====");
+    println!("{:<12} {:>5} {:>8} {:>8} {:>8} {:>8} {:>10}", "instance", "runs", "best", "median", "avg", "stddev", "best width");
+    for name in names {
+        let instance_records = records.iter().filter(|r| &r.instance == name).collect::<Vec<_>>();
+        let usages = instance_records.iter().map(|r| r.density * 100.0).collect::<Vec<_>>();
+        let best_width = instance_records.iter().map(|r| r.width).min_by_key(|&x| OrderedFloat(x)).unwrap();
+        println!("{:<12} {:>5} {:>8.3} {:>8.3} {:>8.3} {:>8.3} {:>10.3}",
+                 name,
+                 usages.len(),
+                 usages.iter().max_by_key(|&x| OrderedFloat(*x)).unwrap(),
+                 calculate_median(&usages),
+                 calculate_average(&usages),
+                 calculate_stddev(&usages),
+                 best_width,
+        );
+    }
+    println!("======================");
+}
+
+/// Prints the differences in median and best density per instance (in percentage points),
+/// and whether they are significant according to a two-sided Mann-Whitney U test.
+fn compare_reports(baseline: &BenchReport, candidate: &BenchReport, alpha: f32) {
+    println!("[BENCH] baseline: {} ({})", baseline.git_hash, baseline.timestamp);
+    println!("[BENCH] candidate: {} ({})", candidate.git_hash, candidate.timestamp);
+    if baseline.time_limit_secs != candidate.time_limit_secs || baseline.n_workers != candidate.n_workers {
+        println!("[BENCH] warning: reports differ in time limit ({}s vs {}s) or number of workers ({} vs {})",
+                 baseline.time_limit_secs, candidate.time_limit_secs, baseline.n_workers, candidate.n_workers);
+    }
+
+    println!("==== COMPARISON (usage %) ====");
+    println!("{:<12} {:>7} {:>8} {:>8} {:>8} {:>8} {:>8} {:>8} {:>8} {:>8}",
+             "instance", "runs", "med A", "med B", "Δmed", "best A", "best B", "Δbest", "U", "p");
+    for c in compare_instances(baseline, candidate) {
+        println!("{:<12} {:>7} {:>8.3} {:>8.3} {:>+8.3} {:>8.3} {:>8.3} {:>+8.3} {:>8.1} {:>8.4}{}",
+                 c.name, format!("{}/{}", c.n_runs.0, c.n_runs.1),
+                 c.median.0, c.median.1, c.median.1 - c.median.0,
+                 c.best.0, c.best.1, c.best.1 - c.best.0,
+                 c.u, c.p, if c.p < alpha { " *" } else { "" });
+    }
+    for name in baseline.instances().iter().filter(|name| candidate.densities(name).is_empty()) {
+        println!("[BENCH] {} is missing from the candidate report", name);
+    }
+    for name in candidate.instances().iter().filter(|name| !baseline.instances().contains(name)) {
+        println!("[BENCH] {} is missing from the baseline report", name);
+    }
+    println!("(* significant at α = {})", alpha);
+    println!("======================");
+}
+
+/// Runs of an instance in the baseline (`.0`) and candidate (`.1`) report, densities in %
+struct InstanceComparison {
+    name: String,
+    n_runs: (usize, usize),
+    median: (f32, f32),
+    best: (f32, f32),
+    /// Mann-Whitney U statistic of the baseline and the two-sided p-value
+    u: f32,
+    p: f32,
+}
+
+/// Compares every instance present in both reports, in the order of the baseline
+fn compare_instances(baseline: &BenchReport, candidate: &BenchReport) -> Vec<InstanceComparison> {
+    baseline.instances().into_iter()
+        .filter_map(|name| {
+            let usages_a = baseline.densities(&name).iter().map(|d| d * 100.0).collect::<Vec<_>>();
+            let usages_b = candidate.densities(&name).iter().map(|d| d * 100.0).collect::<Vec<_>>();
+            if usages_b.is_empty() {
+                return None;
+            }
+            let best = |v: &[f32]| *v.iter().max_by_key(|&x| OrderedFloat(*x)).unwrap();
+            let (u, p) = calculate_mann_whitney_u(&usages_a, &usages_b);
+            Some(InstanceComparison {
+                n_runs: (usages_a.len(), usages_b.len()),
+                median: (calculate_median(&usages_a), calculate_median(&usages_b)),
+                best: (best(&usages_a), best(&usages_b)),
+                u,
+                p,
+                name,
+            })
+        })
+        .collect()
+}
 
 pub fn calculate_percentile(v: &[f32], pct: f32) -> f32 {
     // Validate input
@@ -207,3 +397,229 @@ pub fn get_git_commit_hash() -> String {
         false => "unknown".to_string(),
     }
 }
+
+/// Two-sided Mann-Whitney U test, returns the U statistic of `a` and the p-value.
+/// Uses the normal approximation with tie and continuity correction, which becomes inaccurate for very few runs (< 5 per sample).
+pub fn calculate_mann_whitney_u(a: &[f32], b: &[f32]) -> (f32, f32) {
+    assert!(!a.is_empty() && !b.is_empty(), "Cannot compare empty samples");
+    let (n_a, n_b) = (a.len() as f64, b.len() as f64);
+    let n = n_a + n_b;
+
+    //rank the pooled samples, ties get the average of their ranks
+    let pooled = a.iter().map(|&x| (x, true)).chain(b.iter().map(|&x| (x, false)))
+        .sorted_by(|x, y| x.0.partial_cmp(&y.0).unwrap())
+        .collect::<Vec<_>>();
+    let mut rank_sum_a = 0.0;
+    let mut tie_correction = 0.0;
+    let mut i = 0;
+    while i < pooled.len() {
+        let j = (i..pooled.len()).find(|&j| pooled[j].0 != pooled[i].0).unwrap_or(pooled.len());
+        let avg_rank = (i + j + 1) as f64 / 2.0;
+        rank_sum_a += avg_rank * pooled[i..j].iter().filter(|(_, in_a)| *in_a).count() as f64;
+        let t = (j - i) as f64;
+        tie_correction += t.powi(3) - t;
+        i = j;
+    }
+
+    let u = rank_sum_a - n_a * (n_a + 1.0) / 2.0;
+    let mean = n_a * n_b / 2.0;
+    let variance = n_a * n_b / 12.0 * ((n + 1.0) - tie_correction / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        //all values are equal
+        return (u as f32, 1.0);
+    }
+    let z = ((u - mean).abs() - 0.5).max(0.0) / variance.sqrt();
+    let p = 2.0 * (1.0 - normal_cdf(z));
+    (u as f32, p.min(1.0) as f32)
+}
+
+/// Cumulative distribution function of the standard normal distribution
+fn normal_cdf(z: f64) -> f64 {
+    0.5 * (1.0 + erf(z / std::f64::consts::SQRT_2))
+}
+
+/// Approximation of the error function (Abramowitz and Stegun 7.1.26, max error 1.5e-7)
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let poly = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    (1.0 - poly * (-x * x).exp()).copysign(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty directory in the temp folder, unique to the test
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sparrow_bench_{}_{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Directory with two instances, a configuration and a manifest listing one of the instances
+    fn instance_dir(test: &str) -> PathBuf {
+        let dir = temp_dir(test);
+        fs::write(dir.join("a.json"), r#"{ "Name": "a", "Items": [] }"#).unwrap();
+        fs::write(dir.join("b.json"), r#"{ "Name": "b", "Items": [] }"#).unwrap();
+        fs::write(dir.join("config.json"), r#"{ "rng_seed": 42 }"#).unwrap();
+        fs::write(dir.join("suite.txt"), "# instances of the suite\n\nb.json\n").unwrap();
+        dir
+    }
+
+    fn record(instance: &str, run: usize, density: f32) -> RunRecord {
+        RunRecord {
+            instance: instance.to_string(),
+            run,
+            seed: run as u64,
+            width: 100.0,
+            density,
+            explore_density: density * 0.99,
+            explore_secs: 8.0,
+            compress_secs: 2.0,
+            explore_evals: 1000,
+            compress_evals: 500,
+            evals_per_sec: 150.0,
+        }
+    }
+
+    #[test]
+    fn directory_resolves_to_its_instances() {
+        let dir = instance_dir("directory");
+        let paths = resolve_instances(&[dir.to_string_lossy().to_string()], false);
+        assert_eq!(paths, vec![dir.join("a.json"), dir.join("b.json")]);
+    }
+
+    #[test]
+    fn glob_and_manifest_resolve_without_duplicates() {
+        let dir = instance_dir("glob");
+        let pattern = dir.join("?.json").to_string_lossy().to_string();
+        assert_eq!(resolve_instances(&[pattern.clone()], false), vec![dir.join("a.json"), dir.join("b.json")]);
+
+        //the manifest lists an instance which also matches the pattern
+        let manifest = dir.join("suite.txt").to_string_lossy().to_string();
+        assert_eq!(resolve_instances(&[manifest.clone()], false), vec![dir.join("b.json")]);
+        assert_eq!(resolve_instances(&[manifest, pattern], false), vec![dir.join("b.json"), dir.join("a.json")]);
+    }
+
+    #[test]
+    fn bundled_instances_are_resolved() {
+        let paths = resolve_instances(&[], true);
+        assert!(!paths.is_empty());
+        assert!(paths.iter().all(|path| is_instance_file(path)));
+    }
+
+    #[test]
+    #[should_panic(expected = "no instances found")]
+    fn empty_sources_are_rejected() {
+        let dir = temp_dir("empty");
+        resolve_instances(&[dir.to_string_lossy().to_string()], false);
+    }
+
+    #[test]
+    fn only_files_with_items_are_instances() {
+        let dir = instance_dir("instance_file");
+        fs::write(dir.join("invalid.json"), "{ not json").unwrap();
+        assert!(is_instance_file(&dir.join("a.json")));
+        assert!(!is_instance_file(&dir.join("config.json")));
+        assert!(!is_instance_file(&dir.join("invalid.json")));
+        assert!(!is_instance_file(&dir.join("missing.json")));
+    }
+
+    #[test]
+    fn report_is_written_as_json_and_csv() {
+        let dir = temp_dir("report");
+        let report = BenchReport {
+            git_hash: "a1b2c3d4".to_string(),
+            timestamp: "2025-01-01T00:00:00Z".to_string(),
+            time_limit_secs: 10,
+            n_workers: 3,
+            runs: vec![record("swim", 0, 0.75), record("swim", 1, 0.76), record("shirts", 0, 0.88)],
+        };
+        report.write(&dir, "report");
+
+        let read = BenchReport::read(&dir.join("report.json"));
+        assert_eq!(read.git_hash, report.git_hash);
+        assert_eq!((read.time_limit_secs, read.n_workers), (10, 3));
+        assert_eq!(read.instances(), vec!["swim".to_string(), "shirts".to_string()]);
+        assert_eq!(read.densities("swim"), vec![0.75, 0.76]);
+
+        let csv = fs::read_to_string(dir.join("report.csv")).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 1 + report.runs.len());
+        let n_columns = lines[0].split(',').count();
+        assert_eq!(n_columns, 12);
+        assert!(lines[1..].iter().all(|line| line.split(',').count() == n_columns));
+        assert_eq!(lines[1].split(',').take(4).collect::<Vec<_>>(), vec!["a1b2c3d4", "swim", "0", "0"]);
+        assert_eq!(lines[3].split(',').nth(5), Some("0.880000"));
+    }
+
+    fn assert_mann_whitney(a: &[f32], b: &[f32], expected_u: f32, expected_p: f32) {
+        let (u, p) = calculate_mann_whitney_u(a, b);
+        assert_eq!(u, expected_u);
+        assert!((p - expected_p).abs() < 1e-4, "p-value {} differs from {}", p, expected_p);
+
+        //swapping the samples mirrors U around its mean, the p-value stays the same
+        let (u_swapped, p_swapped) = calculate_mann_whitney_u(b, a);
+        assert_eq!(u + u_swapped, (a.len() * b.len()) as f32);
+        assert_eq!(p, p_swapped);
+    }
+
+    #[test]
+    fn mann_whitney_u_of_separated_samples() {
+        assert_mann_whitney(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0], 0.0, 0.080856);
+        assert_mann_whitney(&[3.0, 1.0, 2.0, 5.0, 4.0], &[6.0, 8.0, 7.0, 10.0, 9.0], 0.0, 0.012186);
+    }
+
+    #[test]
+    fn mann_whitney_u_with_ties() {
+        //ties get the average of their ranks: 1, 3, 3, 5.5 for the first sample
+        assert_mann_whitney(&[1.0, 2.0, 2.0, 3.0], &[2.0, 3.0, 4.0, 5.0], 2.5, 0.136658);
+        //all values tied, there is no evidence of a difference
+        assert_mann_whitney(&[1.0, 1.0], &[1.0, 1.0], 2.0, 1.0);
+    }
+
+    #[test]
+    fn mann_whitney_u_of_single_runs() {
+        assert_mann_whitney(&[1.0], &[2.0], 0.0, 1.0);
+        assert_mann_whitney(&[1.0], &[2.0, 3.0, 4.0, 5.0, 6.0], 0.0, 0.241567);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot compare empty samples")]
+    fn mann_whitney_u_rejects_empty_samples() {
+        calculate_mann_whitney_u(&[], &[1.0]);
+    }
+
+    #[test]
+    fn reports_are_compared_per_instance() {
+        let report = |runs: Vec<RunRecord>| BenchReport {
+            git_hash: "a1b2c3d4".to_string(),
+            timestamp: "2025-01-01T00:00:00Z".to_string(),
+            time_limit_secs: 10,
+            n_workers: 3,
+            runs,
+        };
+        let baseline = report((0..5).map(|i| record("swim", i, 0.70 + 0.01 * i as f32))
+            .chain([record("shirts", 0, 0.88)])
+            .collect());
+        let candidate = report((0..5).map(|i| record("swim", i, 0.75 + 0.01 * i as f32)).collect());
+
+        //instances missing from the candidate are skipped
+        let comparisons = compare_instances(&baseline, &candidate);
+        assert_eq!(comparisons.len(), 1);
+        let c = &comparisons[0];
+        assert_eq!(c.name, "swim");
+        assert_eq!(c.n_runs, (5, 5));
+        assert!((c.median.0 - 72.0).abs() < 1e-3 && (c.median.1 - 77.0).abs() < 1e-3);
+        assert!((c.best.0 - 74.0).abs() < 1e-3 && (c.best.1 - 79.0).abs() < 1e-3);
+        assert_eq!(c.u, 0.0);
+        assert!((c.p - 0.012186).abs() < 1e-4);
+
+        //a report compared to itself shows no difference
+        let c = &compare_instances(&candidate, &candidate)[0];
+        assert_eq!(c.median.0, c.median.1);
+        assert_eq!(c.u, 12.5);
+        assert!((c.p - 1.0).abs() < 1e-6);
+    }
+}
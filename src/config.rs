@@ -1,9 +1,83 @@
 use jagua_rs::collision_detection::CDEConfig;
 use jagua_rs::geometry::fail_fast::SPSurrogateConfig;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use crate::optimizer::separator::SeparatorConfig;
+use crate::optimizer::strip::StripMode;
+use crate::quantify::tracker::WeightConfig;
+use crate::sample::coord_descent::CDConfig;
 use crate::sample::search::SampleConfig;
 use crate::util::io::svg_util::{SvgDrawOptions, SvgLayoutTheme};
 
+/// All runtime tunable parameters of sparrow.
+/// Can be loaded from a (partial) JSON or TOML file through [`read_sparrow_config`](crate::util::io::read_sparrow_config),
+/// missing fields fall back to [`DEFAULT_SPARROW_CONFIG`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SparrowConfig {
+    pub rng_seed: Option<usize>,
+    pub cde_config: CDEConfig,
+    /// See [`SIMPL_TOLERANCE`]
+    pub poly_simpl_tolerance: Option<f32>,
+    /// See [`MIN_ITEM_SEPARATION`]
+    pub min_item_separation: Option<f32>,
+    /// See [`BIN_MARGIN`]
+    pub bin_margin: Option<f32>,
+    /// Dimensions of the strip which are minimized
+    pub strip_mode: StripMode,
+    pub expl_cfg: ExplorationConfig,
+    pub cmpr_cfg: CompressionConfig,
+    pub lbf_sample_config: SampleConfig,
+    pub draw_options: SvgDrawOptions,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ExplorationConfig {
+    /// Fraction of the total time budget spent in the exploration phase
+    pub time_ratio: f32,
+    /// Fraction by which the strip is shrunk after every successful separation
+    pub shrink_step: f32,
+    /// Standard deviation of the normal distribution used to select solutions from the pool
+    pub solution_pool_distribution_stddev: f32,
+    pub separator_config: SeparatorConfig,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CompressionConfig {
+    /// Fraction of the total time budget spent in the compression phase
+    pub time_ratio: f32,
+    /// Shrink step at the start and at the end of the compression phase
+    pub shrink_range: (f32, f32),
+    pub separator_config: SeparatorConfig,
+}
+
+impl Default for SparrowConfig {
+    fn default() -> Self {
+        DEFAULT_SPARROW_CONFIG
+    }
+}
+
+pub const DEFAULT_SPARROW_CONFIG: SparrowConfig = SparrowConfig {
+    rng_seed: RNG_SEED,
+    cde_config: CDE_CONFIG,
+    poly_simpl_tolerance: SIMPL_TOLERANCE,
+    min_item_separation: MIN_ITEM_SEPARATION,
+    bin_margin: BIN_MARGIN,
+    strip_mode: StripMode::Horizontal,
+    expl_cfg: ExplorationConfig {
+        time_ratio: EXPLORE_TIME_RATIO,
+        shrink_step: EXPLORE_SHRINK_STEP,
+        solution_pool_distribution_stddev: EXPLORE_SOL_DISTR_STDDEV,
+        separator_config: SEP_CFG_EXPLORE,
+    },
+    cmpr_cfg: CompressionConfig {
+        time_ratio: COMPRESS_TIME_RATIO,
+        shrink_range: COMPRESS_SHRINK_RANGE,
+        separator_config: SEP_CFG_COMPRESS,
+    },
+    lbf_sample_config: LBF_SAMPLE_CONFIG,
+    draw_options: DRAW_OPTIONS,
+};
+
 pub const RNG_SEED: Option<usize> = None;
 
 pub const CDE_CONFIG: CDEConfig = CDEConfig {
@@ -26,6 +100,17 @@ pub const SIMPL_TOLERANCE: Option<f32> = Some(0.001);
 /// See [`jagua_rs::io::parser::Parser::new`] for more details.
 pub const MIN_ITEM_SEPARATION: Option<f32> = None;
 
+/// Defines the minimum distance between items and the edges of the strip.
+/// The strip is shrunk on all sides by the part of the margin not already covered by [`MIN_ITEM_SEPARATION`],
+/// exported solutions are expanded again. Disabled if `None`.
+pub const BIN_MARGIN: Option<f32> = None;
+
+/// Minimum time between two periodic samples of the trace of a run, improvements are always recorded
+pub const TRACE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Tolerance of the validation of final solutions, relative to the largest dimension of the container
+pub const VALIDATION_TOLERANCE_RATIO: f32 = 1e-5;
+
 pub const EXPLORE_SHRINK_STEP: f32 = 0.001;
 pub const EXPLORE_SOL_DISTR_STDDEV: f32 = 0.25;
 pub const EXPLORE_TIME_RATIO: f32 = 0.8;
@@ -37,6 +122,12 @@ pub const WEIGHT_MAX_INC_RATIO: f32 = 2.0;
 pub const WEIGHT_MIN_INC_RATIO: f32 = 1.2;
 pub const WEIGHT_DECAY: f32 = 0.95;
 
+pub const WEIGHT_CONFIG: WeightConfig = WeightConfig {
+    max_inc_ratio: WEIGHT_MAX_INC_RATIO,
+    min_inc_ratio: WEIGHT_MIN_INC_RATIO,
+    decay: WEIGHT_DECAY,
+};
+
 pub const OVERLAP_PROXY_EPSILON_DIAM_RATIO: f32 = 0.01;
 
 pub const SEP_CFG_EXPLORE: SeparatorConfig = SeparatorConfig {
@@ -44,10 +135,12 @@ pub const SEP_CFG_EXPLORE: SeparatorConfig = SeparatorConfig {
     strike_limit: 3,
     log_level: log::Level::Info,
     n_workers: 3,
+    weight_config: WEIGHT_CONFIG,
     sample_config: SampleConfig {
         n_bin_samples: 50,
         n_focussed_samples: 25,
         n_coord_descents: 3,
+        cd_config: CD_CONFIG,
     }
 };
 
@@ -56,10 +149,12 @@ pub const SEP_CFG_COMPRESS: SeparatorConfig = SeparatorConfig {
     strike_limit: 5,
     log_level: log::Level::Debug,
     n_workers: 3,
+    weight_config: WEIGHT_CONFIG,
     sample_config: SampleConfig {
         n_bin_samples: 50,
         n_focussed_samples: 25,
         n_coord_descents: 3,
+        cd_config: CD_CONFIG,
     },
 };
 
@@ -69,15 +164,32 @@ pub const CD_STEP_SUCCESS: f32 = 1.1;
 /// Coordinate descent step multiplier on failure
 pub const CD_STEP_FAIL: f32 = 0.5;
 
+pub const CD_CONFIG: CDConfig = CDConfig {
+    step_success: CD_STEP_SUCCESS,
+    step_fail: CD_STEP_FAIL,
+};
+
 /// Ratio of the item's min dimension to be used as initial and limit step size for the first refinement
 pub const PRE_REF_CD_RATIOS: (f32, f32) = (0.25, 0.02);
 
 /// Ratio of the item's min dimension to be used as initial and limit step size for the final refinement
 pub const FIN_REF_CD_RATIOS: (f32, f32) = (0.01, 0.001);
 
+/// Initial and limit step size (in radians) of the rotation axis for the first refinement, only for items with continuous rotation
+pub const PRE_REF_CD_ROT_STEPS: (f32, f32) = (0.2, 0.01);
+
+/// Initial and limit step size (in radians) of the rotation axis for the final refinement, only for items with continuous rotation
+pub const FIN_REF_CD_ROT_STEPS: (f32, f32) = (0.01, 0.001);
+
+/// Number of equal buckets the full circle is divided in to sample items with continuous rotation
+pub const CONTINUOUS_ROT_BUCKETS: usize = 16;
+
 /// If two samples are closer than this ratio of the item's min dimension, they are considered duplicates
 pub const UNIQUE_SAMPLE_THRESHOLD: f32 = 0.05;
 
+/// Maximum number of times the strip is expanded to fit a group during construction (each by 20%)
+pub const MAX_GROUP_EXPANSIONS: usize = 50;
+
 pub const OUTPUT_DIR: &str = "build/svg";
 
 pub const LIVE_DIR: &str = "data/live";
@@ -101,4 +213,5 @@ pub const LBF_SAMPLE_CONFIG: SampleConfig = SampleConfig {
     n_bin_samples: 1000,
     n_focussed_samples: 0,
     n_coord_descents: 3,
+    cd_config: CD_CONFIG,
 };
\ No newline at end of file
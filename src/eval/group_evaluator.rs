@@ -0,0 +1,47 @@
+use crate::eval::sample_eval::{SampleEval, SampleEvaluator};
+use crate::optimizer::groups::compose;
+use jagua_rs::geometry::DTransformation;
+
+/// Evaluates the placement of an [`ItemGroup`](crate::optimizer::groups::ItemGroup) as a whole.
+/// Samples are transformations of the anchor, every member is evaluated at its relative transformation and the losses are summed.
+/// The group is [SampleEval::Invalid] if any of the members is.
+pub struct GroupEvaluator<E: SampleEvaluator> {
+    /// Evaluator of every member, with its transformation relative to the anchor
+    members: Vec<(E, DTransformation)>,
+    n_evals: usize,
+}
+
+impl<E: SampleEvaluator> GroupEvaluator<E> {
+    pub fn new(members: Vec<(E, DTransformation)>) -> Self {
+        Self { members, n_evals: 0 }
+    }
+}
+
+impl<E: SampleEvaluator> SampleEvaluator for GroupEvaluator<E> {
+    fn eval(&mut self, dt: DTransformation, upper_bound: Option<SampleEval>) -> SampleEval {
+        self.n_evals += 1;
+        let mut total = SampleEval::Clear { loss: 0.0 };
+        for (evaluator, rel_dt) in self.members.iter_mut() {
+            //the remaining budget of the upper bound is passed on to the member
+            let member_bound = match (upper_bound, total) {
+                (Some(SampleEval::Collision { loss: ub }), SampleEval::Collision { loss } | SampleEval::Clear { loss }) => Some(SampleEval::Collision { loss: ub - loss }),
+                (ub, _) => ub,
+            };
+            let member_eval = evaluator.eval(compose(&dt, rel_dt), member_bound);
+            total = match (total, member_eval) {
+                (_, SampleEval::Invalid) => return SampleEval::Invalid,
+                (SampleEval::Clear { loss: l1 }, SampleEval::Clear { loss: l2 }) => SampleEval::Clear { loss: l1 + l2 },
+                (SampleEval::Clear { loss: l1 } | SampleEval::Collision { loss: l1 }, SampleEval::Clear { loss: l2 } | SampleEval::Collision { loss: l2 }) => SampleEval::Collision { loss: l1 + l2 },
+                (SampleEval::Invalid, _) => unreachable!(),
+            };
+            if upper_bound.is_some_and(|ub| total > ub) {
+                return SampleEval::Invalid;
+            }
+        }
+        total
+    }
+
+    fn n_evals(&self) -> usize {
+        self.n_evals
+    }
+}
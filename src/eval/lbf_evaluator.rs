@@ -1,8 +1,9 @@
 use jagua_rs::collision_detection::hazards::filter::NoHazardFilter;
 use crate::eval::sample_eval::{SampleEval, SampleEvaluator};
+use crate::optimizer::strip::Axis;
 use jagua_rs::entities::general::Item;
 use jagua_rs::entities::general::Layout;
-use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::{DTransformation, Transformation};
 use jagua_rs::geometry::geo_traits::{Shape, TransformableFrom};
 use jagua_rs::geometry::primitives::SPolygon;
 
@@ -12,20 +13,47 @@ pub const Y_MULTIPLIER: f32 = 1.0;
 /// Simple evaluator for the Left-Bottom-Fill constructor.
 /// Basically either returns [SampleEval::Invalid] in case of any collision or [SampleEval::Clear] with a loss value
 /// that rewards placements that are closer to the left-bottom corner of the bin.
+/// For vertical strips the multipliers are swapped, rewarding placements closer to the bottom-left corner.
 pub struct LBFEvaluator<'a> {
     layout: &'a Layout,
     item: &'a Item,
     shape_buff: SPolygon,
-    n_evals: usize
+    n_evals: usize,
+    multipliers: (f32, f32),
 }
 
 impl<'a> LBFEvaluator<'a> {
-    pub fn new(layout: &'a Layout, item: &'a Item) -> Self {
+    /// `fill_axis` is the open dimension of the strip, along which the items are packed
+    pub fn new(layout: &'a Layout, item: &'a Item, fill_axis: Axis) -> Self {
+        let multipliers = match fill_axis {
+            Axis::Horizontal => (X_MULTIPLIER, Y_MULTIPLIER),
+            Axis::Vertical => (Y_MULTIPLIER, X_MULTIPLIER),
+        };
         Self {
             layout,
             item,
             shape_buff: item.shape_cd.as_ref().clone(),
-            n_evals: 0
+            n_evals: 0,
+            multipliers,
+        }
+    }
+}
+
+impl<'a> LBFEvaluator<'a> {
+    /// Inferior quality zones of at least the quality the item requires are ignored
+    fn surrogate_collides(&self, transf: &Transformation) -> bool {
+        let cde = self.layout.cde();
+        match &self.item.hazard_filter {
+            Some(filter) => cde.surrogate_collides(self.item.shape_cd.surrogate(), transf, filter),
+            None => cde.surrogate_collides(self.item.shape_cd.surrogate(), transf, &NoHazardFilter),
+        }
+    }
+
+    fn poly_collides(&self) -> bool {
+        let cde = self.layout.cde();
+        match &self.item.hazard_filter {
+            Some(filter) => cde.poly_collides(&self.shape_buff, filter),
+            None => cde.poly_collides(&self.shape_buff, &NoHazardFilter),
         }
     }
 }
@@ -33,19 +61,19 @@ impl<'a> LBFEvaluator<'a> {
 impl<'a> SampleEvaluator for LBFEvaluator<'a> {
     fn eval(&mut self, dt: DTransformation, _upper_bound: Option<SampleEval>) -> SampleEval {
         self.n_evals += 1;
-        let cde = self.layout.cde();
         let transf = dt.into();
-        match cde.surrogate_collides(self.item.shape_cd.surrogate(), &transf, &NoHazardFilter) {
+        match self.surrogate_collides(&transf) {
             true => SampleEval::Invalid, // Surrogate collides with something
             false => {
                 self.shape_buff.transform_from(&self.item.shape_cd, &transf);
-                match cde.poly_collides(&self.shape_buff, &NoHazardFilter) {
+                match self.poly_collides() {
                     true => SampleEval::Invalid, // Exact shape collides with something
                     false => {
                         // No collisions
                         let poi = self.shape_buff.poi.center;
                         let bbox_corner = self.shape_buff.bbox().corners()[0];
-                        let loss = X_MULTIPLIER * (poi.0 + bbox_corner.0) + Y_MULTIPLIER * (poi.1 + bbox_corner.1);
+                        let (x_mult, y_mult) = self.multipliers;
+                        let loss = x_mult * (poi.0 + bbox_corner.0) + y_mult * (poi.1 + bbox_corner.1);
                         SampleEval::Clear{loss}
                     }
                 }
@@ -1,4 +1,5 @@
 pub mod sample_eval;
 pub mod lbf_evaluator;
 pub mod sep_evaluator;
+pub mod group_evaluator;
 pub mod specialized_jaguars_pipeline;
\ No newline at end of file
@@ -33,6 +33,14 @@ impl<'a> SeparationEvaluator<'a> {
             n_evals: 0,
         }
     }
+
+    /// Collisions with these items are ignored, used for the other members of a group which move along with the item
+    pub fn ignore_items(mut self, pks: &[PItemKey]) -> Self {
+        self.detection_map.ignored_pks = pks.iter().copied()
+            .filter(|pk| *pk != self.detection_map.current_pk)
+            .collect();
+        self
+    }
 }
 
 impl<'a> SampleEvaluator for SeparationEvaluator<'a> {
@@ -1,4 +1,4 @@
-use crate::quantify::quantify_collision_poly_bin;
+use crate::quantify::{is_rectangular, quantify_collision_poly_bin_hazard};
 #[cfg(not(feature = "simd"))]
 use crate::quantify::quantify_collision_poly_poly;
 #[cfg(feature = "simd")]
@@ -12,6 +12,7 @@ use float_cmp::approx_eq;
 use jagua_rs::collision_detection::CDEngine;
 use jagua_rs::collision_detection::hazards::HazardEntity;
 use jagua_rs::collision_detection::hazards::detector::HazardDetector;
+use jagua_rs::collision_detection::hazards::filter::{HazardFilter, QZHazardFilter};
 use jagua_rs::collision_detection::quadtree::{QTHazPresence, QTQueryable};
 use jagua_rs::collision_detection::quadtree::QTNode;
 use jagua_rs::entities::general::Layout;
@@ -66,8 +67,8 @@ pub fn collect_poly_collisions_in_detector_custom(
         // If they are not, remove them again from the detector, as they do not collide with the shape
         for haz in cde.all_hazards().filter(|h| h.active) {
             match haz.entity {
-                HazardEntity::BinExterior => {
-                    if let Some((_, idx)) = det.detected_bin {
+                HazardEntity::BinExterior if det.rectangular_bin => {
+                    if let Some(idx) = det.detected_bin_idx(&haz.entity) {
                         if idx >= checkpoint {
                             // If the bin was detected as a potential containment, remove it.
                             // For this specific problem, an item can never be entirely outside the bin (rectangle).
@@ -86,7 +87,14 @@ pub fn collect_poly_collisions_in_detector_custom(
                         }
                     }
                 }
-                _ => unreachable!("unsupported hazard entity"),
+                _ => {
+                    // Other hazards of the bin (irregular exterior, inferior quality zones)
+                    if let Some(idx) = det.detected_bin_idx(&haz.entity) {
+                        if idx >= checkpoint && !cde.poly_or_hazard_are_contained(shape, haz) {
+                            det.remove(&haz.entity)
+                        }
+                    }
+                }
             }
         }
     }
@@ -101,8 +109,15 @@ pub struct SpecializedHazardDetector<'a> {
     pub layout: &'a Layout,
     pub ct: &'a CollisionTracker,
     pub current_pk: PItemKey,
+    /// Items whose collisions are not considered (the other members of a group moving along with the item)
+    pub ignored_pks: Vec<PItemKey>,
     pub detected_pis: SecondaryMap<PItemKey, (HazardEntity, usize)>,
-    pub detected_bin: Option<(HazardEntity, usize)>,
+    /// Detected hazards of the bin (exterior and inferior quality zones)
+    pub detected_bin: Vec<(HazardEntity, usize)>,
+    /// Whether the exterior of the bin is a rectangle, allowing for a faster containment check
+    pub rectangular_bin: bool,
+    /// Filter of the item, the inferior quality zones it may be placed in are not considered
+    pub hazard_filter: Option<QZHazardFilter>,
     pub idx_counter: usize,
     pub loss_cache: (usize, f32),
     pub loss_bound: f32,
@@ -120,8 +135,11 @@ impl<'a> SpecializedHazardDetector<'a> {
             layout,
             ct,
             current_pk,
+            ignored_pks: vec![],
             detected_pis: SecondaryMap::new(),
-            detected_bin: None,
+            detected_bin: vec![],
+            rectangular_bin: is_rectangular(&layout.bin.outer_cd),
+            hazard_filter: layout.placed_items[current_pk].hazard_filter.clone(),
             idx_counter: 0,
             loss_cache: (0, 0.0),
             loss_bound: f32::INFINITY,
@@ -132,7 +150,7 @@ impl<'a> SpecializedHazardDetector<'a> {
 
     pub fn reload(&mut self, loss_bound: f32) {
         self.detected_pis.clear();
-        self.detected_bin = None;
+        self.detected_bin.clear();
         self.idx_counter = 0;
         self.loss_cache = (0, 0.0);
         self.loss_bound = loss_bound;
@@ -142,6 +160,11 @@ impl<'a> SpecializedHazardDetector<'a> {
         self.detected_pis.values().chain(self.detected_bin.iter())
     }
 
+    /// Index at which the hazard of the bin was detected, if it was
+    pub fn detected_bin_idx(&self, haz: &HazardEntity) -> Option<usize> {
+        self.detected_bin.iter().find(|(h, _)| h == haz).map(|(_, idx)| *idx)
+    }
+
     pub fn early_terminate(&mut self, shape: &SPolygon) -> bool {
         self.loss(shape) > self.loss_bound
     }
@@ -173,12 +196,11 @@ impl<'a> SpecializedHazardDetector<'a> {
                 let weight = self.ct.get_pair_weight(self.current_pk, *other_pk);
                 loss * weight
             }
-            HazardEntity::BinExterior => {
-                let loss = quantify_collision_poly_bin(shape, self.layout.bin.outer_cd.bbox());
-                let weight = self.ct.get_bin_weight(self.current_pk);
+            bin_haz => {
+                let loss = quantify_collision_poly_bin_hazard(shape, bin_haz, &self.layout.bin);
+                let weight = self.ct.get_bin_weight(self.current_pk, bin_haz);
                 loss * weight
             }
-            _ => unimplemented!("unsupported hazard entity"),
         }
     }
 }
@@ -187,10 +209,11 @@ impl<'a> HazardDetector for SpecializedHazardDetector<'a> {
     fn contains(&self, haz: &HazardEntity) -> bool {
         match haz {
             HazardEntity::PlacedItem { pk, .. } => {
-                *pk == self.current_pk || self.detected_pis.contains_key(*pk)
+                *pk == self.current_pk || self.ignored_pks.contains(pk) || self.detected_pis.contains_key(*pk)
+            }
+            bin_haz => {
+                self.hazard_filter.as_ref().is_some_and(|f| f.is_irrelevant(bin_haz)) || self.detected_bin.iter().any(|(h, _)| h == bin_haz)
             }
-            HazardEntity::BinExterior => self.detected_bin.is_some(),
-            _ => unreachable!("unsupported hazard entity"),
         }
     }
 
@@ -200,10 +223,9 @@ impl<'a> HazardDetector for SpecializedHazardDetector<'a> {
             HazardEntity::PlacedItem { pk, .. } => {
                 self.detected_pis.insert(pk, (haz, self.idx_counter));
             }
-            HazardEntity::BinExterior => {
-                self.detected_bin = Some((HazardEntity::BinExterior, self.idx_counter))
+            bin_haz => {
+                self.detected_bin.push((bin_haz, self.idx_counter))
             }
-            _ => unreachable!("unsupported hazard entity"),
         }
         self.idx_counter += 1;
     }
@@ -217,19 +239,19 @@ impl<'a> HazardDetector for SpecializedHazardDetector<'a> {
                     self.loss_cache = (0, 0.0);
                 }
             }
-            HazardEntity::BinExterior => {
-                let (_, idx) = self.detected_bin.take().unwrap();
+            bin_haz => {
+                let pos = self.detected_bin.iter().position(|(h, _)| h == bin_haz).unwrap();
+                let (_, idx) = self.detected_bin.swap_remove(pos);
                 if idx < self.loss_cache.0 {
                     //wipe the cache if a hazard was removed that was in it
                     self.loss_cache = (0, 0.0);
                 }
             }
-            _ => unreachable!("unsupported hazard entity"),
         }
     }
 
     fn len(&self) -> usize {
-        self.detected_pis.len() + self.detected_bin.is_some() as usize
+        self.detected_pis.len() + self.detected_bin.len()
     }
 
     fn iter(&self) -> impl Iterator<Item=&HazardEntity> {
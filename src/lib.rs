@@ -10,6 +10,7 @@ pub mod sample;
 pub mod util;
 pub mod config;
 pub mod eval;
+pub mod api;
 
 pub static EPOCH: Lazy<Instant> = Lazy::new(Instant::now);
 
@@ -18,19 +19,3 @@ const FMT: Lazy<Formatter> = Lazy::new(|| {
         .scales(Scales::short())
         .precision(Precision::Significance(3))
 });
-
-
-#[cfg(feature = "live_svg")]
-pub const EXPORT_LIVE_SVG: bool = true;
-
-#[cfg(not(feature = "live_svg"))]
-pub const EXPORT_LIVE_SVG: bool = false;
-
-#[cfg(feature = "only_final_svg")]
-pub const EXPORT_ONLY_FINAL_SVG: bool = true;
-
-#[cfg(not(feature = "only_final_svg"))]
-pub const EXPORT_ONLY_FINAL_SVG: bool = false;
-
-#[cfg(all(feature = "live_svg", feature = "only_final_svg"))]
-compile_error!("The features `live_svg` and `only_final_svg` are mutually exclusive.");
\ No newline at end of file
@@ -1,20 +1,27 @@
 extern crate core;
 
 use clap::Parser as Clap;
+use log::{error, info, warn, Level};
+use sparrow::api::{run_portfolio, Sparrow, SparrowBuilder, DEFAULT_TIME_LIMIT};
+use sparrow::config::*;
+use sparrow::optimizer::checkpoint::read_checkpoint;
+use sparrow::optimizer::bin_packing::optimize_bin_packing;
+use sparrow::optimizer::knapsack::{optimize_knapsack, KnapsackContainer};
+use sparrow::optimizer::portfolio::max_concurrent_runs;
+use sparrow::optimizer::strip::StripMode;
+use sparrow::optimizer::{Budget, Target, Terminator};
+use sparrow::util::io;
+use sparrow::util::io::InstanceFile;
+use sparrow::util::io::cli::{IntermediateExport, MainCli};
+use sparrow::util::io::sink::{JsonDirSink, LiveSvgSink, SvgDirSink};
 use jagua_rs::entities::general::Instance;
 use jagua_rs::io::parser::Parser;
-use log::{info, warn, Level};
+use jagua_rs::geometry::geo_traits::Shape;
 use rand::prelude::SmallRng;
-use rand::SeedableRng;
-use sparrow::config::*;
-use sparrow::optimizer::{optimize, Terminator};
-use sparrow::util::io;
-use sparrow::util::io::cli::MainCli;
-use sparrow::util::io::layout_to_svg::s_layout_to_svg;
+use rand::{Rng, SeedableRng};
 use std::fs;
 use std::path::Path;
 use std::time::Duration;
-use sparrow::util::io::to_sp_instance;
 
 fn main() {
     fs::create_dir_all(OUTPUT_DIR).expect("could not create output directory");
@@ -25,55 +32,210 @@ fn main() {
 
     let args = MainCli::parse();
     let input_file_path = &args.input;
-    let (explore_dur, compress_dur) = match (args.global_time, args.exploration, args.compression) {
-        (Some(gt), None, None) => {
-            (Duration::from_secs(gt).mul_f32(EXPLORE_TIME_RATIO), Duration::from_secs(gt).mul_f32(COMPRESS_TIME_RATIO))
-        },
-        (None, Some(et), Some(ct)) => {
-            (Duration::from_secs(et), Duration::from_secs(ct))
-        },
-        (None, None, None) => {
-            warn!("[MAIN] No time limit specified");
-            (Duration::from_secs(600).mul_f32(EXPLORE_TIME_RATIO), Duration::from_secs(600).mul_f32(COMPRESS_TIME_RATIO))
-        },
-        _ => unreachable!("invalid cli pattern (clap should have caught this)"),
+
+    let mut config = match &args.config_file {
+        Some(config_file_path) => {
+            info!("[MAIN] loading config from: {}", config_file_path);
+            io::read_sparrow_config(Path::new(config_file_path))
+        }
+        None => DEFAULT_SPARROW_CONFIG,
     };
+    if let Some(spacing) = args.spacing {
+        config.min_item_separation = Some(spacing);
+    }
+    if let Some(margin) = args.margin {
+        config.bin_margin = Some(margin);
+    }
+    if args.vertical {
+        config.strip_mode = StripMode::Vertical;
+        info!("[MAIN] vertical strip, minimizing the height");
+    }
+    if let Some(objective) = args.open_dimension {
+        config.strip_mode = StripMode::Open { objective, max_aspect_ratio: args.max_aspect_ratio };
+        info!("[MAIN] open dimension mode: {:?}", config.strip_mode);
+    }
+
+    info!("[MAIN] system time: {}", jiff::Timestamp::now());
 
-    info!("[MAIN] Configured to explore for {}s and compress for {}s", explore_dur.as_secs(), compress_dur.as_secs());
+    let instance_file = io::read_instance_file(Path::new(&input_file_path));
 
-    let rng = match RNG_SEED {
-        Some(seed) => {
-            info!("[MAIN] using seed: {}", seed);
-            SmallRng::seed_from_u64(seed as u64)
-        },
+    if args.sheet_width.is_some() || args.container_width.is_some() || args.fill_container {
+        if !instance_file.mirrored_items.is_empty() {
+            warn!("[MAIN] mirroring is not supported for bin packing and knapsack, items {:?} are only placed unmirrored", instance_file.mirrored_items);
+        }
+        if !instance_file.groups.is_empty() {
+            //placing the members independently would break the relative placement the groups ask for
+            panic!("item groups are not supported for bin packing and knapsack, the input file defines {} groups", instance_file.groups.len());
+        }
+        run_fixed_container(&args, &instance_file, &config);
+        return;
+    }
+
+    let InstanceFile { json_instance, mirrored_items, groups, .. } = instance_file;
+    let name = json_instance.name.clone();
+
+    let result = match args.portfolio {
         None => {
-            let seed = rand::random();
-            warn!("[MAIN] no seed provided, using: {}", seed);
-            SmallRng::seed_from_u64(seed)
+            let builder = Sparrow::builder()
+                .json_instance(json_instance)
+                .groups(groups)
+                .mirrored_items(mirrored_items);
+            configure_run(&args, &config, builder, &name, format!("{OUTPUT_DIR}/sols_{}", name))
+                .build()
+                .unwrap_or_else(|err| panic!("invalid run configuration: {}", err))
+                .run()
+        }
+        Some(n_runs) => {
+            let max_runs = max_concurrent_runs(config.expl_cfg.separator_config.n_workers);
+            let n_runs = match n_runs {
+                0 => max_runs,
+                n => n,
+            };
+            if n_runs > max_runs {
+                warn!("[MAIN] {} runs with {} workers each exceed the {} physical cores", n_runs, config.expl_cfg.separator_config.n_workers, num_cpus::get_physical());
+            }
+            let base_seed = args.seed.or(config.rng_seed.map(|seed| seed as u64)).unwrap_or_else(rand::random);
+            info!("[MAIN] portfolio of {} runs, seeds derived from: {}", n_runs, base_seed);
+            let mut rng = SmallRng::seed_from_u64(base_seed);
+            let runs = (0..n_runs)
+                .map(|i| {
+                    let builder = Sparrow::builder()
+                        .json_instance(json_instance.clone())
+                        .groups(groups.clone())
+                        .mirrored_items(mirrored_items.clone());
+                    configure_run(&args, &config, builder, &name, format!("{OUTPUT_DIR}/sols_{}/run_{}", name, i))
+                        .seed(rng.random())
+                        .build()
+                        .unwrap_or_else(|err| panic!("invalid run configuration: {}", err))
+                })
+                .collect();
+            run_portfolio(runs, args.share_best)
         }
     };
 
-    info!("[MAIN] system time: {}", jiff::Timestamp::now());
-
-    let json_instance = io::read_json_instance(Path::new(&input_file_path));
+    result.export(Path::new(OUTPUT_DIR), config.draw_options);
+    result.trace.write_csv(&Path::new(OUTPUT_DIR).join(format!("trace_{}.csv", result.name)), Level::Info);
 
-    let parser = Parser::new(CDE_CONFIG, SIMPL_TOLERANCE, MIN_ITEM_SEPARATION);
-    let any_instance = parser.parse(&json_instance);
-    let instance = to_sp_instance(any_instance.as_ref()).expect("Expected SPInstance");
+    let violations = result.validate();
+    match violations.is_empty() {
+        true => info!("[MAIN] final solution respects a spacing of {:.3} and a margin of {:.3}",
+            config.min_item_separation.unwrap_or(0.0), config.bin_margin.unwrap_or(0.0)),
+        false => {
+            violations.iter().for_each(|v| error!("[MAIN] invalid solution, {}", v));
+            error!("[MAIN] final solution has {} violations", violations.len());
+        }
+    }
+}
 
-    info!("[MAIN] loaded instance {} with #{} items", json_instance.name, instance.total_item_qty());
+/// Applies the sinks, budgets, target and starting point of the command line to the builder of a run
+fn configure_run(args: &MainCli, config: &SparrowConfig, mut builder: SparrowBuilder, name: &str, intermediate_folder: String) -> SparrowBuilder {
+    builder = builder
+        .config(*config)
+        .handle_ctrlc(true);
 
-    let output_folder_path = format!("{OUTPUT_DIR}/sols_{}", json_instance.name);
+    match args.intermediate {
+        IntermediateExport::Svg => builder = builder.sink(SvgDirSink::new(intermediate_folder, config.draw_options)),
+        IntermediateExport::Json => builder = builder.sink(JsonDirSink::new(intermediate_folder, name)),
+        IntermediateExport::None => {}
+    }
+    if args.live {
+        builder = builder.sink(LiveSvgSink::new(format!("{LIVE_DIR}/.live_solution.svg"), config.draw_options));
+    }
 
-    let terminator = Terminator::new_with_ctrlc_handler();
+    match (args.global_time, args.exploration, args.compression) {
+        (Some(gt), None, None) => builder = builder.time_limit(Duration::from_secs(gt)),
+        (None, Some(et), Some(ct)) => builder = builder.phase_time_limits(Duration::from_secs(et), Duration::from_secs(ct)),
+        (None, None, None) => {}
+        _ => unreachable!("invalid cli pattern (clap should have caught this)"),
+    }
+    if let Some(n_evals) = args.eval_budget {
+        builder = builder.eval_budget(n_evals);
+    }
+    if let Some(n_iters) = args.iter_budget {
+        builder = builder.iter_budget(n_iters);
+    }
+    if let Some(seed) = args.seed {
+        builder = builder.seed(seed);
+    }
 
-    let solution = optimize(instance.clone(), rng, output_folder_path, terminator, explore_dur, compress_dur);
+    match (args.target_width, args.target_density) {
+        (Some(width), None) => builder = builder.target(Target::Width(width)),
+        (None, Some(density)) => builder = builder.target(Target::Density(density)),
+        (None, None) => {}
+        _ => unreachable!("invalid cli pattern (clap should have caught this)"),
+    }
 
-    {
-        let svg = s_layout_to_svg(&solution.layout_snapshot, &instance, DRAW_OPTIONS, "final");
-        io::write_svg(&svg, Path::new(format!("{OUTPUT_DIR}/final_{}.svg", json_instance.name).as_str()), Level::Info);
-        if cfg!(feature = "live_svg") {
-            io::write_svg(&svg, Path::new(format!("{LIVE_DIR}/.live_solution.svg").as_str()), Level::Trace);
+    match (&args.resume, &args.initial_solution) {
+        (Some(checkpoint_path), _) => {
+            info!("[MAIN] resuming from checkpoint: {}", checkpoint_path);
+            builder = builder.resume(read_checkpoint(Path::new(checkpoint_path)));
+        }
+        (None, Some(initial_solution_path)) => {
+            info!("[MAIN] loading initial solution from: {}", initial_solution_path);
+            builder = builder.initial_solution(io::read_solution(Path::new(initial_solution_path)));
         }
+        (None, None) => {}
     }
+
+    //when resuming without an explicit checkpoint path, keep updating the checkpoint that was resumed from
+    if let Some(checkpoint_path) = args.checkpoint.as_ref().or(args.resume.as_ref()) {
+        builder = builder.checkpoint(checkpoint_path, Duration::from_secs(args.checkpoint_interval));
+    }
+
+    builder
+}
+
+/// Bin packing and knapsack, both with containers of a fixed size
+fn run_fixed_container(args: &MainCli, instance_file: &InstanceFile, config: &SparrowConfig) {
+    let json_instance = &instance_file.json_instance;
+    let parser = Parser::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+    let any_instance = parser.parse(json_instance);
+    let instance = io::to_sp_instance(any_instance.as_ref()).expect("Expected SPInstance");
+
+    let seed = args.seed.or(config.rng_seed.map(|seed| seed as u64)).unwrap_or_else(rand::random);
+    info!("[MAIN] using seed: {}", seed);
+
+    let time = match (args.global_time, args.eval_budget, args.iter_budget) {
+        (Some(gt), _, _) => Some(Duration::from_secs(gt)),
+        (None, None, None) => Some(DEFAULT_TIME_LIMIT),
+        (None, _, _) => None,
+    };
+    let budget = Budget { time, evals: args.eval_budget, iterations: args.iter_budget };
+    let mut terminator = Terminator::new_with_ctrlc_handler();
+    terminator.set_budget(budget);
+
+    let rng = SmallRng::seed_from_u64(seed);
+    let container = match (args.sheet_width, args.container_width, args.fill_container) {
+        (Some(sheet_width), None, false) => {
+            let sheet_height = args.sheet_height.unwrap_or(instance.strip_height);
+            info!("[MAIN] bin packing {} items on sheets of {:.3} x {:.3}", instance.total_item_qty(), sheet_width, sheet_height);
+            let result = optimize_bin_packing(&instance, sheet_width, sheet_height, rng, &terminator, config)
+                .unwrap_or_else(|err| panic!("invalid sheet size: {}", err));
+            result.export(&instance, &json_instance.name, Path::new(OUTPUT_DIR), config.draw_options);
+            return;
+        }
+        (None, Some(width), false) => {
+            info!("[MAIN] knapsack in a container of {:.3} x {:.3}", width, instance.strip_height);
+            KnapsackContainer::Rectangle { width }
+        }
+        (None, None, true) => {
+            let json_container = instance_file.container.as_ref()
+                .unwrap_or_else(|| panic!("the input file has no Container to fill"));
+            let bin = io::parse_container(json_instance, json_container, &parser);
+            info!("[MAIN] knapsack in the container of the input file ({:.3} x {:.3})", bin.outer_cd.bbox().width(), bin.outer_cd.bbox().height());
+            KnapsackContainer::Bin(bin)
+        }
+        _ => unreachable!("invalid cli pattern (clap should have caught this)"),
+    };
+
+    let max_qtys = json_instance.items.iter()
+        .map(|item| item.demand_max.unwrap_or(item.demand) as usize)
+        .collect::<Vec<_>>();
+    //items without a value are worth their area
+    let values = instance.items().iter().zip(instance_file.item_values.iter())
+        .map(|((item, _), value)| value.unwrap_or(item.shape_cd.area()))
+        .collect::<Vec<_>>();
+    info!("[MAIN] placing up to {} items", max_qtys.iter().sum::<usize>());
+    let result = optimize_knapsack(&instance, &container, &max_qtys, &values, rng, &terminator, config);
+    result.export(&instance, &json_instance.name, Path::new(OUTPUT_DIR), config.draw_options);
 }
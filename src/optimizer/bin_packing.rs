@@ -0,0 +1,214 @@
+use crate::config::SparrowConfig;
+use crate::optimizer::lbf::{find_clear_placement, insertion_order};
+use crate::optimizer::mirror::MirrorVariants;
+use crate::optimizer::separator::Separator;
+use crate::optimizer::strip::Axis;
+use crate::optimizer::Terminator;
+use crate::sample::uniform_sampler::UniformBBoxSampler;
+use crate::util::io;
+use crate::util::io::json_solution::{compose_json_solution, JsonSolution};
+use crate::util::io::layout_to_svg::s_layout_to_svg;
+use crate::util::io::sink::NoSink;
+use crate::util::io::svg_util::SvgDrawOptions;
+use itertools::Itertools;
+use jagua_rs::entities::general::Instance;
+use jagua_rs::entities::strip_packing::{SPInstance, SPPlacement, SPProblem, SPSolution};
+use jagua_rs::geometry::geo_traits::Shape;
+use log::{info, warn, Level};
+use ordered_float::OrderedFloat;
+use rand::prelude::SmallRng;
+use rand::{RngCore, SeedableRng};
+use std::cmp::Reverse;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::time::Instant;
+
+/// Outcome of a bin packing run, one solution per sheet
+pub struct BinPackingResult {
+    pub sheets: Vec<SPSolution>,
+    pub sheet_height: f32,
+    /// Ids of the items (one per missing copy) for which no clear position was found on any sheet
+    pub unplaced: Vec<usize>,
+    /// Minimum number of sheets required to fit the total item area
+    pub lower_bound: usize,
+}
+
+impl BinPackingResult {
+    /// Single solution containing a layout per sheet
+    pub fn json_solution(&self, instance: &SPInstance, name: &str) -> JsonSolution {
+        let mut json_sols = self.sheets.iter()
+            .map(|s| compose_json_solution(s, instance, &MirrorVariants::default(), name))
+            .collect_vec();
+        let total_sheet_area = self.sheets.iter().map(|s| s.strip_width * self.sheet_height).sum::<f32>();
+
+        let mut json_sol = json_sols.pop().expect("no sheets");
+        json_sol.layouts = json_sols.into_iter().flat_map(|s| s.layouts).chain(json_sol.layouts).collect();
+        json_sol.density = instance.item_area / total_sheet_area;
+        json_sol
+    }
+
+    /// Writes every sheet to `final_{name}_{i}.svg` and all of them to `final_{name}.json` in the folder
+    pub fn export(&self, instance: &SPInstance, name: &str, folder: &Path, draw_options: SvgDrawOptions) {
+        for (i, sheet) in self.sheets.iter().enumerate() {
+            let sheet_name = format!("{name}_{i}");
+            let svg = s_layout_to_svg(&sheet.layout_snapshot, instance, &MirrorVariants::default(), draw_options, &sheet_name);
+            io::write_svg(&svg, &folder.join(format!("final_{sheet_name}.svg")), Level::Info);
+        }
+        io::write_json(&self.json_solution(instance, name), &folder.join(format!("final_{name}.json")), Level::Info);
+    }
+}
+
+/// Reasons why the items cannot be packed onto sheets
+#[derive(Debug, Clone, PartialEq)]
+pub enum BinPackingError {
+    /// The item does not fit on an empty sheet in any of its allowed rotations
+    ItemExceedsSheet { item_id: usize },
+}
+
+impl Display for BinPackingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BinPackingError::ItemExceedsSheet { item_id } => write!(f, "item {} does not fit on an empty sheet", item_id),
+        }
+    }
+}
+
+impl std::error::Error for BinPackingError {}
+
+/// Packs all items onto as few sheets as possible.
+/// Every sheet is modelled as a strip with a fixed width (`sheet_width`) and height (`sheet_height`),
+/// so the separator, collision tracker and sampling are reused per sheet.
+/// After a first fit construction, the least filled sheet is repeatedly emptied by transferring its items to the other sheets.
+pub fn optimize_bin_packing(instance: &SPInstance, sheet_width: f32, sheet_height: f32, mut rng: SmallRng, term: &Terminator, config: &SparrowConfig) -> Result<BinPackingResult, BinPackingError> {
+    let instance = &SPInstance::new(instance.items().to_vec(), sheet_height);
+    let sheet_bbox = SPProblem::new(instance.clone(), sheet_width, config.cde_config).layout.bin.outer_cd.bbox();
+    if let Some((item, _)) = instance.items().iter().find(|(item, _)| UniformBBoxSampler::new(sheet_bbox, item, sheet_bbox).is_none()) {
+        return Err(BinPackingError::ItemExceedsSheet { item_id: item.id });
+    }
+
+    let sheet_area = sheet_width * sheet_height;
+    let lower_bound = (instance.item_area / sheet_area).ceil() as usize;
+
+    let (mut sheets, unplaced) = construct_sheets(instance, sheet_width, &mut rng, config);
+    info!("[BP] constructed {} sheets (lower bound: {})", sheets.len(), lower_bound);
+    if !unplaced.is_empty() {
+        warn!("[BP] no clear position found for items {:?}, they are left unplaced", unplaced);
+    }
+
+    while !term.is_kill() && sheets.len() > lower_bound {
+        let start = Instant::now();
+        let (weakest, _) = sheets.iter().enumerate()
+            .min_by_key(|(_, s)| OrderedFloat(s.density()))
+            .unwrap();
+
+        match attempt_to_empty(instance, &mut sheets, weakest, &mut rng, term, config) {
+            true => info!("[BP] emptied sheet {}, {} sheets remaining (in {:?})", weakest, sheets.len(), start.elapsed()),
+            false => info!("[BP] failed to empty sheet {} (in {:?})", weakest, start.elapsed()),
+        }
+    }
+
+    match sheets.len() <= lower_bound {
+        true => info!("[BP] reached lower bound of {} sheets", lower_bound),
+        false => info!("[BP] budget exhausted, {} sheets (lower bound: {})", sheets.len(), lower_bound),
+    }
+
+    Ok(BinPackingResult {
+        sheets: sheets.iter().map(|s| s.save()).collect(),
+        sheet_height,
+        unplaced,
+        lower_bound,
+    })
+}
+
+/// First fit: every item is placed on the first sheet with room for it, a new sheet is opened if none has.
+/// Items for which no position is found on a new sheet either are returned as unplaced.
+fn construct_sheets(instance: &SPInstance, sheet_width: f32, rng: &mut SmallRng, config: &SparrowConfig) -> (Vec<SPProblem>, Vec<usize>) {
+    let demands = instance.items().iter().map(|(_, qty)| *qty as isize).collect_vec();
+    let mut sheets: Vec<SPProblem> = vec![];
+    let mut unplaced = vec![];
+
+    for item_id in insertion_order(instance, &demands) {
+        let placement = sheets.iter().enumerate()
+            .find_map(|(i, s)| find_clear_placement(&s.layout, instance, item_id, Axis::Horizontal, config.lbf_sample_config, rng).map(|p| (i, p)));
+
+        match placement {
+            Some((i, p)) => {
+                sheets[i].place_item(p);
+            }
+            None => {
+                let mut sheet = SPProblem::new(instance.clone(), sheet_width, config.cde_config);
+                match find_clear_placement(&sheet.layout, instance, item_id, Axis::Horizontal, config.lbf_sample_config, rng) {
+                    Some(p) => {
+                        sheet.place_item(p);
+                        sheets.push(sheet);
+                    }
+                    None => unplaced.push(item_id),
+                }
+            }
+        }
+    }
+    (sheets, unplaced)
+}
+
+/// Transfers all items of a sheet to the other sheets.
+/// Items are placed in a clear position if one exists, otherwise on the emptiest sheet, which is separated afterward.
+/// If any of the affected sheets cannot be separated, all sheets are restored.
+/// Like the attempts of the compression phase, every separation is bounded by the (shorter) strike and iteration limits of the compression config,
+/// so a single hopeless sheet does not consume the entire budget.
+fn attempt_to_empty(instance: &SPInstance, sheets: &mut Vec<SPProblem>, idx: usize, rng: &mut SmallRng, term: &Terminator, config: &SparrowConfig) -> bool {
+    let backups = sheets.iter().map(|s| s.save()).collect_vec();
+    let restore = |sheets: &mut Vec<SPProblem>| sheets.iter_mut().zip(backups.iter()).for_each(|(s, b)| s.restore(b));
+
+    let item_ids = sheets[idx].layout.placed_items().values()
+        .map(|pi| pi.item_id)
+        .sorted_by_cached_key(|id| Reverse(OrderedFloat(instance.item(*id).shape_cd.area())))
+        .collect_vec();
+
+    let mut overlapping = vec![false; sheets.len()];
+    for item_id in item_ids {
+        //prefer the fullest sheet with room for the item
+        let targets = (0..sheets.len())
+            .filter(|&i| i != idx)
+            .sorted_by_key(|&i| Reverse(OrderedFloat(sheets[i].density())))
+            .collect_vec();
+
+        let clear = targets.iter()
+            .find_map(|&i| find_clear_placement(&sheets[i].layout, instance, item_id, Axis::Horizontal, config.lbf_sample_config, rng).map(|p| (i, p)));
+
+        match clear {
+            Some((i, p)) => {
+                sheets[i].place_item(p);
+            }
+            None => {
+                //place the item at a random position on the emptiest sheet and leave it to the separator
+                let i = *targets.last().unwrap();
+                let bin = &sheets[i].layout.bin;
+                let sampler = UniformBBoxSampler::new(bin.outer_cd.bbox(), instance.item(item_id), bin.outer_cd.bbox());
+                match sampler {
+                    Some(sampler) => {
+                        sheets[i].place_item(SPPlacement { item_id, d_transf: sampler.sample(rng) });
+                        overlapping[i] = true;
+                    }
+                    None => {
+                        restore(sheets);
+                        return false;
+                    }
+                }
+            }
+        }
+    }
+
+    for i in (0..sheets.len()).filter(|&i| overlapping[i]) {
+        let mut sep = Separator::new(instance.clone(), sheets[i].clone(), SmallRng::seed_from_u64(rng.next_u64()), Box::new(NoSink), config.cmpr_cfg.separator_config);
+        let (sol, ct) = sep.separate(term);
+        sep.prob.restore(&sol);
+        sheets[i] = sep.prob;
+        if ct.get_total_loss() > 0.0 {
+            restore(sheets);
+            return false;
+        }
+    }
+
+    sheets.remove(idx);
+    true
+}
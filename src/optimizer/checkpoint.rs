@@ -0,0 +1,143 @@
+use crate::optimizer::mirror::MirrorVariants;
+use crate::optimizer::separator::Separator;
+use crate::optimizer::{Budget, ExplorationState, Phase, Terminator};
+use crate::quantify::tracker::CTWeights;
+use crate::util::io;
+use crate::util::io::json_solution::{build_sp_problem, compose_json_solution, compose_json_solution_from_layout, JsonSolution};
+use itertools::Itertools;
+use jagua_rs::collision_detection::CDEConfig;
+use jagua_rs::entities::general::PItemKey;
+use jagua_rs::entities::strip_packing::{SPInstance, SPSolution};
+use log::Level;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Snapshot of the full state of an optimization run, from which it can be resumed.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Checkpoint {
+    pub phase: Phase,
+    /// Best feasible solution found so far
+    pub best: JsonSolution,
+    /// Solution the separator was working on
+    pub current: JsonSolution,
+    /// Infeasible solutions (and their loss) of the exploration phase at the current width
+    pub solution_pool: Vec<(JsonSolution, f32)>,
+    /// Weights of the collision tracker of `current`, items are identified by their index in its placed items
+    pub ct_weights: CTWeights,
+    /// State of the separator's rng
+    pub rng: Xoshiro256PlusPlus,
+    /// State of the rngs of the separator's workers
+    pub worker_rngs: Vec<Xoshiro256PlusPlus>,
+    pub explore_remaining: Budget,
+    pub compress_remaining: Budget,
+    /// Fraction of the compression budget already consumed, needed to continue the shrink step schedule
+    pub compress_progress: f32,
+}
+
+impl Checkpoint {
+    pub fn restore_exploration_state(&self, instance: &SPInstance, mirrors: &MirrorVariants, cde_config: CDEConfig) -> ExplorationState {
+        let restore = |json_sol: &JsonSolution| build_sp_problem(json_sol, instance, mirrors, cde_config).save();
+        ExplorationState {
+            feasible_solutions: vec![restore(&self.best)],
+            solution_pool: self.solution_pool.iter()
+                .map(|(json_sol, loss)| (restore(json_sol), *loss))
+                .collect(),
+        }
+    }
+
+    /// Restores the rngs and the collision tracker weights of a separator built from `current`,
+    /// `keys` are the keys of its placed items (see [`build_sp_problem_with_keys`](crate::util::io::json_solution::build_sp_problem_with_keys))
+    pub fn restore_separator(&self, sep: &mut Separator, keys: &[Option<PItemKey>]) {
+        sep.rng = self.rng.clone();
+        assert_eq!(sep.workers.len(), self.worker_rngs.len(), "number of workers differs from the checkpoint");
+        for (worker, rng) in sep.workers.iter_mut().zip(self.worker_rngs.iter()) {
+            worker.rng = rng.clone();
+        }
+        sep.ct.import_weights(keys, &self.ct_weights);
+    }
+}
+
+/// Periodically persists a [`Checkpoint`] of the optimization run to disk.
+pub struct Checkpointer {
+    path: Option<PathBuf>,
+    interval: Duration,
+    last_save: Instant,
+    instance_name: String,
+    /// Budget of the compression phase, reported as remaining while still exploring
+    pub compress_budget: Budget,
+}
+
+impl Checkpointer {
+    pub fn new(path: PathBuf, interval: Duration, instance_name: String) -> Self {
+        Self {
+            path: Some(path),
+            interval,
+            last_save: Instant::now(),
+            instance_name,
+            compress_budget: Budget::time(Duration::ZERO),
+        }
+    }
+
+    /// Creates a checkpointer that never saves anything
+    pub fn disabled() -> Self {
+        Self {
+            path: None,
+            interval: Duration::MAX,
+            last_save: Instant::now(),
+            instance_name: String::new(),
+            compress_budget: Budget::time(Duration::ZERO),
+        }
+    }
+
+    pub fn is_due(&self) -> bool {
+        self.path.is_some() && self.last_save.elapsed() >= self.interval
+    }
+
+    pub fn save_exploration(&mut self, instance: &SPInstance, sep: &Separator, state: &ExplorationState, term: &Terminator) {
+        if !self.is_due() { return; }
+        let best = state.feasible_solutions.last().expect("no feasible solution to checkpoint");
+        let solution_pool = state.solution_pool.iter()
+            .map(|(sol, loss)| (compose_json_solution(sol, instance, &sep.mirrors, &self.instance_name), *loss))
+            .collect();
+        let (explore_remaining, compress_remaining) = (term.remaining(), self.compress_budget);
+        self.save(instance, sep, Phase::Exploration, best, solution_pool, explore_remaining, compress_remaining, 0.0);
+    }
+
+    pub fn save_compression(&mut self, instance: &SPInstance, sep: &Separator, best: &SPSolution, progress: f32, term: &Terminator) {
+        if !self.is_due() { return; }
+        let explore_remaining = Budget::time(Duration::ZERO);
+        self.save(instance, sep, Phase::Compression, best, vec![], explore_remaining, term.remaining(), progress);
+    }
+
+    fn save(&mut self, instance: &SPInstance, sep: &Separator, phase: Phase, best: &SPSolution, solution_pool: Vec<(JsonSolution, f32)>,
+            explore_remaining: Budget, compress_remaining: Budget, compress_progress: f32) {
+        let checkpoint = Checkpoint {
+            phase,
+            best: compose_json_solution(best, instance, &sep.mirrors, &self.instance_name),
+            current: compose_json_solution_from_layout(&sep.prob.layout, sep.prob.strip_width(), instance, &sep.mirrors, &self.instance_name, Instant::now()),
+            solution_pool,
+            //listed in the same order as the placed items of `current`
+            ct_weights: sep.ct.export_weights(&sep.prob.layout.placed_items().keys().collect_vec()),
+            rng: sep.rng.clone(),
+            worker_rngs: sep.workers.iter().map(|w| w.rng.clone()).collect(),
+            explore_remaining,
+            compress_remaining,
+            compress_progress,
+        };
+
+        let path = self.path.as_ref().unwrap();
+        io::write_json_atomic(&checkpoint, path, Level::Info);
+        self.last_save = Instant::now();
+    }
+}
+
+pub fn read_checkpoint(path: &Path) -> Checkpoint {
+    let file = File::open(path)
+        .unwrap_or_else(|err| panic!("could not open checkpoint file: {}, {}", path.display(), err));
+    serde_json::from_reader(BufReader::new(file))
+        .unwrap_or_else(|err| panic!("could not parse checkpoint file: {}, {}", path.display(), err))
+}
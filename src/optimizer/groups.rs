@@ -0,0 +1,208 @@
+use crate::optimizer::strip::Axis;
+use crate::util::io::json_solution::JsonPlacedItem;
+use itertools::Itertools;
+use jagua_rs::entities::general::{Instance, Layout, PItemKey};
+use jagua_rs::entities::strip_packing::SPInstance;
+use jagua_rs::geometry::geo_traits::Shape;
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::io::parser;
+use serde::{Deserialize, Serialize};
+use slotmap::SecondaryMap;
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+/// Group as defined in the instance file (`Groups`), the transformations are applied to the original shapes of the items.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct JsonItemGroup {
+    pub items: Vec<JsonPlacedItem>,
+}
+
+/// Items which are placed as a rigid unit.
+/// The first member is the anchor, all members are placed with a fixed transformation relative to it.
+/// The group rotates along with the anchor, within the allowed rotations of the anchor.
+#[derive(Debug, Clone)]
+pub struct ItemGroup {
+    /// Id of every member with its (internal) transformation relative to the anchor
+    pub members: Vec<(usize, DTransformation)>,
+}
+
+impl ItemGroup {
+    pub fn from_json(json_group: &JsonItemGroup, instance: &SPInstance) -> Self {
+        assert!(!json_group.items.is_empty(), "group without items");
+        //transformations applicable to the internal shapes of the members
+        let internal = json_group.items.iter()
+            .map(|json_pi| {
+                let item = instance.item(json_pi.index);
+                let abs_transf = DTransformation::new(
+                    json_pi.transformation.rotation.to_radians(),
+                    json_pi.transformation.translation,
+                );
+                (item.id, parser::absolute_to_internal_transform(&abs_transf, &item.shape_orig.pre_transform))
+            })
+            .collect_vec();
+
+        let inv_anchor = inverse(&internal[0].1);
+        let members = internal.iter()
+            .map(|(id, dt)| (*id, compose(&inv_anchor, dt)))
+            .collect();
+        Self { members }
+    }
+
+    pub fn anchor_id(&self) -> usize {
+        self.members[0].0
+    }
+
+    /// Transformations of all members for a transformation of the anchor
+    pub fn member_transforms<'a>(&'a self, anchor_dt: &'a DTransformation) -> impl Iterator<Item=(usize, DTransformation)> + 'a {
+        self.members.iter().map(move |(id, rel)| (*id, compose(anchor_dt, rel)))
+    }
+
+    /// Number of copies of the group which can be formed from the quantities
+    pub fn n_copies(&self, qtys: &[isize]) -> usize {
+        self.members.iter()
+            .counts_by(|(id, _)| *id)
+            .into_iter()
+            .map(|(id, n)| qtys[id].max(0) as usize / n)
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+/// Copies of the [`ItemGroup`]s present in a layout, identified by the relative transformations of their members.
+/// Every copy consists of the index of its group and the keys of the members, in the same order as in the group.
+/// Built once per layout with [`GroupInstances::from_layout`] and kept up to date as items are moved and removed.
+#[derive(Debug, Clone, Default)]
+pub struct GroupInstances {
+    pub instances: Vec<(usize, Vec<PItemKey>)>,
+    pub pk_instance_map: SecondaryMap<PItemKey, usize>,
+}
+
+impl GroupInstances {
+    pub fn from_layout(groups: &[ItemGroup], l: &Layout) -> Self {
+        let mut gi = Self::default();
+        if groups.is_empty() {
+            return gi;
+        }
+        //index the placed items by id, a member is only matched against items of its own id
+        let mut items_by_id: HashMap<usize, Vec<(PItemKey, DTransformation)>> = HashMap::new();
+        for (pk, pi) in l.placed_items().iter() {
+            items_by_id.entry(pi.item_id).or_default().push((pk, pi.d_transf));
+        }
+        let tolerances = l.placed_items().values()
+            .map(|pi| (pi.item_id, pi.shape.diameter() * 1e-4))
+            .collect::<HashMap<_, _>>();
+
+        for (group_idx, group) in groups.iter().enumerate() {
+            for &(anchor_pk, anchor_dt) in items_by_id.get(&group.anchor_id()).into_iter().flatten() {
+                if gi.pk_instance_map.contains_key(anchor_pk) {
+                    continue;
+                }
+                let mut members = vec![anchor_pk];
+                for (id, dt) in group.member_transforms(&anchor_dt).skip(1) {
+                    let tolerance = tolerances.get(&id).copied().unwrap_or(0.0);
+                    let member = items_by_id.get(&id).into_iter().flatten()
+                        .filter(|(pk, _)| !members.contains(pk) && !gi.pk_instance_map.contains_key(*pk))
+                        .find(|(_, pi_dt)| transforms_match(pi_dt, &dt, tolerance))
+                        .map(|(pk, _)| *pk);
+                    match member {
+                        Some(pk) => members.push(pk),
+                        None => break,
+                    }
+                }
+                if members.len() == group.members.len() {
+                    gi.insert(group_idx, members);
+                }
+            }
+        }
+        gi
+    }
+
+    fn insert(&mut self, group_idx: usize, members: Vec<PItemKey>) {
+        let idx = self.instances.len();
+        members.iter().for_each(|pk| { self.pk_instance_map.insert(*pk, idx); });
+        self.instances.push((group_idx, members));
+    }
+
+    /// Index of the group and members of the copy the item belongs to, if any
+    pub fn members_of(&self, pk: PItemKey) -> Option<(usize, &[PItemKey])> {
+        self.pk_instance_map.get(pk).map(|&idx| (self.instances[idx].0, self.instances[idx].1.as_slice()))
+    }
+
+    /// Replaces the keys of a copy after its members were moved
+    pub fn update(&mut self, old_pks: &[PItemKey], new_pks: Vec<PItemKey>) {
+        let idx = self.pk_instance_map[old_pks[0]];
+        old_pks.iter().for_each(|pk| { self.pk_instance_map.remove(*pk); });
+        new_pks.iter().for_each(|pk| { self.pk_instance_map.insert(*pk, idx); });
+        self.instances[idx].1 = new_pks;
+    }
+
+    /// Replaces the key of a single member after it was moved, the relative position of the copy is assumed to be kept
+    pub fn update_member(&mut self, old_pk: PItemKey, new_pk: PItemKey) {
+        if let Some(idx) = self.pk_instance_map.remove(old_pk) {
+            self.pk_instance_map.insert(new_pk, idx);
+            self.instances[idx].1.iter_mut()
+                .filter(|pk| **pk == old_pk)
+                .for_each(|pk| *pk = new_pk);
+        }
+    }
+
+    /// Registers the removal of an item from the layout, the copy it belonged to (if any) is no longer intact
+    pub fn remove(&mut self, pk: PItemKey) {
+        let Some(idx) = self.pk_instance_map.get(pk).copied() else {
+            return;
+        };
+        let (_, members) = self.instances.swap_remove(idx);
+        members.iter().for_each(|pk| { self.pk_instance_map.remove(*pk); });
+        //the last copy took the place of the removed one
+        if let Some((_, moved)) = self.instances.get(idx) {
+            moved.iter().for_each(|pk| { self.pk_instance_map.insert(*pk, idx); });
+        }
+    }
+}
+
+/// Items which lie beyond the split position along the axis.
+/// Members of a group are judged by the position of their anchor, keeping the group intact when it is shifted.
+pub fn items_beyond_split(l: &Layout, gi: &GroupInstances, axis: Axis, split_position: f32) -> Vec<PItemKey> {
+    let coord = |pk: PItemKey| {
+        let centroid = l.placed_items()[pk].shape.centroid();
+        match axis {
+            Axis::Horizontal => centroid.0,
+            Axis::Vertical => centroid.1,
+        }
+    };
+    l.placed_items().keys()
+        .filter(|pk| {
+            let reference = gi.members_of(*pk).map_or(*pk, |(_, members)| members[0]);
+            coord(reference) > split_position
+        })
+        .collect()
+}
+
+/// Transformation equivalent to first applying `b`, then `a`
+pub fn compose(a: &DTransformation, b: &DTransformation) -> DTransformation {
+    let (sin, cos) = a.rotation().sin_cos();
+    let (bx, by) = b.translation();
+    let (ax, ay) = a.translation();
+    DTransformation::new(
+        a.rotation() + b.rotation(),
+        (cos * bx - sin * by + ax, sin * bx + cos * by + ay),
+    )
+}
+
+pub fn inverse(a: &DTransformation) -> DTransformation {
+    let (sin, cos) = (-a.rotation()).sin_cos();
+    let (ax, ay) = a.translation();
+    DTransformation::new(
+        -a.rotation(),
+        (-(cos * ax - sin * ay), -(sin * ax + cos * ay)),
+    )
+}
+
+fn transforms_match(a: &DTransformation, b: &DTransformation, tolerance: f32) -> bool {
+    let d_rot = (a.rotation() - b.rotation()).rem_euclid(2.0 * PI);
+    let d_rot = d_rot.min(2.0 * PI - d_rot);
+    let (ax, ay) = a.translation();
+    let (bx, by) = b.translation();
+    d_rot < 1e-4 && (ax - bx).hypot(ay - by) <= tolerance
+}
@@ -0,0 +1,173 @@
+use crate::config::SparrowConfig;
+use crate::optimizer::lbf::{find_clear_placement, insertion_order};
+use crate::optimizer::mirror::MirrorVariants;
+use crate::optimizer::separator::Separator;
+use crate::optimizer::strip::Axis;
+use crate::optimizer::Terminator;
+use crate::sample::uniform_sampler::UniformBBoxSampler;
+use crate::util::io;
+use crate::util::io::json_solution::{compose_json_solution, JsonSolution};
+use crate::util::io::layout_to_svg::s_layout_to_svg;
+use crate::util::io::sink::NoSink;
+use crate::util::io::svg_util::SvgDrawOptions;
+use itertools::Itertools;
+use jagua_rs::collision_detection::CDEConfig;
+use jagua_rs::entities::general::{Bin, Instance, Layout};
+use jagua_rs::entities::strip_packing::{SPInstance, SPPlacement, SPProblem, SPSolution};
+use jagua_rs::geometry::geo_traits::Shape;
+use log::{info, Level};
+use ordered_float::OrderedFloat;
+use rand::prelude::{IndexedRandom, SmallRng};
+use rand::{RngCore, SeedableRng};
+use std::cmp::Reverse;
+use std::path::Path;
+
+/// Outcome of a knapsack run
+pub struct KnapsackResult {
+    pub solution: SPSolution,
+    /// Total value of the placed items
+    pub value: f32,
+    /// Ratio of the placed item area to the area of the container
+    pub density: f32,
+    /// Number of items placed in the container
+    pub n_placed: usize,
+}
+
+impl KnapsackResult {
+    pub fn json_solution(&self, instance: &SPInstance, name: &str) -> JsonSolution {
+        compose_json_solution(&self.solution, instance, &MirrorVariants::default(), name)
+    }
+
+    /// Writes the solution to `final_{name}.svg` and `final_{name}.json` in the folder
+    pub fn export(&self, instance: &SPInstance, name: &str, folder: &Path, draw_options: SvgDrawOptions) {
+        let svg = s_layout_to_svg(&self.solution.layout_snapshot, instance, &MirrorVariants::default(), draw_options, "final");
+        io::write_svg(&svg, &folder.join(format!("final_{name}.svg")), Level::Info);
+        io::write_json(&self.json_solution(instance, name), &folder.join(format!("final_{name}.json")), Level::Info);
+    }
+}
+
+/// Container filled by [`optimize_knapsack`]
+#[derive(Clone, Debug)]
+pub enum KnapsackContainer {
+    /// Rectangle of this width and the height of the instance's strip
+    Rectangle { width: f32 },
+    /// Bin of any shape, with holes and inferior quality zones (see [`parse_container`](crate::util::io::parse_container))
+    Bin(Bin),
+}
+
+impl KnapsackContainer {
+    fn empty_problem(&self, instance: &SPInstance, cde_config: CDEConfig) -> SPProblem {
+        match self {
+            KnapsackContainer::Rectangle { width } => SPProblem::new(instance.clone(), *width, cde_config),
+            KnapsackContainer::Bin(bin) => {
+                let mut prob = SPProblem::new(instance.clone(), bin.outer_cd.bbox().width(), cde_config);
+                prob.layout = Layout::new(bin.clone());
+                prob
+            }
+        }
+    }
+}
+
+/// Maximizes the value of the items placed in a container of fixed size.
+/// At most `max_qtys[id]` copies of every item are placed (`DemandMax` of the instance), each worth `values[id]`.
+///
+/// Starting from a bottom-left fill, items from the unplaced pool are repeatedly inserted and the layout separated.
+/// If the separation fails, the colliding items with the most loss for their value are dropped one by one until the layout is feasible.
+/// The result is kept if it places at least as much value as the best solution so far.
+pub fn optimize_knapsack(instance: &SPInstance, container: &KnapsackContainer, max_qtys: &[usize], values: &[f32], mut rng: SmallRng, term: &Terminator, config: &SparrowConfig) -> KnapsackResult {
+    //the problem keeps track of the missing quantities up to the maximum demand
+    let items = instance.items().iter().zip(max_qtys.iter())
+        .map(|((item, _), &max_qty)| (item.clone(), max_qty))
+        .collect_vec();
+    let instance = &SPInstance::new(items, instance.strip_height);
+
+    let mut prob = container.empty_problem(instance, config.cde_config);
+    let max_qtys_isize = max_qtys.iter().map(|&q| q as isize).collect_vec();
+    //the most valuable items for their area first
+    let fill_order = insertion_order(instance, &max_qtys_isize).into_iter()
+        .sorted_by_cached_key(|&id| Reverse(OrderedFloat(values[id] / instance.item(id).shape_cd.area())));
+    for item_id in fill_order {
+        if let Some(p) = find_clear_placement(&prob.layout, instance, item_id, Axis::Horizontal, config.lbf_sample_config, &mut rng) {
+            prob.place_item(p);
+        }
+    }
+    info!("[KS] initial fill placed {} items (value: {:.3}, {:.3}%)", prob.layout.placed_items().len(), placed_value(&prob, values), prob.density() * 100.0);
+
+    let sep_rng = SmallRng::seed_from_u64(rng.next_u64());
+    let mut sep = Separator::new(instance.clone(), prob, sep_rng, Box::new(NoSink), config.expl_cfg.separator_config);
+    let mut best = (sep.prob.save(), sep.ct.save());
+    let mut best_value = placed_value(&sep.prob, values);
+    let mut best_density = sep.prob.density();
+
+    while !term.is_kill() {
+        //only items which could still fit in the free area of the container are candidates
+        let free_area = (1.0 - best_density) * sep.prob.layout.bin.outer_cd.area();
+        let candidates = (0..instance.items().len())
+            .filter(|&id| sep.prob.missing_item_qtys[id] > 0)
+            .filter(|&id| instance.item(id).shape_cd.area() <= free_area)
+            .collect_vec();
+
+        let Some(&item_id) = candidates.choose(&mut rng) else {
+            info!("[KS] no more items fit in the free area");
+            break;
+        };
+
+        //insert the item at a random position and try to resolve the collisions
+        let item = instance.item(item_id);
+        let bin = &sep.prob.layout.bin;
+        let Some(sampler) = UniformBBoxSampler::new(bin.outer_cd.bbox(), item, bin.outer_cd.bbox()) else {
+            continue;
+        };
+        sep.place_item(SPPlacement { item_id, d_transf: sampler.sample(&mut rng) });
+        let (sol, ct) = sep.separate(term);
+        sep.rollback(&sol, Some(&ct));
+
+        if ct.get_total_loss() > 0.0 {
+            drop_colliding_items(&mut sep, values);
+        }
+
+        let value = placed_value(&sep.prob, values);
+        match value >= best_value {
+            true => {
+                if value > best_value {
+                    info!("[KS] improved to {} items (value: {:.3}, {:.3}%)", sep.prob.layout.placed_items().len(), value, sep.prob.density() * 100.0);
+                }
+                best = (sep.prob.save(), sep.ct.save());
+                best_value = value;
+                best_density = sep.prob.density();
+            }
+            false => {
+                //return to the best solution, along with the weights learned up to that point
+                sep.prob.restore(&best.0);
+                sep.ct = best.1.clone();
+            }
+        }
+    }
+
+    let (solution, _) = best;
+    let n_placed = Layout::from_snapshot(&solution.layout_snapshot).placed_items().len();
+    info!("[KS] finished with {} items placed (value: {:.3}, {:.3}%)", n_placed, best_value, best_density * 100.0);
+
+    KnapsackResult {
+        solution,
+        value: best_value,
+        density: best_density,
+        n_placed,
+    }
+}
+
+/// Removes the item with the highest loss relative to its value until no collisions remain
+fn drop_colliding_items(sep: &mut Separator, values: &[f32]) {
+    while sep.ct.get_total_loss() > 0.0 {
+        let pk = sep.prob.layout.placed_items().iter()
+            .max_by_key(|(pk, pi)| OrderedFloat(sep.ct.get_loss(*pk) / values[pi.item_id]))
+            .map(|(pk, _)| pk)
+            .unwrap();
+        info!("[KS] dropping item {}", sep.prob.layout.placed_items()[pk].item_id);
+        sep.remove_item(pk);
+    }
+}
+
+fn placed_value(prob: &SPProblem, values: &[f32]) -> f32 {
+    prob.layout.placed_items().values().map(|pi| values[pi.item_id]).sum()
+}
@@ -1,15 +1,20 @@
+use crate::config::MAX_GROUP_EXPANSIONS;
+use crate::eval::group_evaluator::GroupEvaluator;
 use crate::eval::lbf_evaluator::LBFEvaluator;
 use crate::eval::sample_eval::SampleEval;
-use crate::sample::search::{search_placement, SampleConfig};
+use crate::optimizer::groups::ItemGroup;
+use crate::optimizer::mirror::MirrorVariants;
+use crate::optimizer::strip::{fit_strip_height, resize_strip, Axis};
+use crate::sample::search::{search_placement, search_variant_placement, SampleConfig};
 use itertools::Itertools;
-use log::debug;
+use log::{debug, warn};
 use ordered_float::OrderedFloat;
 use rand::prelude::SmallRng;
 use std::cmp::Reverse;
 use std::iter;
 use std::time::Instant;
 use jagua_rs::collision_detection::CDEConfig;
-use jagua_rs::entities::general::Instance;
+use jagua_rs::entities::general::{Instance, Layout};
 use jagua_rs::entities::strip_packing::{SPInstance, SPPlacement, SPProblem};
 
 pub struct LBFBuilder {
@@ -17,6 +22,12 @@ pub struct LBFBuilder {
     pub prob: SPProblem,
     pub rng: SmallRng,
     pub sample_config: SampleConfig,
+    /// Dimension of the strip which is expanded when an item does not fit
+    pub fill_axis: Axis,
+    /// Groups of items which are placed as a single unit, before all other items
+    pub groups: Vec<ItemGroup>,
+    /// Mirrored variants of items, placed instead of their original where they fit better
+    pub mirrors: MirrorVariants,
 }
 
 impl LBFBuilder {
@@ -34,25 +45,84 @@ impl LBFBuilder {
             prob,
             rng,
             sample_config,
+            fill_axis: Axis::Horizontal,
+            groups: vec![],
+            mirrors: MirrorVariants::default(),
         }
     }
 
+    /// Builder for a vertical strip of fixed width, the height is expanded instead of the width.
+    pub fn new_vertical(
+        instance: SPInstance,
+        strip_width: f32,
+        cde_config: CDEConfig,
+        rng: SmallRng,
+        sample_config: SampleConfig,
+    ) -> Self {
+        let init_strip_height = instance.item_area / strip_width; //100% utilization
+        let instance = SPInstance::new(instance.items().to_vec(), init_strip_height);
+        let prob = SPProblem::new(instance.clone(), strip_width, cde_config);
+
+        Self {
+            instance,
+            prob,
+            rng,
+            sample_config,
+            fill_axis: Axis::Vertical,
+            groups: vec![],
+            mirrors: MirrorVariants::default(),
+        }
+    }
+
+    /// Creates a builder which continues from an existing, possibly incomplete, problem.
+    pub fn from_problem(
+        instance: SPInstance,
+        prob: SPProblem,
+        rng: SmallRng,
+        sample_config: SampleConfig,
+    ) -> Self {
+        Self {
+            instance,
+            prob,
+            rng,
+            sample_config,
+            fill_axis: Axis::Horizontal,
+            groups: vec![],
+            mirrors: MirrorVariants::default(),
+        }
+    }
+
+    /// As many copies of every group as the missing quantities allow are placed, before the remaining items
+    pub fn with_groups(mut self, groups: Vec<ItemGroup>) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    pub fn with_mirrors(mut self, mirrors: MirrorVariants) -> Self {
+        self.mirrors = mirrors;
+        self
+    }
+
+    /// Missing quantities, placed mirrored variants count towards their original
+    fn missing_qtys(&self) -> Vec<isize> {
+        self.mirrors.shared_qtys(&self.prob.missing_item_qtys)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.missing_qtys().iter().all(|&qty| qty <= 0)
+    }
+
     pub fn construct(mut self) -> Self {
         let start = Instant::now();
-        let n_items = self.instance.items().len();
-        let sorted_item_indices = (0..n_items)
-            .sorted_by_cached_key(|id| {
-                let item_shape = self.instance.items()[*id].0.shape_cd.as_ref();
-                let convex_hull_area = item_shape.surrogate().convex_hull_area;
-                let diameter = item_shape.diameter;
-                Reverse(OrderedFloat(convex_hull_area * diameter))
-            })
-            .map(|id| {
-                let missing_qty = self.prob.missing_item_qtys[id].max(0) as usize;
-                iter::repeat(id).take(missing_qty)
-            })
-            .flatten()
-            .collect_vec();
+        for group in self.groups.clone() {
+            let n_copies = group.n_copies(&self.missing_qtys());
+            debug!("[CONSTR] placing {} copies of group with anchor {}", n_copies, group.anchor_id());
+            for _ in 0..n_copies {
+                self.place_group(&group);
+            }
+        }
+
+        let sorted_item_indices = insertion_order(&self.instance, &self.missing_qtys());
 
         debug!("[CONSTR] placing items in order: {:?}",sorted_item_indices);
 
@@ -60,8 +130,14 @@ impl LBFBuilder {
             self.place_item(item_id);
         }
 
-        self.prob.fit_strip();
-        debug!("[CONSTR] placed all items in width: {:.3} (in {:?})",self.prob.strip_width(), start.elapsed());
+        match self.fill_axis {
+            Axis::Horizontal => { self.prob.fit_strip(); }
+            Axis::Vertical => {
+                fit_strip_height(&mut self.prob);
+                self.instance.strip_height = self.prob.instance.strip_height;
+            }
+        }
+        debug!("[CONSTR] placed all items in strip: {:.3} x {:.3} (in {:?})",self.prob.strip_width(), self.instance.strip_height, start.elapsed());
         self
     }
 
@@ -72,25 +148,109 @@ impl LBFBuilder {
                 debug!("[CONSTR] placing item {}/{} with id {} at [{}]",self.prob.layout.placed_items.len(),self.instance.total_item_qty(),p_opt.item_id,p_opt.d_transf);
             }
             None => {
-                debug!("[CONSTR] failed to place item with id {}, expanding strip width",item_id);
-                self.prob.change_strip_width(self.prob.strip_width() * 1.2);
+                debug!("[CONSTR] failed to place item with id {}, expanding strip {:?}",item_id,self.fill_axis);
+                self.expand_strip();
                 self.place_item(item_id);
             }
         }
     }
 
+    fn place_group(&mut self, group: &ItemGroup) {
+        for _ in 0..MAX_GROUP_EXPANSIONS {
+            match find_clear_group_placement(&self.prob.layout, &self.instance, group, self.fill_axis, self.sample_config, &mut self.rng) {
+                Some(placements) => {
+                    for p_opt in placements {
+                        self.prob.place_item(p_opt);
+                    }
+                    debug!("[CONSTR] placing group with anchor {} ({} items)",group.anchor_id(),group.members.len());
+                    return;
+                }
+                None => {
+                    debug!("[CONSTR] failed to place group with anchor {}, expanding strip {:?}",group.anchor_id(),self.fill_axis);
+                    self.expand_strip();
+                }
+            }
+        }
+        //the members are placed individually along with the other items
+        warn!("[CONSTR] group with anchor {} does not fit in the strip, placing its items separately",group.anchor_id());
+    }
+
+    fn expand_strip(&mut self) {
+        match self.fill_axis {
+            Axis::Horizontal => { self.prob.change_strip_width(self.prob.strip_width() * 1.2); }
+            Axis::Vertical => {
+                let new_height = self.instance.strip_height * 1.2;
+                resize_strip(&mut self.prob, self.prob.strip_width(), new_height);
+                self.instance.strip_height = new_height;
+            }
+        }
+    }
+
     fn find_placement(&mut self, item_id: usize) -> Option<SPPlacement> {
-        let layout = &self.prob.layout;
-        let item = self.instance.item(item_id);
-        let evaluator = LBFEvaluator::new(layout, item);
+        let item_ids = self.mirrors.variants(item_id).collect_vec();
+        find_clear_variant_placement(&self.prob.layout, &self.instance, &item_ids, self.fill_axis, self.sample_config, &mut self.rng)
+    }
+}
 
-        let (best_sample, _) = search_placement(layout, item, None, evaluator, self.sample_config, &mut self.rng);
+/// Ids of the missing items (repeated for their quantity), largest items first
+pub fn insertion_order(instance: &SPInstance, missing_item_qtys: &[isize]) -> Vec<usize> {
+    (0..instance.items().len())
+        .sorted_by_cached_key(|id| {
+            let item_shape = instance.items()[*id].0.shape_cd.as_ref();
+            let convex_hull_area = item_shape.surrogate().convex_hull_area;
+            let diameter = item_shape.diameter;
+            Reverse(OrderedFloat(convex_hull_area * diameter))
+        })
+        .map(|id| {
+            let missing_qty = missing_item_qtys[id].max(0) as usize;
+            iter::repeat(id).take(missing_qty)
+        })
+        .flatten()
+        .collect_vec()
+}
 
-        match best_sample {
-            Some((d_transf, SampleEval::Clear { .. })) => {
-                Some(SPPlacement { item_id, d_transf })
-            }
-            _ => None
+/// Searches for a position of the item in the layout where it does not collide with anything.
+/// Positions at the start of the `fill_axis` are preferred (left-bottom for horizontal, bottom-left for vertical).
+pub fn find_clear_placement(layout: &Layout, instance: &SPInstance, item_id: usize, fill_axis: Axis, sample_config: SampleConfig, rng: &mut SmallRng) -> Option<SPPlacement> {
+    find_clear_variant_placement(layout, instance, &[item_id], fill_axis, sample_config, rng)
+}
+
+/// Counterpart of [`find_clear_placement`] for variants of an item (e.g. the item and its mirrored counterpart), the best placement of any of them is returned
+pub fn find_clear_variant_placement(layout: &Layout, instance: &SPInstance, item_ids: &[usize], fill_axis: Axis, sample_config: SampleConfig, rng: &mut SmallRng) -> Option<SPPlacement> {
+    let variants = item_ids.iter()
+        .map(|&id| {
+            let item = instance.item(id);
+            (item, LBFEvaluator::new(layout, item, fill_axis))
+        })
+        .collect_vec();
+
+    let (best_sample, _) = search_variant_placement(layout, variants, None, sample_config, rng);
+
+    match best_sample {
+        Some((item_id, d_transf, SampleEval::Clear { .. })) => {
+            Some(SPPlacement { item_id, d_transf })
         }
+        _ => None
     }
 }
+
+/// Counterpart of [`find_clear_placement`] for a group, sampled as a single unit through the transformation of its anchor
+pub fn find_clear_group_placement(layout: &Layout, instance: &SPInstance, group: &ItemGroup, fill_axis: Axis, sample_config: SampleConfig, rng: &mut SmallRng) -> Option<Vec<SPPlacement>> {
+    let evaluator = GroupEvaluator::new(
+        group.members.iter()
+            .map(|(id, rel_dt)| (LBFEvaluator::new(layout, instance.item(*id), fill_axis), *rel_dt))
+            .collect()
+    );
+    let anchor = instance.item(group.anchor_id());
+
+    let (best_sample, _) = search_placement(layout, anchor, None, evaluator, sample_config, rng);
+
+    match best_sample {
+        Some((anchor_dt, SampleEval::Clear { .. })) => {
+            Some(group.member_transforms(&anchor_dt)
+                .map(|(item_id, d_transf)| SPPlacement { item_id, d_transf })
+                .collect())
+        }
+        _ => None
+    }
+}
\ No newline at end of file
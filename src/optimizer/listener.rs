@@ -0,0 +1,68 @@
+use crate::optimizer::Phase;
+use crate::optimizer::worker::SepStats;
+use crate::optimizer::OptimizationResult;
+use jagua_rs::entities::strip_packing::SPSolution;
+
+/// Observer of the events of an optimization run, all hooks default to doing nothing.
+/// Hooks are invoked on the thread running the optimization, keep them cheap.
+pub trait OptimizationListener: Send {
+    /// A phase of the optimization starts
+    fn on_phase_change(&mut self, _phase: Phase) {}
+
+    /// A new best feasible solution was found
+    fn on_solution(&mut self, _solution: &SPSolution, _phase: Phase) {}
+
+    /// The strip width of the layout being separated changed
+    fn on_width_change(&mut self, _old_width: f32, _new_width: f32, _phase: Phase) {}
+
+    /// The strip height of the layout being separated changed (vertical and open strips only)
+    fn on_height_change(&mut self, _old_height: f32, _new_height: f32, _phase: Phase) {}
+
+    /// A call to [`Separator::separate`](crate::optimizer::separator::Separator::separate) finished,
+    /// `loss` is the loss of the best layout it found (0.0 if it is feasible)
+    fn on_separation(&mut self, _stats: &SepStats, _loss: f32) {}
+
+    /// The optimization run terminated
+    fn on_termination(&mut self, _result: &OptimizationResult) {}
+}
+
+/// Listener ignoring all events
+pub struct NoListener;
+
+impl OptimizationListener for NoListener {}
+
+/// Forwards all events to each of the listeners, in order
+impl OptimizationListener for Vec<Box<dyn OptimizationListener>> {
+    fn on_phase_change(&mut self, phase: Phase) {
+        self.iter_mut().for_each(|l| l.on_phase_change(phase));
+    }
+
+    fn on_solution(&mut self, solution: &SPSolution, phase: Phase) {
+        self.iter_mut().for_each(|l| l.on_solution(solution, phase));
+    }
+
+    fn on_width_change(&mut self, old_width: f32, new_width: f32, phase: Phase) {
+        self.iter_mut().for_each(|l| l.on_width_change(old_width, new_width, phase));
+    }
+
+    fn on_height_change(&mut self, old_height: f32, new_height: f32, phase: Phase) {
+        self.iter_mut().for_each(|l| l.on_height_change(old_height, new_height, phase));
+    }
+
+    fn on_separation(&mut self, stats: &SepStats, loss: f32) {
+        self.iter_mut().for_each(|l| l.on_separation(stats, loss));
+    }
+
+    fn on_termination(&mut self, result: &OptimizationResult) {
+        self.iter_mut().for_each(|l| l.on_termination(result));
+    }
+}
+
+/// Adapter turning a closure into a listener which is only interested in new solutions
+pub struct SolutionListener<F: FnMut(&SPSolution) + Send>(pub F);
+
+impl<F: FnMut(&SPSolution) + Send> OptimizationListener for SolutionListener<F> {
+    fn on_solution(&mut self, solution: &SPSolution, _phase: Phase) {
+        (self.0)(solution)
+    }
+}
\ No newline at end of file
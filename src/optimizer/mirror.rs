@@ -0,0 +1,115 @@
+use itertools::Itertools;
+use jagua_rs::io::json_instance::JsonInstance;
+use log::info;
+use serde_json::Value;
+use std::iter;
+
+/// Mirrored (reflected) counterparts of the items which allow mirroring (`AllowMirror` in the instance file).
+/// jagua-rs transformations are limited to rotation and translation, so the mirror image of such an item is added to the instance as a separate variant.
+/// The variants are appended after the original items with a demand of 0, the demand is shared with the original item:
+/// an original and its variant together are placed as many times as the original is demanded.
+#[derive(Debug, Clone, Default)]
+pub struct MirrorVariants {
+    /// Id of the counterpart of every item (original <-> variant), if it allows mirroring
+    counterparts: Vec<Option<usize>>,
+    /// Number of items in the instance file, all ids from here on are variants
+    n_originals: usize,
+}
+
+impl MirrorVariants {
+    /// Appends a mirrored variant for each of the `mirrored_items` to the instance.
+    /// The shape of a variant is the shape of the original reflected across the y-axis (x -> -x).
+    pub fn new(json_instance: &JsonInstance, mirrored_items: &[usize]) -> (JsonInstance, Self) {
+        let n_originals = json_instance.items.len();
+        let mut value = serde_json::to_value(json_instance)
+            .unwrap_or_else(|err| panic!("could not serialize instance: {}", err));
+        let items = value["Items"].as_array_mut().expect("instance without items");
+
+        let mirrored_items = mirrored_items.iter().copied().sorted().dedup().collect_vec();
+        let mut counterparts = vec![None; n_originals];
+        for &id in mirrored_items.iter() {
+            assert!(id < n_originals, "item {} cannot be mirrored, the instance only has {} items", id, n_originals);
+            let mut variant = items[id].clone();
+            reflect_points(&mut variant["Shape"]["Data"]);
+            variant["Demand"] = Value::from(0);
+            if let Some(variant) = variant.as_object_mut() {
+                variant.remove("DemandMax");
+                variant.remove("AllowMirror");
+            }
+            counterparts[id] = Some(counterparts.len());
+            counterparts.push(Some(id));
+            items.push(variant);
+        }
+        if !mirrored_items.is_empty() {
+            info!("[MIRR] items {:?} can also be placed mirrored", mirrored_items);
+        }
+
+        let json_instance = serde_json::from_value(value)
+            .unwrap_or_else(|err| panic!("could not add mirrored items to instance: {}", err));
+        (json_instance, Self { counterparts, n_originals })
+    }
+
+    /// The mirrored variant of an original item, or the original of a variant
+    pub fn counterpart(&self, item_id: usize) -> Option<usize> {
+        self.counterparts.get(item_id).copied().flatten()
+    }
+
+    pub fn is_variant(&self, item_id: usize) -> bool {
+        !self.counterparts.is_empty() && item_id >= self.n_originals
+    }
+
+    /// Id of the item in the instance file
+    pub fn original(&self, item_id: usize) -> usize {
+        match self.is_variant(item_id) {
+            true => self.counterparts[item_id].unwrap(),
+            false => item_id,
+        }
+    }
+
+    /// Id of the item to place for an item of the instance file, placed mirrored or not
+    pub fn variant(&self, original_id: usize, mirrored: bool) -> Option<usize> {
+        match mirrored {
+            true => self.counterpart(original_id),
+            false => Some(original_id),
+        }
+    }
+
+    /// The item itself, followed by its counterpart (if any)
+    pub fn variants(&self, item_id: usize) -> impl Iterator<Item=usize> {
+        iter::once(item_id).chain(self.counterpart(item_id))
+    }
+
+    /// Quantities in which the variants are added to their original, leaving 0 for the variants themselves
+    pub fn shared_qtys(&self, qtys: &[isize]) -> Vec<isize> {
+        let mut shared = qtys.to_vec();
+        for id in (0..qtys.len()).filter(|id| self.is_variant(*id)) {
+            shared[self.original(id)] += qtys[id];
+            shared[id] = 0;
+        }
+        shared
+    }
+}
+
+/// Reflects all points (`[x, y]`) across the y-axis.
+/// The order of the points is reversed to keep the orientation of the polygons.
+fn reflect_points(value: &mut Value) {
+    if is_point(value) {
+        let x = value[0].as_f64().unwrap();
+        value[0] = Value::from(-x);
+        return;
+    }
+    match value {
+        Value::Array(elements) => {
+            elements.iter_mut().for_each(reflect_points);
+            if elements.iter().all(is_point) {
+                elements.reverse();
+            }
+        }
+        Value::Object(fields) => fields.values_mut().for_each(reflect_points),
+        _ => {}
+    }
+}
+
+fn is_point(value: &Value) -> bool {
+    value.as_array().is_some_and(|p| p.len() == 2 && p.iter().all(Value::is_number))
+}
@@ -1,75 +1,316 @@
 use crate::config::*;
+use crate::optimizer::checkpoint::{Checkpoint, Checkpointer};
+use crate::optimizer::groups::ItemGroup;
 use crate::optimizer::lbf::LBFBuilder;
+use crate::optimizer::mirror::MirrorVariants;
+use crate::optimizer::listener::OptimizationListener;
+use crate::optimizer::portfolio::SharedBest;
 use crate::optimizer::separator::Separator;
-pub use crate::optimizer::terminator::Terminator;
+use crate::optimizer::strip::{solution_height, Axis, StripMode};
+use crate::optimizer::trace::Trace;
+pub use crate::optimizer::terminator::{Budget, Target, Terminator};
+pub use crate::optimizer::worker::SepStats;
+use crate::util::io::json_solution::build_sp_problem_with_keys;
+use crate::util::io::sink::{ExportKind, SolutionSink};
 use crate::FMT;
-use log::info;
+use log::{info, warn};
 use ordered_float::OrderedFloat;
 use rand::prelude::{IteratorRandom, SmallRng};
 use rand::{Rng, RngCore, SeedableRng};
 use rand_distr::Distribution;
 use rand_distr::Normal;
+use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant};
 use float_cmp::approx_eq;
 use jagua_rs::entities::general::Instance;
-use jagua_rs::entities::strip_packing::{SPInstance, SPSolution};
+use jagua_rs::entities::strip_packing::{SPInstance, SPProblem, SPSolution};
 use jagua_rs::geometry::geo_traits::Shape;
 
+pub mod bin_packing;
+pub mod checkpoint;
+pub mod groups;
+pub mod knapsack;
 pub mod lbf;
+pub mod listener;
+pub mod mirror;
+pub mod portfolio;
 pub mod separator;
+pub mod strip;
 mod worker;
 pub mod terminator;
+pub mod trace;
+
+/// Phases of an optimization run, in order
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Exploration,
+    Compression,
+}
+
+/// Determines the solution an optimization run starts from
+pub enum InitialState {
+    /// Construct an initial solution with the [`LBFBuilder`]
+    Construct,
+    /// Warm start from an existing (possibly incomplete) solution
+    Solution(SPProblem),
+    /// Resume a previous run from a checkpoint
+    Checkpoint(Checkpoint),
+}
+
+/// Everything an optimization run needs besides the instance, the rng and the terminator.
+/// Built by the [`SparrowBuilder`](crate::api::SparrowBuilder).
+pub struct RunOptions {
+    pub config: SparrowConfig,
+    /// Budgets of both phases, the remaining budgets of a checkpoint take precedence
+    pub explore_budget: Budget,
+    pub compress_budget: Budget,
+    /// Groups of items which are placed as a single unit
+    pub groups: Vec<ItemGroup>,
+    /// Mirrored variants of the items which allow mirroring, part of the instance
+    pub mirrors: MirrorVariants,
+    pub init: InitialState,
+    pub checkpointer: Checkpointer,
+    pub sink: Box<dyn SolutionSink>,
+    pub listener: Box<dyn OptimizationListener>,
+    /// Set if the run is part of a portfolio sharing its best solutions
+    pub shared_best: Option<SharedBest>,
+}
+
+/// Outcome of an optimization run
+pub struct OptimizationResult {
+    pub solution: SPSolution,
+    /// Whether the solution meets the target of the terminator (false if no target was set)
+    pub target_met: bool,
+    pub stats: RunStats,
+    pub trace: Trace,
+}
+
+/// Statistics of an optimization run, evaluations and iterations are summed over all workers
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct RunStats {
+    pub explore_time: Duration,
+    pub compress_time: Duration,
+    pub explore_evals: usize,
+    pub compress_evals: usize,
+    pub explore_iters: usize,
+    pub compress_iters: usize,
+    /// Density of the solution the run started from
+    pub initial_density: f32,
+    /// Density of the best solution at the end of the exploration phase
+    pub explore_density: f32,
+    pub final_density: f32,
+}
 
 // All high-level heuristic logic
-pub fn optimize(instance: SPInstance, mut rng: SmallRng, output_folder_path: String, mut terminator: Terminator, explore_dur: Duration, compress_dur: Duration) -> SPSolution {
+/// If the terminator has a [`Target`], the run returns as soon as a feasible solution meeting it is found.
+/// Intermediate and final solutions are passed to the sink.
+/// If the run is part of a portfolio, it exchanges its best solutions through `shared_best`.
+/// Items with a mirrored variant in the instance (`mirrors`) can be placed either way.
+pub fn optimize(instance: SPInstance, mut rng: SmallRng, mut terminator: Terminator, options: RunOptions) -> OptimizationResult {
+    let RunOptions { config, explore_budget, compress_budget, groups, mirrors, init, mut checkpointer, sink, listener, shared_best } = options;
     let mut next_rng = || SmallRng::seed_from_u64(rng.next_u64());
-    let builder = LBFBuilder::new(instance.clone(), CDE_CONFIG, next_rng(), LBF_SAMPLE_CONFIG).construct();
+    let (start_prob, checkpoint) = match init {
+        InitialState::Construct => {
+            let builder = match config.strip_mode {
+                //the strip height of the instance is the fixed width of a vertical strip
+                StripMode::Vertical => LBFBuilder::new_vertical(instance.clone(), instance.strip_height, config.cde_config, next_rng(), config.lbf_sample_config),
+                _ => LBFBuilder::new(instance.clone(), config.cde_config, next_rng(), config.lbf_sample_config),
+            };
+            (builder.with_groups(groups.to_vec()).with_mirrors(mirrors.clone()).construct().prob, None)
+        }
+        InitialState::Solution(prob) => {
+            info!("[OPT] warm starting from initial solution with width: {:.3} ({:.3}%)", prob.strip_width(), prob.density() * 100.0);
+            let builder = LBFBuilder::from_problem(instance.clone(), prob, next_rng(), config.lbf_sample_config)
+                .with_groups(groups.to_vec())
+                .with_mirrors(mirrors.clone());
+            match builder.is_complete() {
+                true => (builder.prob, None),
+                false => {
+                    warn!("[OPT] initial solution is incomplete, placing the missing items");
+                    (builder.construct().prob, None)
+                }
+            }
+        }
+        InitialState::Checkpoint(cp) => {
+            info!("[OPT] resuming {:?} phase from checkpoint (best: {:.3}%)", cp.phase, cp.best.density * 100.0);
+            //the weights of the checkpoint refer to the placed items of `current` by their index
+            let (prob, keys) = build_sp_problem_with_keys(&cp.current, &instance, &mirrors, config.cde_config);
+            (prob, Some((cp, keys)))
+        }
+    };
 
-    terminator.set_timeout_from_now(explore_dur);
-    let mut expl_separator = Separator::new(builder.instance, builder.prob, next_rng(), output_folder_path.clone(), 0, SEP_CFG_EXPLORE);
-    let solutions = exploration_phase(&instance, &mut expl_separator, &terminator);
+    // when resuming, the remaining budgets of the checkpoint take precedence
+    let (explore_budget, compress_budget) = match &checkpoint {
+        Some((cp, _)) => (cp.explore_remaining, cp.compress_remaining),
+        None => (explore_budget, compress_budget),
+    };
+    checkpointer.compress_budget = compress_budget;
+
+    terminator.set_budget(explore_budget);
+    let explore_start = Instant::now();
+    let mut expl_separator = Separator::new(instance.clone(), start_prob, next_rng(), sink, config.expl_cfg.separator_config);
+    expl_separator.listener = listener;
+    expl_separator.strip_mode = config.strip_mode;
+    expl_separator.set_groups(groups.to_vec());
+    expl_separator.mirrors = mirrors.clone();
+    expl_separator.shared_best = shared_best.clone();
+    let initial_density = expl_separator.prob.density();
+    let expl_state = match &checkpoint {
+        Some((cp, keys)) => {
+            if cp.phase == Phase::Exploration {
+                cp.restore_separator(&mut expl_separator, keys);
+            }
+            cp.restore_exploration_state(&instance, &mirrors, config.cde_config)
+        }
+        None => ExplorationState::new(&expl_separator),
+    };
+    let solutions = exploration_phase_from(&instance, &mut expl_separator, &terminator, &config.expl_cfg, expl_state, &mut checkpointer);
     let final_explore_sol = solutions.last().unwrap().clone();
+    let (explore_time, explore_evals, explore_iters) = (explore_start.elapsed(), terminator.n_evals(), terminator.n_iters());
+    expl_separator.tracer.end_phase(explore_evals);
+
+    terminator.set_budget(compress_budget).reset_ctrlc();
+    let compress_start = Instant::now();
+    let mut cmpr_separator = Separator::new(expl_separator.instance, expl_separator.prob, next_rng(), expl_separator.sink, config.cmpr_cfg.separator_config);
+    cmpr_separator.listener = expl_separator.listener;
+    cmpr_separator.tracer = expl_separator.tracer;
+    cmpr_separator.strip_mode = config.strip_mode;
+    cmpr_separator.set_groups(groups);
+    cmpr_separator.mirrors = mirrors;
+    cmpr_separator.shared_best = shared_best;
+    let compress_progress = match &checkpoint {
+        Some((cp, keys)) if cp.phase == Phase::Compression => {
+            //exploration was skipped, so the layout (and its keys) is still the one of the checkpoint
+            cp.restore_separator(&mut cmpr_separator, keys);
+            cp.compress_progress
+        }
+        _ => 0.0,
+    };
+    let cmpr_sol = compression_phase_from(&instance, &mut cmpr_separator, &final_explore_sol, &terminator, &config.cmpr_cfg, compress_progress, &mut checkpointer);
+
+    let cmpr_length = config.strip_mode.strip_length(cmpr_sol.strip_width, solution_height(&cmpr_sol));
+    let target_met = terminator.target.map_or(false, |t| t.is_met(cmpr_length, cmpr_sol.density(&instance)));
+    if let Some(target) = terminator.target {
+        match target_met {
+            true => info!("[OPT] target {:?} met with length: {:.3} ({:.3}%)", target, cmpr_length, cmpr_sol.density(&instance) * 100.0),
+            false => warn!("[OPT] target {:?} not met, best length: {:.3} ({:.3}%)", target, cmpr_length, cmpr_sol.density(&instance) * 100.0),
+        }
+    }
+
+    let stats = RunStats {
+        explore_time,
+        compress_time: compress_start.elapsed(),
+        explore_evals,
+        compress_evals: terminator.n_evals(),
+        explore_iters,
+        compress_iters: terminator.n_iters(),
+        initial_density,
+        explore_density: final_explore_sol.density(&instance),
+        final_density: cmpr_sol.density(&instance),
+    };
 
-    terminator.set_timeout_from_now(compress_dur).reset_ctrlc();
-    let mut cmpr_separator = Separator::new(expl_separator.instance, expl_separator.prob, next_rng(), expl_separator.output_svg_folder, expl_separator.svg_counter, SEP_CFG_COMPRESS);
-    let cmpr_sol = compression_phase(&instance, &mut cmpr_separator, &final_explore_sol, &terminator);
+    let result = OptimizationResult {
+        solution: cmpr_sol,
+        target_met,
+        stats,
+        trace: std::mem::take(&mut cmpr_separator.tracer.trace),
+    };
+    cmpr_separator.export(Some(&result.solution), "final", ExportKind::Final);
+    cmpr_separator.listener.on_termination(&result);
 
-    cmpr_sol
+    result
 }
 
-pub fn exploration_phase(instance: &SPInstance, sep: &mut Separator, term: &Terminator) -> Vec<SPSolution> {
-    let mut current_width = sep.prob.strip_width();
-    let mut best_width = current_width;
+/// State of the exploration phase which persists across separation attempts
+pub struct ExplorationState {
+    /// All feasible solutions found so far, the last one is the best
+    pub feasible_solutions: Vec<SPSolution>,
+    /// Infeasible solutions at the current width, sorted by loss
+    pub solution_pool: Vec<(SPSolution, f32)>,
+}
 
-    let mut feasible_solutions = vec![sep.prob.save()];
+impl ExplorationState {
+    pub fn new(sep: &Separator) -> Self {
+        Self {
+            feasible_solutions: vec![sep.prob.save()],
+            solution_pool: vec![],
+        }
+    }
+}
+
+pub fn exploration_phase(instance: &SPInstance, sep: &mut Separator, term: &Terminator, config: &ExplorationConfig) -> Vec<SPSolution> {
+    let state = ExplorationState::new(sep);
+    exploration_phase_from(instance, sep, term, config, state, &mut Checkpointer::disabled())
+}
 
-    sep.export_svg(None, "init", false);
-    info!("[EXPL] starting optimization with initial width: {:.3} ({:.3}%)",current_width,sep.prob.density() * 100.0);
+pub fn exploration_phase_from(instance: &SPInstance, sep: &mut Separator, term: &Terminator, config: &ExplorationConfig, mut state: ExplorationState, ckpt: &mut Checkpointer) -> Vec<SPSolution> {
+    let mut best_obj = sep.strip_mode.solution_objective(state.feasible_solutions.last().unwrap());
 
-    let mut solution_pool: Vec<(SPSolution, f32)> = vec![];
+    sep.listener.on_phase_change(Phase::Exploration);
+    sep.export(None, "init", ExportKind::Intermediate);
+    info!("[EXPL] starting optimization with initial strip: {:.3} x {:.3} ({:.3}%)",sep.prob.strip_width(),sep.strip_height(),sep.prob.density() * 100.0);
+
+    //the starting solution might already meet the target
+    let best = state.feasible_solutions.last().unwrap();
+    let best_length = sep.strip_mode.strip_length(best.strip_width, solution_height(best));
+    term.register_feasible(best_length, best.density(instance));
+    sep.tracer.record(Phase::Exploration, term, best_length, best.density(instance), 0.0, true);
+    if let Some(shared_best) = &sep.shared_best {
+        shared_best.offer(best_obj, best);
+    }
 
     while !term.is_kill() {
+        ckpt.save_exploration(instance, sep, &state, term);
+
         let local_best = sep.separate(&term);
         let total_loss = local_best.1.get_total_loss();
+        let local_best_length = sep.strip_mode.strip_length(local_best.0.strip_width, solution_height(&local_best.0));
 
         if total_loss == 0.0 {
             //layout is successfully separated
-            if current_width < best_width {
-                info!("[EXPL] new best at width: {:.3} ({:.3}%)",current_width,sep.prob.density() * 100.0);
-                best_width = current_width;
-                feasible_solutions.push(local_best.0.clone());
-                sep.export_svg(Some(local_best.0.clone()), "expl_f", false);
+            let (current_width, current_height) = (sep.prob.strip_width(), sep.strip_height());
+            term.register_feasible(sep.strip_mode.strip_length(current_width, current_height), sep.prob.density());
+            let current_obj = sep.strip_mode.objective(current_width, current_height);
+            sep.tracer.record(Phase::Exploration, term, local_best_length, local_best.0.density(instance), 0.0, current_obj < best_obj);
+            if current_obj < best_obj {
+                info!("[EXPL] new best at strip: {:.3} x {:.3} ({:.3}%)",current_width,current_height,sep.prob.density() * 100.0);
+                best_obj = current_obj;
+                state.feasible_solutions.push(local_best.0.clone());
+                if let Some(shared_best) = &sep.shared_best {
+                    shared_best.offer(current_obj, &local_best.0);
+                }
+                sep.listener.on_solution(&local_best.0, Phase::Exploration);
+                sep.export(Some(&local_best.0), "expl_f", ExportKind::Intermediate);
             }
-            let next_width = current_width * (1.0 - EXPLORE_SHRINK_STEP);
-            info!("[EXPL] shrinking width by {}%: {:.3} -> {:.3}", EXPLORE_SHRINK_STEP * 100.0, current_width, next_width);
-            sep.change_strip_width(next_width, None);
-            current_width = next_width;
-            solution_pool.clear();
+            let axis = sep.strip_mode.shrink_axis(current_width, current_height, config.shrink_step, &mut sep.rng);
+            let current_dim = sep.strip_dim(axis);
+            let next_dim = current_dim * (1.0 - config.shrink_step);
+            info!("[EXPL] shrinking {:?} by {}%: {:.3} -> {:.3}", axis, config.shrink_step * 100.0, current_dim, next_dim);
+            sep.change_strip_dim(axis, next_dim, None);
+            match axis {
+                Axis::Horizontal => sep.listener.on_width_change(current_dim, next_dim, Phase::Exploration),
+                Axis::Vertical => sep.listener.on_height_change(current_dim, next_dim, Phase::Exploration),
+            }
+            state.solution_pool.clear();
         } else {
             info!("[EXPL] layout separation unsuccessful, exporting min loss solution");
-            sep.export_svg(Some(local_best.0.clone()), "expl_nf", false);
+            sep.tracer.record(Phase::Exploration, term, local_best_length, local_best.0.density(instance), total_loss, false);
+            sep.export(Some(&local_best.0), "expl_nf", ExportKind::Intermediate);
+
+            //another run of the portfolio is ahead, restart from its solution instead of the solution pool
+            if let Some((shared_obj, shared_sol)) = sep.shared_best.as_ref().and_then(|s| s.better_than(best_obj)) {
+                let shared_length = sep.strip_mode.strip_length(shared_sol.strip_width, solution_height(&shared_sol));
+                info!("[EXPL] restarting from the best solution of the portfolio: {:.3} x {:.3} ({:.3}%)", shared_sol.strip_width, solution_height(&shared_sol), shared_sol.density(instance) * 100.0);
+                sep.tracer.record(Phase::Exploration, term, shared_length, shared_sol.density(instance), 0.0, true);
+                sep.restore_strip(&shared_sol);
+                best_obj = shared_obj;
+                state.feasible_solutions.push(shared_sol);
+                state.solution_pool.clear();
+                continue;
+            }
 
             //layout was not successfully separated, add to local bests
+            let solution_pool = &mut state.solution_pool;
             match solution_pool.binary_search_by(|(_, o)| o.partial_cmp(&total_loss).unwrap()) {
                 Ok(idx) | Err(idx) => solution_pool.insert(idx, (local_best.0.clone(), total_loss)),
             }
@@ -77,7 +318,7 @@ pub fn exploration_phase(instance: &SPInstance, sep: &mut Separator, term: &Term
             //restore to a random solution from the tabu list, better solutions have more chance to be selected
             let selected_sol = {
                 //sample a value in range [0.0, 1.0[ from a normal distribution
-                let distr = Normal::new(0.0, EXPLORE_SOL_DISTR_STDDEV).unwrap();
+                let distr = Normal::new(0.0, config.solution_pool_distribution_stddev).unwrap();
                 let sample = distr.sample(&mut sep.rng).abs().min(0.999);
                 //map it to the range of the solution pool
                 let selected_idx = (sample * solution_pool.len() as f32) as usize;
@@ -93,33 +334,54 @@ pub fn exploration_phase(instance: &SPInstance, sep: &mut Separator, term: &Term
         }
     }
 
-    info!("[EXPL] time limit reached, best solution found: {:.3} ({:.3}%)",best_width,feasible_solutions.last().unwrap().density(instance) * 100.0);
+    match term.is_target_met() {
+        true => info!("[EXPL] target met, stopping exploration"),
+        false => info!("[EXPL] budget exhausted"),
+    }
+    let best = state.feasible_solutions.last().unwrap();
+    info!("[EXPL] best solution found: {:.3} x {:.3} ({:.3}%)",best.strip_width,solution_height(best),best.density(instance) * 100.0);
 
-    feasible_solutions
+    state.feasible_solutions
 }
 
-pub fn compression_phase(instance: &SPInstance, sep: &mut Separator, init: &SPSolution, term: &Terminator) -> SPSolution {
+pub fn compression_phase(instance: &SPInstance, sep: &mut Separator, init: &SPSolution, term: &Terminator, config: &CompressionConfig) -> SPSolution {
+    compression_phase_from(instance, sep, init, term, config, 0.0, &mut Checkpointer::disabled())
+}
+
+/// Compression phase, `prev_progress` is the fraction of the compression budget already consumed in a previous (resumed) run.
+pub fn compression_phase_from(instance: &SPInstance, sep: &mut Separator, init: &SPSolution, term: &Terminator, config: &CompressionConfig, prev_progress: f32, ckpt: &mut Checkpointer) -> SPSolution {
+    sep.listener.on_phase_change(Phase::Compression);
     let mut best = init.clone();
-    let start = Instant::now();
-    let end = term.timeout.expect("compression running without timeout");
+    let progress = || prev_progress + (1.0 - prev_progress) * term.consumed_fraction();
     let step_size = || -> f32 {
-        //map the range [COMPRESS_SHRINK_RANGE.0, COMPRESS_SHRINK_RANGE.1] to timeout
-        let range = COMPRESS_SHRINK_RANGE.1 - COMPRESS_SHRINK_RANGE.0;
-        let elapsed = start.elapsed();
-        let remaining = end.duration_since(Instant::now());
-        let ratio = elapsed.as_secs_f32() / (elapsed + remaining).as_secs_f32();
-        COMPRESS_SHRINK_RANGE.0 + ratio * range
+        //map the range [shrink_range.0, shrink_range.1] to the consumed budget
+        let (shrink_start, shrink_end) = config.shrink_range;
+        let range = shrink_end - shrink_start;
+        shrink_start + progress() * range
     };
     while !term.is_kill() {
+        ckpt.save_compression(instance, sep, &best, progress(), term);
+
         let step = step_size();
         info!("[CMPR] attempting {:.3}%", step * 100.0);
-        match attempt_to_compress(sep, &best, step, &term) {
-            Some(compacted_sol) => {
-                info!("[CMPR] compressed to {:.3} ({:.3}%)", compacted_sol.strip_width, compacted_sol.density(instance) * 100.0);
-                sep.export_svg(Some(compacted_sol.clone()), "cmpr", false);
-                best = compacted_sol;
+        let (compacted_sol, total_loss) = attempt_to_compress(sep, &best, step, &term);
+        let compacted_length = sep.strip_mode.strip_length(compacted_sol.strip_width, solution_height(&compacted_sol));
+        sep.tracer.record(Phase::Compression, term, compacted_length, compacted_sol.density(instance), total_loss, total_loss == 0.0);
+        if total_loss == 0.0 {
+            term.register_feasible(compacted_length, compacted_sol.density(instance));
+            info!("[CMPR] compressed to {:.3} x {:.3} ({:.3}%)", compacted_sol.strip_width, solution_height(&compacted_sol), compacted_sol.density(instance) * 100.0);
+            sep.export(Some(&compacted_sol), "cmpr", ExportKind::Intermediate);
+            sep.listener.on_solution(&compacted_sol, Phase::Compression);
+            if let Some(shared_best) = &sep.shared_best {
+                shared_best.offer(sep.strip_mode.solution_objective(&compacted_sol), &compacted_sol);
             }
-            None => {}
+            best = compacted_sol;
+        } else if let Some((_, shared_sol)) = sep.shared_best.as_ref().and_then(|s| s.better_than(sep.strip_mode.solution_objective(&best))) {
+            //another run of the portfolio is ahead, continue compressing its solution
+            let shared_length = sep.strip_mode.strip_length(shared_sol.strip_width, solution_height(&shared_sol));
+            info!("[CMPR] continuing from the best solution of the portfolio: {:.3} x {:.3} ({:.3}%)", shared_sol.strip_width, solution_height(&shared_sol), shared_sol.density(instance) * 100.0);
+            sep.tracer.record(Phase::Compression, term, shared_length, shared_sol.density(instance), 0.0, true);
+            best = shared_sol;
         }
     }
     info!("[CMPR] finished compression, improved from {:.3}% to {:.3}% (+{:.3}%)", init.density(instance) * 100.0, best.density(instance) * 100.0, (best.density(instance) - init.density(instance)) * 100.0);
@@ -127,22 +389,25 @@ pub fn compression_phase(instance: &SPInstance, sep: &mut Separator, init: &SPSo
 }
 
 
-fn attempt_to_compress(sep: &mut Separator, init: &SPSolution, r_shrink: f32, term: &Terminator) -> Option<SPSolution> {
-    //restore to the initial solution and width
-    sep.change_strip_width(init.strip_width, None);
-    sep.rollback(&init, None);
+/// Returns the best layout found at the shrunk strip and its total loss, it is feasible if the loss is 0.0
+fn attempt_to_compress(sep: &mut Separator, init: &SPSolution, r_shrink: f32, term: &Terminator) -> (SPSolution, f32) {
+    //restore to the initial solution and dimensions
+    sep.restore_strip(init);
 
     //shrink the bin at a random position
-    let new_width = init.strip_width * (1.0 - r_shrink);
-    let split_pos = sep.rng.random_range(0.0..sep.prob.strip_width());
-    sep.change_strip_width(new_width, Some(split_pos));
+    let axis = sep.strip_mode.shrink_axis(init.strip_width, solution_height(init), r_shrink, &mut sep.rng);
+    let init_dim = sep.strip_dim(axis);
+    let new_dim = init_dim * (1.0 - r_shrink);
+    let split_pos = sep.rng.random_range(0.0..init_dim);
+    sep.change_strip_dim(axis, new_dim, Some(split_pos));
+    match axis {
+        Axis::Horizontal => sep.listener.on_width_change(init_dim, new_dim, Phase::Compression),
+        Axis::Vertical => sep.listener.on_height_change(init_dim, new_dim, Phase::Compression),
+    }
 
-    //try to separate layout, if all collisions are eliminated, return the solution
+    //try to separate layout, all collisions are eliminated if the loss is 0.0
     let (compacted_sol, ot) = sep.separate(term);
-    match ot.get_total_loss() == 0.0 {
-        true => Some(compacted_sol),
-        false => None,
-    }
+    (compacted_sol, ot.get_total_loss())
 }
 
 fn swap_large_pair_of_items(sep: &mut Separator) {
@@ -155,21 +420,31 @@ fn swap_large_pair_of_items(sep: &mut Separator) {
 
     let layout = &sep.prob.layout;
 
+    //Members of a group are never swapped, as it would break up their group
+    let group_instances = &sep.group_instances;
+    let ungrouped_items = || layout.placed_items.iter()
+        .filter(|(pk, _)| !group_instances.pk_instance_map.contains_key(*pk));
+
     //Choose a first item with a large enough convex hull
-    let (pk1, pi1) = layout.placed_items.iter()
+    let Some((pk1, pi1)) = ungrouped_items()
         .filter(|(_, pi)| pi.shape.surrogate().convex_hull_area > large_area_ch_area_cutoff)
         .choose(&mut sep.rng)
-        .unwrap();
+    else {
+        return;
+    };
 
     //Choose a second item with a large enough convex hull and different enough from the first.
     //If no such item is found, choose a random one.
-    let (pk2, pi2) = layout.placed_items.iter()
+    let Some((pk2, pi2)) = ungrouped_items()
         .filter(|(_, pi)| !approx_eq!(f32, pi.shape.area(),pi1.shape.area(), epsilon = pi1.shape.area() * 0.1))
         .filter(|(_, pi)| pi.shape.surrogate().convex_hull_area > large_area_ch_area_cutoff)
         .choose(&mut sep.rng)
-        .unwrap_or(layout.placed_items.iter()
+        .or(ungrouped_items()
             .filter(|(pk2, _)| *pk2 != pk1)
-            .choose(&mut sep.rng).unwrap());
+            .choose(&mut sep.rng))
+    else {
+        return;
+    };
 
     let dt1 = pi1.d_transf;
     let dt2 = pi2.d_transf;
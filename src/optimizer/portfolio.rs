@@ -0,0 +1,40 @@
+use jagua_rs::entities::strip_packing::SPSolution;
+use std::sync::{Arc, Mutex};
+
+/// Best feasible solution across all runs of a portfolio.
+/// Runs offer every new best solution, and restart from the shared one once it is better than their own.
+/// All runs must optimize the same instance (with the same configuration), so solutions can be exchanged between them.
+#[derive(Clone, Default)]
+pub struct SharedBest {
+    best: Arc<Mutex<Option<(f32, SPSolution)>>>,
+}
+
+impl SharedBest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the shared solution if `objective` is lower, returns true if it was replaced
+    pub fn offer(&self, objective: f32, solution: &SPSolution) -> bool {
+        let mut best = self.best.lock().unwrap();
+        match best.as_ref().is_none_or(|(best_obj, _)| objective < *best_obj) {
+            true => {
+                *best = Some((objective, solution.clone()));
+                true
+            }
+            false => false,
+        }
+    }
+
+    /// The shared solution and its objective, if it is better than `objective`
+    pub fn better_than(&self, objective: f32) -> Option<(f32, SPSolution)> {
+        self.best.lock().unwrap().as_ref()
+            .filter(|(best_obj, _)| *best_obj < objective)
+            .cloned()
+    }
+}
+
+/// Number of runs which fit on the machine at once, each using `n_workers` separator workers
+pub fn max_concurrent_runs(n_workers: usize) -> usize {
+    (num_cpus::get_physical() / n_workers.max(1)).max(1)
+}
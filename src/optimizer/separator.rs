@@ -1,12 +1,17 @@
-use crate::config::{DRAW_OPTIONS, LIVE_DIR};
+use crate::optimizer::groups::{items_beyond_split, GroupInstances, ItemGroup};
+use crate::optimizer::mirror::MirrorVariants;
 use crate::optimizer::worker::{SepStats, SeparatorWorker};
+use crate::optimizer::listener::{NoListener, OptimizationListener};
+use crate::optimizer::strip::{resize_strip, solution_height, Axis, StripMode};
+use crate::optimizer::portfolio::SharedBest;
+use crate::optimizer::trace::Tracer;
 use crate::optimizer::Terminator;
-use crate::quantify::tracker::{CTSnapshot, CollisionTracker};
+use crate::quantify::tracker::{CTSnapshot, CollisionTracker, WeightConfig};
 use crate::sample::search::SampleConfig;
 use crate::util::assertions::tracker_matches_layout;
-use crate::util::io;
-use crate::util::io::layout_to_svg::{layout_to_svg, s_layout_to_svg};
-use crate::{EXPORT_LIVE_SVG, EXPORT_ONLY_FINAL_SVG, FMT};
+use crate::util::io::sink::{ExportKind, SolutionSink};
+use crate::config::TRACE_INTERVAL;
+use crate::FMT;
 use itertools::Itertools;
 use jagua_rs::entities::general::PItemKey;
 use jagua_rs::entities::strip_packing::{SPInstance, SPPlacement, SPProblem, SPSolution};
@@ -15,42 +20,60 @@ use log::{debug, log, Level};
 use ordered_float::OrderedFloat;
 use rand::rngs::SmallRng;
 use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
 use rayon::iter::IntoParallelRefMutIterator;
 use rayon::iter::ParallelIterator;
 use rayon::ThreadPool;
-use std::path::Path;
+use serde::{Deserialize, Serialize};
 use std::time::Instant;
 use jagua_rs::geometry::geo_traits::Shape;
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct SeparatorConfig {
     pub iter_no_imprv_limit: usize,
     pub strike_limit: usize,
     pub n_workers: usize,
     pub log_level: Level,
+    pub weight_config: WeightConfig,
     pub sample_config: SampleConfig,
 }
 
 pub struct Separator {
     pub instance: SPInstance,
-    pub rng: SmallRng,
+    /// Unlike a [`SmallRng`], the state of a xoshiro rng can be serialized into a [`Checkpoint`](crate::optimizer::checkpoint::Checkpoint)
+    pub rng: Xoshiro256PlusPlus,
     pub prob: SPProblem,
     pub ct: CollisionTracker,
     pub workers: Vec<SeparatorWorker>,
-    pub svg_counter: usize,
-    pub output_svg_folder: String,
+    pub sink: Box<dyn SolutionSink>,
     pub config: SeparatorConfig,
+    pub listener: Box<dyn OptimizationListener>,
+    pub tracer: Tracer,
+    pub strip_mode: StripMode,
+    /// Groups of items which are moved as a single unit, set with [`Separator::set_groups`]
+    pub groups: Vec<ItemGroup>,
+    /// Copies of the groups in the layout, kept up to date as items are moved, placed and removed
+    pub group_instances: GroupInstances,
+    /// Mirrored variants of items, which replace their original (or vice versa) when moved
+    pub mirrors: MirrorVariants,
+    /// Best solution of the portfolio this run is part of, if any
+    pub shared_best: Option<SharedBest>,
     pub pool: ThreadPool,
 }
 
 impl Separator {
-    pub fn new(instance: SPInstance, prob: SPProblem, mut rng: SmallRng, output_svg_folder: String, svg_counter: usize, config: SeparatorConfig) -> Self {
+    pub fn new(mut instance: SPInstance, prob: SPProblem, mut rng: SmallRng, sink: Box<dyn SolutionSink>, config: SeparatorConfig) -> Self {
+        //the strip height of a vertical strip is set by the problem
+        instance.strip_height = prob.instance.strip_height;
+        let mut rng = Xoshiro256PlusPlus::from_rng(&mut rng);
         let ct = CollisionTracker::new(&prob.layout);
         let workers = (0..config.n_workers).map(|_|
             SeparatorWorker {
                 instance: instance.clone(),
                 prob: prob.clone(),
                 ct: ct.clone(),
-                rng: SmallRng::seed_from_u64(rng.random()),
+                group_instances: GroupInstances::default(),
+                rng: Xoshiro256PlusPlus::seed_from_u64(rng.random()),
                 sample_config: config.sample_config.clone(),
             }).collect();
         let pool = rayon::ThreadPoolBuilder::new()
@@ -62,15 +85,28 @@ impl Separator {
             rng,
             ct,
             workers,
-            svg_counter,
-            output_svg_folder,
+            sink,
             config,
+            listener: Box::new(NoListener),
+            tracer: Tracer::new(TRACE_INTERVAL),
+            strip_mode: StripMode::default(),
+            groups: vec![],
+            group_instances: GroupInstances::default(),
+            mirrors: MirrorVariants::default(),
+            shared_best: None,
             pool,
         }
     }
 
+    /// Sets the groups of items and finds their copies in the current layout
+    pub fn set_groups(&mut self, groups: Vec<ItemGroup>) {
+        self.group_instances = GroupInstances::from_layout(&groups, &self.prob.layout);
+        self.groups = groups;
+    }
+
     pub fn separate(&mut self, term: &Terminator) -> (SPSolution, CTSnapshot) {
         let mut min_loss_sol = (self.prob.save(), self.ct.save());
+        let mut min_loss_gi = self.group_instances.clone();
         let mut min_loss = self.ct.get_total_loss();
         log!(self.config.log_level,"[SEP] separating at width: {:.3} and loss: {} ", self.prob.strip_width(), FMT.fmt2(min_loss));
 
@@ -85,12 +121,16 @@ impl Separator {
             let initial_strike_loss = self.ct.get_total_loss();
             debug!("[SEP] [s:{n_strikes},i:{n_iter}]     init_l: {}",FMT.fmt2(initial_strike_loss));
 
-            while n_iter_no_improvement < self.config.iter_no_imprv_limit {
+            while n_iter_no_improvement < self.config.iter_no_imprv_limit && !term.is_kill() {
                 let (loss_before, w_loss_before) = (
                     self.ct.get_total_loss(),
                     self.ct.get_total_weighted_loss(),
                 );
-                sep_stats += self.move_colliding_items();
+                let move_stats = self.move_colliding_items();
+                //evaluations are counted on the master thread, keeping evaluation budgets deterministic
+                term.register_evals(move_stats.total_evals);
+                term.register_iteration();
+                sep_stats += move_stats;
                 let (loss, w_loss) = (
                     self.ct.get_total_loss(),
                     self.ct.get_total_weighted_loss(),
@@ -103,22 +143,24 @@ impl Separator {
                     //layout is successfully separated
                     log!(self.config.log_level,"[SEP] [s:{n_strikes},i:{n_iter}] (S)  min_l: {}",FMT.fmt2(loss));
                     min_loss_sol = (self.prob.save(), self.ct.save());
+                    min_loss_gi = self.group_instances.clone();
                     break 'outer;
                 } else if loss < min_loss {
                     //layout is not separated, but absolute loss is better than before
                     log!(self.config.log_level,"[SEP] [s:{n_strikes},i:{n_iter}] (*) min_l: {}",FMT.fmt2(loss));
-                    self.export_svg(None, "i", true);
+                    self.export(None, "i", ExportKind::Live);
                     if loss < min_loss * 0.98 {
                         //only reset the iter_no_improvement counter if the loss improved significantly
                         n_iter_no_improvement = 0;
                     }
                     min_loss_sol = (self.prob.save(), self.ct.save());
+                    min_loss_gi = self.group_instances.clone();
                     min_loss = loss;
                 } else {
                     n_iter_no_improvement += 1;
                 }
 
-                self.ct.increment_weights();
+                self.ct.increment_weights(&self.config.weight_config);
                 n_iter += 1;
             }
 
@@ -127,7 +169,10 @@ impl Separator {
             } else {
                 n_strikes = 0;
             }
-            self.rollback(&min_loss_sol.0, Some(&min_loss_sol.1));
+            //restore the best layout, along with the copies of the groups recorded for it
+            self.prob.restore(&min_loss_sol.0);
+            self.ct.restore_but_keep_weights(&min_loss_sol.1, &self.prob.layout);
+            self.group_instances = min_loss_gi.clone();
         }
         let secs = start.elapsed().as_secs_f32();
         log!(self.config.log_level, "[SEP] finished, evals/s: {}, evals/move: {}, moves/s: {}, iter/s: {}, #workers: {}, total {:.3}s",
@@ -138,6 +183,7 @@ impl Separator {
             self.workers.len(),
             FMT.fmt2(secs),
         );
+        self.listener.on_separation(&sep_stats, min_loss_sol.1.get_total_loss());
 
         (min_loss_sol.0, min_loss_sol.1)
     }
@@ -149,9 +195,9 @@ impl Separator {
         let sep_report = self.pool.install(|| {
             self.workers.par_iter_mut().map(|worker| {
                 // Sync the workers with the master
-                worker.load(&master_sol, &self.ct);
+                worker.load(&master_sol, &self.ct, &self.group_instances);
                 // Let them modify
-                worker.separate()
+                worker.separate(&self.groups, &self.mirrors)
             }).sum()
         });
 
@@ -160,18 +206,20 @@ impl Separator {
         // Check which worker has the lowest total weighted loss
         let best_opt = self.workers.iter_mut()
             .min_by_key(|opt| OrderedFloat(opt.ct.get_total_weighted_loss()))
-            .map(|opt| (opt.prob.save(), &opt.ct))
+            .map(|opt| (opt.prob.save(), &opt.ct, &opt.group_instances))
             .unwrap();
 
         // Sync the master with the best optimizer
         self.prob.restore(&best_opt.0);
         self.ct = best_opt.1.clone();
+        self.group_instances = best_opt.2.clone();
 
         sep_report
     }
 
     pub fn rollback(&mut self, sol: &SPSolution, ots: Option<&CTSnapshot>) {
         debug_assert!(sol.strip_width == self.prob.strip_width());
+        debug_assert!(solution_height(sol) == self.strip_height());
         self.prob.restore(sol);
 
         match ots {
@@ -184,6 +232,8 @@ impl Separator {
                 self.ct = CollisionTracker::new(&self.prob.layout);
             }
         }
+        //the keys of the restored layout differ from the current ones
+        self.group_instances = GroupInstances::from_layout(&self.groups, &self.prob.layout);
     }
 
     pub fn move_item(&mut self, pk: PItemKey, d_transf: DTransformation) -> PItemKey {
@@ -201,6 +251,7 @@ impl Separator {
         let new_pk = self.prob.place_item(SPPlacement{d_transf,item_id});
 
         self.ct.register_item_move(&self.prob.layout, pk, new_pk);
+        self.group_instances.update_member(pk, new_pk);
 
         let new_loss = self.ct.get_loss(new_pk);
         let new_weighted_loss = self.ct.get_weighted_loss(new_pk);
@@ -213,15 +264,28 @@ impl Separator {
         new_pk
     }
 
+    /// Adds an item to the layout, the weights of the collision tracker are kept
+    pub fn place_item(&mut self, placement: SPPlacement) -> PItemKey {
+        let pk = self.prob.place_item(placement);
+        self.ct.register_item_insert(&self.prob.layout, pk);
+        pk
+    }
+
+    /// Removes an item from the layout, the weights of the collision tracker are kept
+    pub fn remove_item(&mut self, pk: PItemKey) {
+        self.prob.remove_item(pk, true);
+        self.ct.register_item_removal(&self.prob.layout, pk);
+        self.group_instances.remove(pk);
+    }
+
     pub fn change_strip_width(&mut self, new_width: f32, split_position: Option<f32>) {
         //if no split position is provided, use the center of the strip
         let split_position = split_position.unwrap_or(self.prob.strip_width() / 2.0);
         let delta = new_width - self.prob.strip_width();
 
         //shift all items right of the split position
-        let items_to_shift = self.prob.layout.placed_items().iter()
-            .filter(|(_, pi)| pi.shape.centroid().0 > split_position)
-            .map(|(k, pi)| (k, pi.d_transf))
+        let items_to_shift = items_beyond_split(&self.prob.layout, &self.group_instances, Axis::Horizontal, split_position).into_iter()
+            .map(|pk| (pk, self.prob.layout.placed_items()[pk].d_transf))
             .collect_vec();
 
         for (pik, dtransf) in items_to_shift {
@@ -230,53 +294,89 @@ impl Separator {
             self.move_item(pik, new_transf.decompose());
         }
 
-        self.prob.change_strip_width(new_width);
+        self.resize_strip(new_width, self.instance.strip_height);
+        debug!("[SEP] changed strip width to {:.3}", new_width);
+    }
+
+    pub fn strip_height(&self) -> f32 {
+        self.prob.layout.bin.outer_cd.bbox().height()
+    }
+
+    /// Counterpart of [`Separator::change_strip_width`], shifting all items above the split position
+    pub fn change_strip_height(&mut self, new_height: f32, split_position: Option<f32>) {
+        //if no split position is provided, use the center of the strip
+        let split_position = split_position.unwrap_or(self.strip_height() / 2.0);
+        let delta = new_height - self.strip_height();
+
+        //shift all items above the split position
+        let items_to_shift = items_beyond_split(&self.prob.layout, &self.group_instances, Axis::Vertical, split_position).into_iter()
+            .map(|pk| (pk, self.prob.layout.placed_items()[pk].d_transf))
+            .collect_vec();
+
+        for (pik, dtransf) in items_to_shift {
+            let existing_transf = dtransf.compose();
+            let new_transf = existing_transf.translate((0.0, delta));
+            self.move_item(pik, new_transf.decompose());
+        }
+
+        self.resize_strip(self.prob.strip_width(), new_height);
+        debug!("[SEP] changed strip height to {:.3}", new_height);
+    }
+
+    /// Resizes the strip without moving any items.
+    /// The instance of the separator, the one of its problem and the ones of the workers all keep the same strip height.
+    pub fn resize_strip(&mut self, width: f32, height: f32) {
+        resize_strip(&mut self.prob, width, height);
+        self.instance.strip_height = height;
 
         //rebuild the collision tracker
         self.ct = CollisionTracker::new(&self.prob.layout);
+        self.rebuild_workers();
+    }
+
+    pub fn strip_dim(&self, axis: Axis) -> f32 {
+        match axis {
+            Axis::Horizontal => self.prob.strip_width(),
+            Axis::Vertical => self.strip_height(),
+        }
+    }
+
+    pub fn change_strip_dim(&mut self, axis: Axis, new_dim: f32, split_position: Option<f32>) {
+        match axis {
+            Axis::Horizontal => self.change_strip_width(new_dim, split_position),
+            Axis::Vertical => self.change_strip_height(new_dim, split_position),
+        }
+    }
+
+    /// Restores the strip dimensions and layout of a solution
+    pub fn restore_strip(&mut self, sol: &SPSolution) {
+        //the items are restored along with the layout, no need to shift them
+        self.resize_strip(sol.strip_width, solution_height(sol));
+        self.rollback(sol, None);
+    }
 
-        //rebuild the workers
+    fn rebuild_workers(&mut self) {
         self.workers.iter_mut().for_each(|opt| {
             *opt = SeparatorWorker {
                 instance: self.instance.clone(),
                 prob: self.prob.clone(),
                 ct: self.ct.clone(),
-                rng: SmallRng::seed_from_u64(self.rng.random()),
+                group_instances: self.group_instances.clone(),
+                rng: Xoshiro256PlusPlus::seed_from_u64(self.rng.random()),
                 sample_config: self.config.sample_config.clone(),
             };
         });
-        debug!("[SEP] changed strip width to {:.3}", new_width);
     }
 
-    pub fn export_svg(&mut self, solution: Option<SPSolution>, suffix: &str, only_live: bool) {
-        if !EXPORT_ONLY_FINAL_SVG {
-            if self.svg_counter == 0 {
-                std::fs::create_dir_all(&self.output_svg_folder).unwrap();
-                //remove all svg files from the directory. ONLY SVG FILES
-                for file in std::fs::read_dir(&self.output_svg_folder).unwrap().flatten() {
-                    if file.path().extension().unwrap_or_default() == "svg" {
-                        std::fs::remove_file(file.path()).unwrap();
-                    }
-                }
-            }
-
-            let file_name = format!("{}_{:.3}_{suffix}", self.svg_counter, self.prob.strip_width());
-            let svg = match solution {
-                Some(sol) => s_layout_to_svg(&sol.layout_snapshot, &self.instance, DRAW_OPTIONS, file_name.as_str()),
-                None => layout_to_svg(&self.prob.layout, &self.instance, DRAW_OPTIONS, file_name.as_str()),
-            };
-
-            if EXPORT_LIVE_SVG {
-                if !Path::new(LIVE_DIR).exists() {
-                    std::fs::create_dir_all(LIVE_DIR).unwrap();
+    /// Passes a solution (or the current layout if `None`) to the sink, if it accepts solutions of this kind
+    pub fn export(&mut self, solution: Option<&SPSolution>, label: &str, kind: ExportKind) {
+        if self.sink.accepts(kind) {
+            match solution {
+                Some(sol) => self.sink.export(sol, &self.instance, &self.mirrors, label, kind, self.config.log_level),
+                None => {
+                    let sol = self.prob.save();
+                    self.sink.export(&sol, &self.instance, &self.mirrors, label, kind, self.config.log_level)
                 }
-                io::write_svg(&svg, Path::new(&*format!("{}/.live_solution.svg", LIVE_DIR)), Level::Trace);
-            }
-
-            if !only_live {
-                let file_path = &*format!("{}/{}.svg", &self.output_svg_folder, file_name);
-                io::write_svg(&svg, Path::new(file_path), self.config.log_level);
-                self.svg_counter += 1;
             }
         }
     }
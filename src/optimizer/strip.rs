@@ -0,0 +1,119 @@
+use float_cmp::approx_eq;
+use jagua_rs::entities::strip_packing::{SPProblem, SPSolution};
+use jagua_rs::geometry::geo_traits::Shape;
+use rand::Rng;
+use rand::prelude::IndexedRandom;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// Dimension of the strip
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    /// Width of the strip
+    Horizontal,
+    /// Height of the strip
+    Vertical,
+}
+
+/// Which dimensions of the strip are open and what is minimized
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum StripMode {
+    /// Fixed height, the width is minimized
+    #[default]
+    Horizontal,
+    /// Fixed width (the strip height of the instance), the height is minimized.
+    /// Warm starts and checkpoints are not supported, as they assume the strip height of the instance.
+    Vertical,
+    /// Both dimensions are open, the area or perimeter of the strip is minimized.
+    /// The ratio between the longest and shortest side is kept below `max_aspect_ratio` (if any).
+    /// Warm starts and checkpoints are not supported, as they assume the strip height of the instance.
+    Open { objective: OpenObjective, max_aspect_ratio: Option<f32> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+pub enum OpenObjective {
+    Area,
+    Perimeter,
+}
+
+impl StripMode {
+    /// Value to be minimized for a strip of these dimensions
+    pub fn objective(&self, width: f32, height: f32) -> f32 {
+        match self {
+            StripMode::Horizontal => width,
+            StripMode::Vertical => height,
+            StripMode::Open { objective: OpenObjective::Area, .. } => width * height,
+            StripMode::Open { objective: OpenObjective::Perimeter, .. } => 2.0 * (width + height),
+        }
+    }
+
+    /// Length of the open dimension of the strip, compared against [`Target::Width`](crate::optimizer::Target::Width)
+    pub fn strip_length(&self, width: f32, height: f32) -> f32 {
+        match self {
+            StripMode::Vertical => height,
+            _ => width,
+        }
+    }
+
+    /// Dimension along which the constructor fills the strip
+    pub fn fill_axis(&self) -> Axis {
+        match self {
+            StripMode::Vertical => Axis::Vertical,
+            _ => Axis::Horizontal,
+        }
+    }
+
+    pub fn solution_objective(&self, solution: &SPSolution) -> f32 {
+        self.objective(solution.strip_width, solution_height(solution))
+    }
+
+    /// Selects the dimension of the strip to shrink by the fraction `r_shrink`.
+    pub fn shrink_axis(&self, width: f32, height: f32, r_shrink: f32, rng: &mut impl Rng) -> Axis {
+        match self {
+            StripMode::Horizontal => Axis::Horizontal,
+            StripMode::Vertical => Axis::Vertical,
+            StripMode::Open { objective, max_aspect_ratio } => {
+                let max_ratio = max_aspect_ratio.unwrap_or(f32::INFINITY);
+                //shrinking a dimension is only allowed if the aspect ratio remains within bounds
+                let allowed = [Axis::Horizontal, Axis::Vertical].into_iter()
+                    .filter(|axis| match axis {
+                        Axis::Horizontal => height / (width * (1.0 - r_shrink)) <= max_ratio,
+                        Axis::Vertical => width / (height * (1.0 - r_shrink)) <= max_ratio,
+                    })
+                    .collect::<Vec<_>>();
+
+                match (allowed.as_slice(), objective) {
+                    //neither is allowed, shrink the longest side to restore the aspect ratio
+                    ([], _) => if width >= height { Axis::Horizontal } else { Axis::Vertical },
+                    ([axis], _) => *axis,
+                    //the longest side yields the largest decrease in perimeter
+                    (_, OpenObjective::Perimeter) => if width >= height { Axis::Horizontal } else { Axis::Vertical },
+                    //both decrease the area equally
+                    (_, OpenObjective::Area) => *allowed.choose(rng).unwrap(),
+                }
+            }
+        }
+    }
+}
+
+/// Height of the strip of a solution
+pub fn solution_height(solution: &SPSolution) -> f32 {
+    solution.layout_snapshot.bin.outer_cd.bbox().height()
+}
+
+/// Resizes the strip of `prob` in both dimensions, the placed items are left untouched.
+/// [`SPProblem::change_strip_width`] rebuilds the bin with the strip height of the instance of `prob`, so the height is changed there first.
+/// Other copies of the instance are not updated, see [`Separator::resize_strip`](crate::optimizer::separator::Separator::resize_strip).
+pub fn resize_strip(prob: &mut SPProblem, width: f32, height: f32) {
+    prob.instance.strip_height = height;
+    prob.change_strip_width(width);
+    debug_assert!(approx_eq!(f32, prob.layout.bin.outer_cd.bbox().height(), height, epsilon = 1e-4), "bin not rebuilt at strip height {height}");
+}
+
+/// Counterpart of [`SPProblem::fit_strip`] for the height: shrinks the strip to the highest placed item
+pub fn fit_strip_height(prob: &mut SPProblem) {
+    let max_y = prob.layout.placed_items().values()
+        .map(|pi| pi.shape.bbox().y_max)
+        .fold(0.0, f32::max);
+    resize_strip(prob, prob.strip_width(), max_y);
+}
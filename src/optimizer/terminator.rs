@@ -1,12 +1,75 @@
 use log::warn;
-use std::sync::atomic::{AtomicBool, Ordering};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+/// Decides when the optimization should stop, on whichever criterion is reached first:
+/// timeout, evaluation or iteration budget, target solution or Ctrl-C.
 #[derive(Debug, Clone)]
 pub struct Terminator {
     pub timeout: Option<Instant>,
     pub ctrlc: Arc<AtomicBool>,
+    /// Maximum number of sample evaluations
+    pub eval_budget: Option<usize>,
+    /// Number of sample evaluations registered since the budget was last set
+    pub n_evals: Arc<AtomicUsize>,
+    /// Maximum number of separator iterations
+    pub iter_budget: Option<usize>,
+    /// Number of separator iterations registered since the budget was last set
+    pub n_iters: Arc<AtomicUsize>,
+    /// Feasible solution which, once found, terminates the entire run
+    pub target: Option<Target>,
+    pub target_met: Arc<AtomicBool>,
+    /// Moment the budget was last set
+    pub start: Instant,
+}
+
+/// Resources a phase of the optimization is allowed to consume, terminates on whichever is exhausted first.
+/// Runs not limited by time are deterministic for a given seed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Budget {
+    pub time: Option<Duration>,
+    /// Sample evaluations, summed over all workers
+    pub evals: Option<usize>,
+    /// Iterations of the separator (rounds in which all workers move the colliding items)
+    pub iterations: Option<usize>,
+}
+
+impl Budget {
+    pub fn time(duration: Duration) -> Self {
+        Budget { time: Some(duration), ..Default::default() }
+    }
+
+    pub fn evals(n_evals: usize) -> Self {
+        Budget { evals: Some(n_evals), ..Default::default() }
+    }
+
+    pub fn iterations(n_iters: usize) -> Self {
+        Budget { iterations: Some(n_iters), ..Default::default() }
+    }
+
+    pub fn is_deterministic(&self) -> bool {
+        self.time.is_none() && (self.evals.is_some() || self.iterations.is_some())
+    }
+}
+
+/// A target for the strip, met by any feasible solution at or below the width, or at or above the density
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Target {
+    /// Length of the open dimension of the strip (the height for vertical strips)
+    Width(f32),
+    Density(f32),
+}
+
+impl Target {
+    pub fn is_met(&self, width: f32, density: f32) -> bool {
+        match self {
+            Target::Width(w) => width <= *w,
+            Target::Density(d) => density >= *d,
+        }
+    }
 }
 
 impl Terminator {
@@ -15,6 +78,13 @@ impl Terminator {
         Terminator {
             timeout: None,
             ctrlc: Arc::new(AtomicBool::new(false)),
+            eval_budget: None,
+            n_evals: Arc::new(AtomicUsize::new(0)),
+            iter_budget: None,
+            n_iters: Arc::new(AtomicUsize::new(0)),
+            target: None,
+            target_met: Arc::new(AtomicBool::new(false)),
+            start: Instant::now(),
         }
     }
 
@@ -29,12 +99,15 @@ impl Terminator {
         }).expect("Error setting Ctrl-C handler");
 
         Terminator {
-            timeout: None,
             ctrlc,
+            ..Self::new_without_ctrlc()
         }
     }
     pub fn is_kill(&self) -> bool {
         self.timeout.map_or(false, |timeout| Instant::now() > timeout)
+            || self.eval_budget.map_or(false, |budget| self.n_evals() >= budget)
+            || self.iter_budget.map_or(false, |budget| self.n_iters() >= budget)
+            || self.is_target_met()
             || self.ctrlc.load(Ordering::SeqCst)
     }
 
@@ -46,6 +119,7 @@ impl Terminator {
     /// Sets the timeout to a specific time in the future
     pub fn set_timeout_from_now(&mut self, timeout: Duration) -> &mut Self {
         self.timeout = Some(Instant::now() + timeout);
+        self.start = Instant::now();
         self
     }
 
@@ -53,4 +127,78 @@ impl Terminator {
         self.timeout = None;
         self
     }
+
+    /// Replaces all limits with the ones of the budget and resets the counters.
+    /// The target (and whether it was met) is kept, as it applies to the entire run.
+    pub fn set_budget(&mut self, budget: Budget) -> &mut Self {
+        self.timeout = budget.time.map(|t| Instant::now() + t);
+        self.eval_budget = budget.evals;
+        self.iter_budget = budget.iterations;
+        //new counters, so clones of this terminator do not share them
+        self.n_evals = Arc::new(AtomicUsize::new(0));
+        self.n_iters = Arc::new(AtomicUsize::new(0));
+        self.start = Instant::now();
+        self
+    }
+
+    pub fn set_target(&mut self, target: Option<Target>) -> &mut Self {
+        self.target = target;
+        self.target_met = Arc::new(AtomicBool::new(false));
+        self
+    }
+
+    /// The part of the current budget which has not been consumed yet
+    pub fn remaining(&self) -> Budget {
+        Budget {
+            time: self.timeout.map(|t| t.saturating_duration_since(Instant::now())),
+            evals: self.eval_budget.map(|b| b.saturating_sub(self.n_evals())),
+            iterations: self.iter_budget.map(|b| b.saturating_sub(self.n_iters())),
+        }
+    }
+
+    /// Fraction of the current budget which has been consumed, in range [0.0, 1.0].
+    /// If multiple limits are set, the one closest to exhaustion determines the fraction.
+    pub fn consumed_fraction(&self) -> f32 {
+        let time_fraction = self.timeout.map(|t| {
+            let elapsed = self.start.elapsed();
+            let remaining = t.saturating_duration_since(Instant::now());
+            elapsed.as_secs_f32() / (elapsed + remaining).as_secs_f32()
+        });
+        let eval_fraction = self.eval_budget.map(|b| self.n_evals() as f32 / b.max(1) as f32);
+        let iter_fraction = self.iter_budget.map(|b| self.n_iters() as f32 / b.max(1) as f32);
+
+        [time_fraction, eval_fraction, iter_fraction].into_iter()
+            .flatten()
+            .fold(0.0, f32::max)
+            .min(1.0)
+    }
+
+    pub fn register_evals(&self, n_evals: usize) {
+        self.n_evals.fetch_add(n_evals, Ordering::SeqCst);
+    }
+
+    pub fn n_evals(&self) -> usize {
+        self.n_evals.load(Ordering::SeqCst)
+    }
+
+    pub fn register_iteration(&self) {
+        self.n_iters.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn n_iters(&self) -> usize {
+        self.n_iters.load(Ordering::SeqCst)
+    }
+
+    /// Reports a feasible solution, returns true if it meets the target
+    pub fn register_feasible(&self, width: f32, density: f32) -> bool {
+        let met = self.target.map_or(false, |t| t.is_met(width, density));
+        if met {
+            self.target_met.store(true, Ordering::SeqCst);
+        }
+        met
+    }
+
+    pub fn is_target_met(&self) -> bool {
+        self.target_met.load(Ordering::SeqCst)
+    }
 }
\ No newline at end of file
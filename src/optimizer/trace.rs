@@ -0,0 +1,99 @@
+use crate::optimizer::Phase;
+use crate::optimizer::Terminator;
+use itertools::Itertools;
+use log::{log, Level};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// State of the run at a moment in time
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct TracePoint {
+    /// Time since the start of the run
+    pub elapsed: Duration,
+    /// Sample evaluations since the start of the run, summed over all workers
+    pub evals: usize,
+    pub phase: Phase,
+    /// Length of the open dimension of the strip (the height for vertical strips)
+    pub strip_width: f32,
+    pub density: f32,
+    /// Total loss of the layout, 0.0 if it is feasible
+    pub total_loss: f32,
+    /// Whether the layout is a new best feasible solution, otherwise it is a periodic sample
+    pub improvement: bool,
+}
+
+/// Timeline of a run: every improvement and periodic samples of the layout being separated
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Trace {
+    pub points: Vec<TracePoint>,
+}
+
+impl Trace {
+    /// First moment a feasible solution at or above the density was found
+    pub fn time_to_density(&self, density: f32) -> Option<Duration> {
+        self.points.iter()
+            .find(|p| p.improvement && p.density >= density)
+            .map(|p| p.elapsed)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let header = "elapsed_secs,evals,phase,strip_width,density,total_loss,improvement";
+        let rows = self.points.iter().map(|p| {
+            format!("{:.3},{},{:?},{:.4},{:.6},{},{}",
+                p.elapsed.as_secs_f32(), p.evals, p.phase, p.strip_width, p.density, p.total_loss, p.improvement)
+        });
+        std::iter::once(header.to_string()).chain(rows).join("\n") + "\n"
+    }
+
+    pub fn write_csv(&self, path: &Path, log_lvl: Level) {
+        fs::write(path, self.to_csv())
+            .unwrap_or_else(|err| panic!("could not write csv file: {}, {}", path.display(), err));
+        log!(log_lvl, "[IO] trace exported to file://{}", path.display());
+    }
+}
+
+/// Records the [`Trace`] of a run, samples are taken at most once every `interval`
+pub struct Tracer {
+    pub trace: Trace,
+    pub interval: Duration,
+    start: Instant,
+    last_sample: Option<Instant>,
+    /// Evaluations of previous phases, the counter of the terminator is reset every phase
+    eval_offset: usize,
+}
+
+impl Tracer {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            trace: Trace::default(),
+            interval,
+            start: Instant::now(),
+            last_sample: None,
+            eval_offset: 0,
+        }
+    }
+
+    /// Adds the evaluations of a finished phase to all subsequent points
+    pub fn end_phase(&mut self, n_evals: usize) {
+        self.eval_offset += n_evals;
+    }
+
+    /// Records improvements unconditionally and other layouts if the interval since the last point has passed
+    pub fn record(&mut self, phase: Phase, term: &Terminator, strip_width: f32, density: f32, total_loss: f32, improvement: bool) {
+        let due = self.last_sample.is_none_or(|t| t.elapsed() >= self.interval);
+        if improvement || due {
+            self.trace.points.push(TracePoint {
+                elapsed: self.start.elapsed(),
+                evals: self.eval_offset + term.n_evals(),
+                phase,
+                strip_width,
+                density,
+                total_loss,
+                improvement,
+            });
+            self.last_sample = Some(Instant::now());
+        }
+    }
+}
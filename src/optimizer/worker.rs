@@ -1,4 +1,7 @@
+use crate::eval::group_evaluator::GroupEvaluator;
 use crate::eval::sep_evaluator::SeparationEvaluator;
+use crate::optimizer::groups::{GroupInstances, ItemGroup};
+use crate::optimizer::mirror::MirrorVariants;
 use crate::quantify::tracker::CollisionTracker;
 use crate::sample::search;
 use crate::sample::search::SampleConfig;
@@ -10,7 +13,8 @@ use jagua_rs::entities::strip_packing::{SPInstance, SPPlacement, SPProblem, SPSo
 use jagua_rs::geometry::DTransformation;
 use jagua_rs::util::FPA;
 use log::debug;
-use rand::prelude::{SliceRandom, SmallRng};
+use rand::prelude::SliceRandom;
+use rand_xoshiro::Xoshiro256PlusPlus;
 use std::iter::Sum;
 use std::ops::AddAssign;
 use tap::Tap;
@@ -19,19 +23,23 @@ pub struct SeparatorWorker {
     pub instance: SPInstance,
     pub prob: SPProblem,
     pub ct: CollisionTracker,
-    pub rng: SmallRng,
+    /// Copies of the groups in the layout, kept up to date as they are moved
+    pub group_instances: GroupInstances,
+    pub rng: Xoshiro256PlusPlus,
     pub sample_config: SampleConfig,
 }
 
 impl SeparatorWorker {
-    pub fn load(&mut self, sol: &SPSolution, ct: &CollisionTracker) {
-        // restores the state of the worker to the given solution and accompanying tracker
+    pub fn load(&mut self, sol: &SPSolution, ct: &CollisionTracker, group_instances: &GroupInstances) {
+        // restores the state of the worker to the given solution and accompanying tracker and groups
         debug_assert!(sol.strip_width == self.prob.strip_width());
         self.prob.restore(sol);
         self.ct = ct.clone();
+        self.group_instances = group_instances.clone();
     }
 
-    pub fn separate(&mut self) -> SepStats {
+    pub fn separate(&mut self, groups: &[ItemGroup], mirrors: &MirrorVariants) -> SepStats {
+
         //collect all colliding items and shuffle them
         let candidates = self.prob.layout.placed_items().keys()
             .filter(|pk| self.ct.get_loss(*pk) > 0.0)
@@ -43,22 +51,40 @@ impl SeparatorWorker {
 
         //give each item a chance to move to a better (eval) position
         for &pk in candidates.iter() {
+            //members of a group are moved along with the group, invalidating their old keys
+            if !self.prob.layout.placed_items().contains_key(pk) {
+                continue;
+            }
+            if let Some((group_idx, members)) = self.group_instances.members_of(pk).map(|(g, m)| (g, m.to_vec())) {
+                //check if the group is still colliding
+                if self.ct.get_group_loss(&members) > 0.0 {
+                    let (new_members, n_evals) = self.separate_group(&groups[group_idx], &members);
+                    self.group_instances.update(&members, new_members);
+                    total_moves += 1;
+                    total_evals += n_evals;
+                }
+                continue;
+            }
             //check if the item is still colliding
             if self.ct.get_loss(pk) > 0.0 {
                 let item_id = self.prob.layout.placed_items()[pk].item_id;
-                let item = self.instance.item(item_id);
 
-                // create an evaluator to evaluate the samples during the search
-                let evaluator = SeparationEvaluator::new(&self.prob.layout, item, pk, &self.ct);
+                // create an evaluator for the item and its mirrored counterpart (if any) to evaluate the samples during the search
+                let variants = mirrors.variants(item_id)
+                    .map(|id| {
+                        let item = self.instance.item(id);
+                        (item, SeparationEvaluator::new(&self.prob.layout, item, pk, &self.ct))
+                    })
+                    .collect_vec();
 
                 // search for a better position for the item
                 let (best_sample, n_evals) =
-                    search::search_placement(&self.prob.layout, item, Some(pk), evaluator, self.sample_config, &mut self.rng);
+                    search::search_variant_placement(&self.prob.layout, variants, Some(pk), self.sample_config, &mut self.rng);
 
-                let (new_dt, _eval) = best_sample.expect("search_placement should always return a sample");
+                let (new_item_id, new_dt, _eval) = best_sample.expect("search_placement should always return a sample");
 
-                // move the item to the new position
-                self.move_item(pk, new_dt);
+                // move the item to the new position, possibly mirrored
+                self.replace_item(pk, new_item_id, new_dt);
                 total_moves += 1;
                 total_evals += n_evals;
             }
@@ -66,16 +92,67 @@ impl SeparatorWorker {
         SepStats { total_moves, total_evals }
     }
 
-    pub fn move_item(&mut self, pk: PItemKey, d_transf: DTransformation) -> PItemKey {
+    /// Searches a better position for a copy of a group, sampled as a single unit around its anchor
+    fn separate_group(&mut self, group: &ItemGroup, members: &[PItemKey]) -> (Vec<PItemKey>, usize) {
+        let evaluator = GroupEvaluator::new(
+            members.iter().zip(group.members.iter())
+                .map(|(&pk, (id, rel_dt))| {
+                    let evaluator = SeparationEvaluator::new(&self.prob.layout, self.instance.item(*id), pk, &self.ct)
+                        .ignore_items(members);
+                    (evaluator, *rel_dt)
+                })
+                .collect()
+        );
+
+        let anchor = self.instance.item(group.anchor_id());
+        let (best_sample, n_evals) =
+            search::search_placement(&self.prob.layout, anchor, Some(members[0]), evaluator, self.sample_config, &mut self.rng);
+
+        let (new_anchor_dt, _eval) = best_sample.expect("search_placement should always return a sample");
+        let new_dts = group.member_transforms(&new_anchor_dt).map(|(_, dt)| dt).collect_vec();
+
+        (self.move_group(members, &new_dts), n_evals)
+    }
+
+    /// Moves all members of a group at once, so the tracker attributes the collisions to their final positions
+    pub fn move_group(&mut self, pks: &[PItemKey], d_transfs: &[DTransformation]) -> Vec<PItemKey> {
+        debug_assert!(tracker_matches_layout(&self.ct, &self.prob.layout));
+
+        let (old_l, old_w_l) = (self.ct.get_group_loss(pks), self.ct.get_group_weighted_loss(pks));
+
+        let placements = pks.iter().zip(d_transfs.iter())
+            .map(|(&pk, &d_transf)| {
+                let item_id = self.prob.layout.placed_items()[pk].item_id;
+                SPPlacement { d_transf, item_id }
+            })
+            .collect_vec();
+        for &pk in pks {
+            self.prob.remove_item(pk, true);
+        }
+        let new_pks = placements.into_iter()
+            .map(|placement| self.prob.place_item(placement))
+            .collect_vec();
+        let moves = pks.iter().copied().zip(new_pks.iter().copied()).collect_vec();
+        self.ct.register_group_move(&self.prob.layout, &moves);
+
+        let (new_l, new_w_l) = (self.ct.get_group_loss(&new_pks), self.ct.get_group_weighted_loss(&new_pks));
+
+        debug!("Moved group {:?} (l: {}, wl: {}) to {:?} (l+1: {}, wl+1: {})", pks, FMT.fmt2(old_l), FMT.fmt2(old_w_l), new_pks, FMT.fmt2(new_l), FMT.fmt2(new_w_l));
+        debug_assert!(new_w_l <= old_w_l * 1.001, "weighted loss should never increase: {} > {}", FMT.fmt2(old_w_l), FMT.fmt2(new_w_l));
         debug_assert!(tracker_matches_layout(&self.ct, &self.prob.layout));
 
-        let item = self.instance.item(self.prob.layout.placed_items()[pk].item_id);
+        new_pks
+    }
+
+    /// Moves the item, placing `item_id` in its stead (the item itself or its mirrored counterpart)
+    pub fn replace_item(&mut self, pk: PItemKey, item_id: usize, d_transf: DTransformation) -> PItemKey {
+        debug_assert!(tracker_matches_layout(&self.ct, &self.prob.layout));
 
         let (old_l, old_w_l) = (self.ct.get_loss(pk), self.ct.get_weighted_loss(pk));
 
         //modify the problem, by removing the item and placing it in the new position
         let old_placement = self.prob.remove_item(pk, true);
-        let new_placement = SPPlacement { d_transf, item_id: item.id };
+        let new_placement = SPPlacement { d_transf, item_id };
         let new_pk = self.prob.place_item(new_placement);
         //update the collision tracker to reflect the changes
         self.ct.register_item_move(&self.prob.layout, pk, new_pk);
@@ -90,6 +167,7 @@ impl SeparatorWorker {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
 pub struct SepStats {
     pub total_moves: usize,
     pub total_evals: usize,
@@ -1,7 +1,12 @@
-use jagua_rs::geometry::geo_traits::{DistanceTo, Shape};
+use jagua_rs::collision_detection::hazards::detector::HazardDetector;
+use jagua_rs::collision_detection::hazards::filter::{HazardFilter, QZHazardFilter};
+use jagua_rs::collision_detection::hazards::HazardEntity;
+use jagua_rs::entities::general::Bin;
+use jagua_rs::geometry::geo_traits::{CollidesWith, DistanceTo, Shape};
 use jagua_rs::geometry::primitives::{Rect, SPolygon};
 use crate::config::OVERLAP_PROXY_EPSILON_DIAM_RATIO;
 use crate::quantify::overlap_proxy::overlap_area_proxy;
+use itertools::Itertools;
 
 pub mod overlap_proxy;
 mod pair_matrix;
@@ -49,4 +54,76 @@ pub fn quantify_collision_poly_bin(s: &SPolygon, bin_bbox: Rect) -> f32 {
     let penalty = s.surrogate().convex_hull_area;
 
     10.0 * (overlap * penalty).sqrt()
-}
\ No newline at end of file
+}
+
+/// Quantifies a collision between a simple polygon and one of the hazards of the bin.
+pub fn quantify_collision_poly_bin_hazard(s: &SPolygon, haz: &HazardEntity, bin: &Bin) -> f32 {
+    match haz {
+        HazardEntity::BinExterior => match is_rectangular(&bin.outer_cd) {
+            true => quantify_collision_poly_bin(s, bin.outer_cd.bbox()),
+            false => quantify_collision_poly_irregular_bin(s, &bin.outer_cd),
+        },
+        HazardEntity::InferiorQualityZone { quality, id } => {
+            let zone = bin.quality_zones[*quality].as_ref().expect("quality zone should exist");
+            quantify_collision_poly_zone(s, &zone.shapes_cd[*id])
+        }
+        _ => unreachable!("not a hazard of the bin: {:?}", haz),
+    }
+}
+
+/// Quantifies a collision between a simple polygon and the exterior of a non-rectangular bin.
+/// The poles of the polygon with their center outside the bin approximate the area outside the bin.
+#[inline(always)]
+pub fn quantify_collision_poly_irregular_bin(s: &SPolygon, bin: &SPolygon) -> f32 {
+    let outside = s.surrogate().poles.iter()
+        .filter(|pole| !bin.collides_with(&pole.center))
+        .map(|pole| pole.radius.powi(2))
+        .sum::<f32>();
+    //a small value to ensure it is never zero, when the polygon only crosses the edges of the bin
+    let overlap = outside + 0.001 * s.bbox().area();
+    debug_assert!(overlap.is_normal());
+
+    let penalty = s.surrogate().convex_hull_area;
+
+    10.0 * (overlap * penalty).sqrt()
+}
+
+/// Quantifies a collision between a simple polygon and an inferior quality zone (or hole) of the bin.
+/// The poles of the polygon with their center inside the zone approximate the overlap,
+/// but it is at least the overlap of the bounding boxes (limited to the area of the zone), so that small defects between the poles still count.
+/// Scaled like [`quantify_collision_poly_bin`], so the weights of all hazards of the bin are comparable.
+#[inline(always)]
+pub fn quantify_collision_poly_zone(s: &SPolygon, zone: &SPolygon) -> f32 {
+    let s_bbox = s.bbox();
+    let pole_overlap = s.surrogate().poles.iter()
+        .filter(|pole| zone.collides_with(&pole.center))
+        .map(|pole| pole.radius.powi(2))
+        .sum::<f32>();
+    let bbox_overlap = Rect::intersection(s_bbox, zone.bbox())
+        .map_or(0.0, |r| r.area().min(zone.area()));
+    //a small value to ensure it is never zero
+    let overlap = f32::max(pole_overlap, bbox_overlap) + 0.001 * s_bbox.area();
+    debug_assert!(overlap.is_normal());
+
+    let penalty = s.surrogate().convex_hull_area;
+
+    10.0 * (overlap * penalty).sqrt()
+}
+
+/// Removes the hazards which are irrelevant to an item with this filter from the detector:
+/// inferior quality zones of at least the quality the item requires
+pub fn remove_irrelevant_hazards(detector: &mut impl HazardDetector, filter: Option<&QZHazardFilter>) {
+    if let Some(filter) = filter {
+        let irrelevant = detector.iter()
+            .filter(|h| filter.is_irrelevant(h))
+            .cloned()
+            .collect_vec();
+        irrelevant.iter().for_each(|h| detector.remove(h));
+    }
+}
+
+/// Whether the polygon coincides with its bounding box
+pub fn is_rectangular(s: &SPolygon) -> bool {
+    let bbox_area = s.bbox().area();
+    bbox_area - s.area() < bbox_area * 1e-6
+}
@@ -17,6 +17,30 @@ impl PairMatrix {
             data: vec![CTEntry { weight: 1.0, loss: 0.0 }; len],
         }
     }
+
+    /// Changes the size of the matrix, the entries of the rows and columns which remain are kept
+    pub fn resize(&mut self, size: usize) {
+        let mut resized = PairMatrix::new(size);
+        let n = size.min(self.size);
+        for row in 0..n {
+            for col in row..n {
+                resized[(row, col)] = self[(row, col)];
+            }
+        }
+        *self = resized;
+    }
+
+    /// Removes the row and column of `idx`, the last row and column take their place
+    pub fn swap_remove(&mut self, idx: usize) {
+        let last = self.size - 1;
+        if idx != last {
+            for col in 0..last {
+                let src_col = if col == idx { last } else { col };
+                self[(idx, col)] = self[(last, src_col)];
+            }
+        }
+        self.resize(last);
+    }
 }
 
 impl Index<(usize, usize)> for PairMatrix {
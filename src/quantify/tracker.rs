@@ -1,20 +1,36 @@
 use jagua_rs::collision_detection::hazards::detector::{BasicHazardDetector, HazardDetector};
 use jagua_rs::collision_detection::hazards::HazardEntity;
 use jagua_rs::entities::general::{Layout, PItemKey};
-use crate::config::{WEIGHT_DECAY, WEIGHT_MAX_INC_RATIO, WEIGHT_MIN_INC_RATIO};
 use crate::quantify::pair_matrix::PairMatrix;
-use crate::quantify::{quantify_collision_poly_bin, quantify_collision_poly_poly};
+use crate::quantify::{quantify_collision_poly_bin_hazard, quantify_collision_poly_poly, remove_irrelevant_hazards};
 use crate::util::assertions::tracker_matches_layout;
+use itertools::Itertools;
+use log::warn;
 use ordered_float::Float;
+use serde::{Deserialize, Serialize};
 use slotmap::SecondaryMap;
 
-/// Tracker of both collisions between pair of items and collisions with the bin.
+/// Determines how the weights of the [`CollisionTracker`] evolve between iterations.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WeightConfig {
+    /// Multiplier applied to the weight of the most severe collision
+    pub max_inc_ratio: f32,
+    /// Multiplier applied to the weight of the least severe collision
+    pub min_inc_ratio: f32,
+    /// Multiplier applied to the weights of non-colliding pairs
+    pub decay: f32,
+}
+
+/// Tracker of both collisions between pair of items and collisions with the hazards of the bin.
 /// It also stores the weights for every pair of hazards and is used as a cache for collisions.
 #[derive(Debug, Clone)]
 pub struct CollisionTracker {
     pub size: usize,
     pub pk_idx_map: SecondaryMap<PItemKey, usize>,
     pub pair_collisions: PairMatrix,
+    /// Hazards of the bin: its exterior and inferior quality zones (holes are zones of quality 0)
+    pub bin_hazards: Vec<HazardEntity>,
+    /// Collisions of every item with every hazard of the bin, at index `item_idx * bin_hazards.len() + hazard_idx`
     pub bin_collisions: Vec<CTEntry>,
 }
 
@@ -23,6 +39,10 @@ pub type CTSnapshot = CollisionTracker;
 impl CollisionTracker {
     pub fn new(l: &Layout) -> Self {
         let size = l.placed_items.len();
+        let bin_hazards = l.cde().all_hazards()
+            .map(|h| h.entity)
+            .filter(|e| !matches!(e, HazardEntity::PlacedItem { .. }))
+            .collect_vec();
 
         // Create the tracker
         let mut ot = Self {
@@ -31,7 +51,8 @@ impl CollisionTracker {
                 .map(|(i, pk)| (pk, i))
                 .collect(),
             pair_collisions: PairMatrix::new(size),
-            bin_collisions: vec![CTEntry { weight: 1.0, loss: 0.0 }; size],
+            bin_collisions: vec![CTEntry { weight: 1.0, loss: 0.0 }; size * bin_hazards.len()],
+            bin_hazards,
         };
 
         // Recompute the loss for all items
@@ -53,13 +74,16 @@ impl CollisionTracker {
         for i in 0..self.size {
             self.pair_collisions[(idx, i)].loss = 0.0;
         }
-        self.bin_collisions[idx].loss = 0.0;
+        for e in self.item_bin_collisions_mut(idx) {
+            e.loss = 0.0;
+        }
 
         // Compute which hazards are currently colliding with the item
         let mut detector = BasicHazardDetector::new();
         l.cde().collect_poly_collisions(shape, &mut detector);
-        // Remove the item itself from the detector
+        // Remove the item itself from the detector, along with the zones it may be placed in
         detector.remove(&HazardEntity::from((pk, pi)));
+        remove_irrelevant_hazards(&mut detector, pi.hazard_filter.as_ref());
 
         // For each colliding hazard, quantify the collision and store it in the tracker
         for haz in detector.iter() {
@@ -72,16 +96,32 @@ impl CollisionTracker {
                     assert!(loss > 0.0, "loss for a collision should be > 0.0");
                     self.pair_collisions[(idx, idx_other)].loss = loss;
                 }
-                HazardEntity::BinExterior => {
-                    let loss = quantify_collision_poly_bin(shape, l.bin.outer_cd.bbox);
+                bin_haz => {
+                    let entry_idx = self.bin_entry_idx(idx, bin_haz);
+                    let loss = quantify_collision_poly_bin_hazard(shape, bin_haz, &l.bin);
                     assert!(loss > 0.0, "loss for a collision should be > 0.0");
-                    self.bin_collisions[idx].loss = loss;
+                    self.bin_collisions[entry_idx].loss = loss;
                 }
-                _ => unimplemented!("unsupported hazard entity"),
             }
         }
     }
 
+    fn bin_entry_idx(&self, idx: usize, bin_haz: &HazardEntity) -> usize {
+        let haz_idx = self.bin_hazards.iter().position(|h| h == bin_haz)
+            .unwrap_or_else(|| panic!("unknown bin hazard: {:?}", bin_haz));
+        idx * self.bin_hazards.len() + haz_idx
+    }
+
+    fn item_bin_collisions(&self, idx: usize) -> &[CTEntry] {
+        let n = self.bin_hazards.len();
+        &self.bin_collisions[idx * n..(idx + 1) * n]
+    }
+
+    fn item_bin_collisions_mut(&mut self, idx: usize) -> &mut [CTEntry] {
+        let n = self.bin_hazards.len();
+        &mut self.bin_collisions[idx * n..(idx + 1) * n]
+    }
+
     pub fn restore_but_keep_weights(&mut self, cts: &CTSnapshot, layout: &Layout) {
         //Copy the loss and keys, but keep the weights
         self.pk_idx_map = cts.pk_idx_map.clone();
@@ -108,36 +148,130 @@ impl CollisionTracker {
         debug_assert!(tracker_matches_layout(self, l));
     }
 
-    pub fn increment_weights(&mut self) {
+    /// Registers an item which was added to the layout, the weights of the items already placed are kept
+    pub fn register_item_insert(&mut self, l: &Layout, pk: PItemKey) {
+        let idx = self.size;
+        self.pk_idx_map.insert(pk, idx);
+        self.size += 1;
+        self.pair_collisions.resize(self.size);
+        self.bin_collisions.resize(self.size * self.bin_hazards.len(), CTEntry { weight: 1.0, loss: 0.0 });
+
+        self.recompute_loss_for_item(pk, l);
+
+        debug_assert!(tracker_matches_layout(self, l));
+    }
+
+    /// Registers an item which was removed from the layout, the weights of the remaining items are kept
+    pub fn register_item_removal(&mut self, l: &Layout, pk: PItemKey) {
+        let idx = self.pk_idx_map.remove(pk).unwrap();
+        let last = self.size - 1;
+        let n = self.bin_hazards.len();
+
+        //the item at the last index takes the place of the removed one
+        if idx != last {
+            let last_pk = self.pk_idx_map.iter()
+                .find(|(_, i)| **i == last)
+                .map(|(pk, _)| pk)
+                .unwrap();
+            self.pk_idx_map[last_pk] = idx;
+            self.bin_collisions.copy_within(last * n..(last + 1) * n, idx * n);
+        }
+        self.pair_collisions.swap_remove(idx);
+        self.bin_collisions.truncate(last * n);
+        self.size = last;
+
+        debug_assert!(tracker_matches_layout(self, l));
+    }
+
+    /// Counterpart of [`CollisionTracker::register_item_move`] for the members of a group, which all moved at once
+    pub fn register_group_move(&mut self, l: &Layout, moves: &[(PItemKey, PItemKey)]) {
+        //all keys need to be swapped before the losses can be recomputed
+        for &(old_pk, new_pk) in moves {
+            let idx = self.pk_idx_map.remove(old_pk).unwrap();
+            self.pk_idx_map.insert(new_pk, idx);
+        }
+        for &(_, new_pk) in moves {
+            self.recompute_loss_for_item(new_pk, l);
+        }
+
+        debug_assert!(tracker_matches_layout(self, l));
+    }
+
+    pub fn increment_weights(&mut self, config: &WeightConfig) {
         let max_o = self.pair_collisions.data.iter()
             .map(|e| e.loss)
             .fold(0.0, |a, b| a.max(b));
 
         for e in self.pair_collisions.data.iter_mut() {
             let multiplier = match e.loss == 0.0 {
-                true => WEIGHT_DECAY, // no collision
-                false => WEIGHT_MIN_INC_RATIO + (WEIGHT_MAX_INC_RATIO - WEIGHT_MIN_INC_RATIO) * (e.loss / max_o),
+                true => config.decay, // no collision
+                false => config.min_inc_ratio + (config.max_inc_ratio - config.min_inc_ratio) * (e.loss / max_o),
             };
             e.weight = (e.weight * multiplier).max(1.0);
         }
 
         for e in self.bin_collisions.iter_mut() {
             let multiplier = match e.loss == 0.0 {
-                true => WEIGHT_DECAY, // no collision
-                false => WEIGHT_MAX_INC_RATIO,
+                true => config.decay, // no collision
+                false => config.max_inc_ratio,
             };
             e.weight = (e.weight * multiplier).max(1.0);
         }
     }
 
+    /// Exports all weights which differ from the initial weight, keyed by the index of the items in `pks` instead of the keys of the layout.
+    /// `pks` lists all placed items, in the order they are stored with the layout (e.g. the placed items of a [`JsonSolution`](crate::util::io::json_solution::JsonSolution)).
+    pub fn export_weights(&self, pks: &[PItemKey]) -> CTWeights {
+        let pair = pks.iter().enumerate().tuple_combinations()
+            .map(|((i_1, pk_1), (i_2, pk_2))| (i_1, i_2, self.get_pair_weight(*pk_1, *pk_2)))
+            .filter(|(_, _, weight)| *weight != 1.0)
+            .collect();
+        let bin = pks.iter().enumerate()
+            .map(|(i, pk)| (i, self.item_bin_collisions(self.pk_idx_map[*pk]).iter().map(|e| e.weight).collect_vec()))
+            .filter(|(_, weights)| weights.iter().any(|w| *w != 1.0))
+            .collect();
+
+        CTWeights { pair, bin }
+    }
+
+    /// Imports weights previously exported with [`CollisionTracker::export_weights`].
+    /// `pks` holds the key of every item listed at export, `None` for items which are no longer placed.
+    pub fn import_weights(&mut self, pks: &[Option<PItemKey>], weights: &CTWeights) {
+        let idx_of = |i: usize| match pks.get(i).copied().flatten() {
+            Some(pk) => Some(self.pk_idx_map[pk]),
+            None => {
+                warn!("[CT] no placed item with index {}, its weights are ignored", i);
+                None
+            }
+        };
+
+        let pair = weights.pair.iter()
+            .filter_map(|&(i_1, i_2, weight)| Some((idx_of(i_1)?, idx_of(i_2)?, weight)))
+            .collect_vec();
+        let bin = weights.bin.iter()
+            .filter_map(|(i, weights)| Some((idx_of(*i)?, weights)))
+            .collect_vec();
+
+        for (idx_1, idx_2, weight) in pair {
+            self.pair_collisions[(idx_1, idx_2)].weight = weight;
+        }
+        let n_bin_hazards = self.bin_hazards.len();
+        for (idx, weights) in bin {
+            assert_eq!(weights.len(), n_bin_hazards, "number of weights does not match the number of bin hazards");
+            for (h, &weight) in weights.iter().enumerate() {
+                self.bin_collisions[idx * n_bin_hazards + h].weight = weight;
+            }
+        }
+    }
+
     pub fn get_pair_weight(&self, pk1: PItemKey, pk2: PItemKey) -> f32 {
         let (idx1, idx2) = (self.pk_idx_map[pk1], self.pk_idx_map[pk2]);
         self.pair_collisions[(idx1, idx2)].weight
     }
 
-    pub fn get_bin_weight(&self, pk: PItemKey) -> f32 {
+    pub fn get_bin_weight(&self, pk: PItemKey, bin_haz: &HazardEntity) -> f32 {
         let idx = self.pk_idx_map[pk];
-        self.bin_collisions[idx].weight
+        self.bin_collisions[self.bin_entry_idx(idx, bin_haz)].weight
     }
 
     pub fn get_pair_loss(&self, pk1: PItemKey, pk2: PItemKey) -> f32 {
@@ -145,9 +279,9 @@ impl CollisionTracker {
         self.pair_collisions[(idx1, idx2)].loss
     }
 
-    pub fn get_bin_loss(&self, pk: PItemKey) -> f32 {
+    pub fn get_bin_loss(&self, pk: PItemKey, bin_haz: &HazardEntity) -> f32 {
         let idx = self.pk_idx_map[pk];
-        self.bin_collisions[idx].loss
+        self.bin_collisions[self.bin_entry_idx(idx, bin_haz)].loss
     }
 
     pub fn get_loss(&self, pk: PItemKey) -> f32 {
@@ -157,7 +291,11 @@ impl CollisionTracker {
             .map(|i| self.pair_collisions[(idx, i)].loss)
             .sum::<f32>();
 
-        self.bin_collisions[idx].loss + pair_loss
+        let bin_loss = self.item_bin_collisions(idx).iter()
+            .map(|e| e.loss)
+            .sum::<f32>();
+
+        bin_loss + pair_loss
     }
 
     pub fn get_weighted_loss(&self, pk: PItemKey) -> f32 {
@@ -167,7 +305,30 @@ impl CollisionTracker {
             .map(|i| self.pair_collisions[(idx, i)].weighted_loss())
             .sum::<f32>();
 
-        self.bin_collisions[idx].weighted_loss() + w_pair_loss
+        let w_bin_loss = self.item_bin_collisions(idx).iter()
+            .map(|e| e.weighted_loss())
+            .sum::<f32>();
+
+        w_bin_loss + w_pair_loss
+    }
+
+    /// Loss of a group of items, collisions between the members themselves are not included
+    pub fn get_group_loss(&self, pks: &[PItemKey]) -> f32 {
+        let internal_loss = pks.iter().tuple_combinations()
+            .map(|(pk1, pk2)| self.get_pair_loss(*pk1, *pk2))
+            .sum::<f32>();
+        pks.iter().map(|pk| self.get_loss(*pk)).sum::<f32>() - 2.0 * internal_loss
+    }
+
+    /// Weighted counterpart of [`CollisionTracker::get_group_loss`]
+    pub fn get_group_weighted_loss(&self, pks: &[PItemKey]) -> f32 {
+        let internal_w_loss = pks.iter().tuple_combinations()
+            .map(|(pk1, pk2)| {
+                let (idx1, idx2) = (self.pk_idx_map[*pk1], self.pk_idx_map[*pk2]);
+                self.pair_collisions[(idx1, idx2)].weighted_loss()
+            })
+            .sum::<f32>();
+        pks.iter().map(|pk| self.get_weighted_loss(*pk)).sum::<f32>() - 2.0 * internal_w_loss
     }
 
     pub fn get_total_loss(&self) -> f32 {
@@ -193,6 +354,16 @@ impl CollisionTracker {
     }
 }
 
+/// Weights of a [`CollisionTracker`], detached from the keys of a specific layout.
+/// Items are identified by their index in the list of placed items the weights were exported with.
+/// Only weights which differ from the initial weight of 1.0 are stored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CTWeights {
+    pub pair: Vec<(usize, usize, f32)>,
+    /// Weights of an item with every hazard of the bin, ordered as in [`CollisionTracker::bin_hazards`]
+    pub bin: Vec<(usize, Vec<f32>)>,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct CTEntry {
     pub loss: f32,
@@ -6,10 +6,11 @@ use std::fmt::Debug;
 
 /// Datastructure to store the N best samples, automatically keeps them sorted and evicts the worst.
 /// It makes sure that no two included samples are too similar.
+/// Every sample belongs to a variant of the item (e.g. its mirrored counterpart), samples of different variants are never similar.
 #[derive(Debug, Clone)]
 pub struct BestSamples {
     pub size: usize,
-    pub samples: Vec<(DTransformation, SampleEval)>,
+    pub samples: Vec<(usize, DTransformation, SampleEval)>,
     pub unique_thresh: f32,
 }
 
@@ -22,12 +23,14 @@ impl BestSamples {
         }
     }
 
-    pub fn report(&mut self, dt: DTransformation, eval: SampleEval) -> bool {
+    pub fn report(&mut self, variant: usize, dt: DTransformation, eval: SampleEval) -> bool {
+        let unique_thresh = self.unique_thresh;
+        let similar = |v: usize, d: DTransformation| v == variant && dtransfs_are_similar(d, dt, unique_thresh, unique_thresh);
         let accept = match eval < self.upper_bound() {
             false => false,
             true => {
                 let any_similar = self.samples.iter()
-                    .any(|(d, _)| dtransfs_are_similar(*d, dt, self.unique_thresh, self.unique_thresh));
+                    .any(|(v, d, _)| similar(*v, *d));
 
                 match any_similar {
                     false => { //no similar sample found, evict worst and accept
@@ -38,12 +41,12 @@ impl BestSamples {
                     }
                     true => { //at least one similar sample exists
                         let better_than_all_similar = self.samples.iter()
-                            .filter(|(d, _)| dtransfs_are_similar(*d, dt, self.unique_thresh, self.unique_thresh))
-                            .all(|(_, sim_eval)| eval < *sim_eval);
+                            .filter(|(v, d, _)| similar(*v, *d))
+                            .all(|(_, _, sim_eval)| eval < *sim_eval);
 
                         if better_than_all_similar {
                             //evict all similar samples
-                            self.samples.retain(|(d, _)| !dtransfs_are_similar(*d, dt, self.unique_thresh, self.unique_thresh));
+                            self.samples.retain(|(v, d, _)| !similar(*v, *d));
                             true
                         }
                         else {
@@ -54,13 +57,13 @@ impl BestSamples {
             }
         };
         if accept {
-            self.samples.push((dt, eval));
-            self.samples.sort_by_key(|(_, eval)| *eval);
+            self.samples.push((variant, dt, eval));
+            self.samples.sort_by_key(|(_, _, eval)| *eval);
             debug_assert!(
                 self.samples.iter()
-                    .filter(|(_, eval)| *eval != SampleEval::Invalid)
+                    .filter(|(_, _, eval)| *eval != SampleEval::Invalid)
                     .tuple_combinations().all(|(a, b)| {
-                        !dtransfs_are_similar(a.0, b.0, self.unique_thresh, self.unique_thresh)
+                        a.0 != b.0 || !dtransfs_are_similar(a.1, b.1, self.unique_thresh, self.unique_thresh)
                     }
                 ),
                 "BestSamples: samples are not unique: {:?}", &self.samples
@@ -68,17 +71,17 @@ impl BestSamples {
             true
         }
         else{
-            debug_assert!(self.samples.is_sorted_by_key(|(_, eval)| *eval));
+            debug_assert!(self.samples.is_sorted_by_key(|(_, _, eval)| *eval));
             false
         }
     }
 
-    pub fn best(&self) -> Option<(DTransformation, SampleEval)> {
+    pub fn best(&self) -> Option<(usize, DTransformation, SampleEval)> {
         self.samples.first().cloned()
     }
 
     pub fn upper_bound(&self) -> SampleEval {
-        if let Some((_, eval)) = self.samples.get(self.size - 1) {
+        if let Some((_, _, eval)) = self.samples.get(self.size - 1) {
             *eval
         } else {
             SampleEval::Invalid
@@ -1,69 +1,143 @@
-use crate::config::{CD_STEP_FAIL, CD_STEP_SUCCESS};
 use crate::eval::sample_eval::{SampleEval, SampleEvaluator};
 use jagua_rs::geometry::DTransformation;
 use jagua_rs::geometry::primitives::Point;
 use log::trace;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::f32::consts::PI;
 use std::fmt::Debug;
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CDConfig {
+    /// Step multiplier on success
+    pub step_success: f32,
+    /// Step multiplier on failure
+    pub step_fail: f32,
+}
+
+/// Rotations the coordinate descent is allowed to explore besides translations
+#[derive(Debug, Clone, Copy)]
+pub enum RotationMove<'a> {
+    /// Only the translation is refined
+    Fixed,
+    /// Rotate by an adaptive step, starting and ending with the given step sizes (in radians)
+    Continuous { steps: (f32, f32), ref_point: Point },
+    /// Rotate to the neighbouring orientations, `rotations` are sorted and within [0, 2π)
+    Discrete { rotations: &'a [f32], ref_point: Point },
+}
+
+/// Refines a placement by coordinate descent.
+/// Rotations keep the item's reference point (in its local frame) in place.
 pub fn refine_coord_desc(
     (init_dt, init_eval): (DTransformation, SampleEval),
     evaluator: &mut impl SampleEvaluator,
     step_init: f32,
     step_limit: f32,
+    rot_move: RotationMove,
+    cd_config: CDConfig,
     rng: &mut impl Rng,
 ) -> (DTransformation, SampleEval) {
     let n_evals_init = evaluator.n_evals();
     let init_pos = init_dt.translation().into();
-    let rot = init_dt.rotation.into();
+    let init_rot = init_dt.rotation();
+
+    let rot_state = match rot_move {
+        RotationMove::Fixed => RotState::Fixed,
+        RotationMove::Continuous { steps: (rot_step, limit), .. } => RotState::Continuous { step: rot_step, limit },
+        RotationMove::Discrete { .. } => RotState::Discrete { exhausted: false },
+    };
 
     // Initialize the coordinate descent algorithm
     let mut cd = CoordinateDescent {
         pos: init_pos,
+        rot: init_rot,
         eval: init_eval,
-        axis: CDAxis::random(rng),
+        axis: CDAxis::random(rng, rot_state.is_active()),
         steps: (step_init, step_init),
         step_limit,
+        rot_move,
+        rot_state,
+        config: cd_config,
     };
 
     // As long as new candidates are available, evaluate them and update the state
-    while let Some([p0, p1]) = cd.ask() {
-        let p0_eval = evaluator.eval(DTransformation::new(rot, p0.into()), Some(cd.eval));
-        let p1_eval = evaluator.eval(DTransformation::new(rot, p1.into()), Some(cd.eval));
+    while let Some([c0, c1]) = cd.ask() {
+        let c0_eval = evaluator.eval(c0.into(), Some(cd.eval));
+        let c1_eval = evaluator.eval(c1.into(), Some(cd.eval));
 
-        let best = [(p0, p0_eval), (p1, p1_eval)].into_iter()
+        let best = [(c0, c0_eval), (c1, c1_eval)].into_iter()
             .min_by_key(|(_, e)| *e).unwrap();
 
         cd.tell(best, rng);
         trace!("CD: {:?}", cd);
         debug_assert!(evaluator.n_evals() - n_evals_init < 1000, "coordinate descent exceeded 1000 evals");
     }
-    trace!("CD: {} evals, t: ({:.3}, {:.3}) -> ({:.3}, {:.3}), eval: {:?}",evaluator.n_evals() - n_evals_init, init_pos.0, init_pos.1, cd.pos.0, cd.pos.1, cd.eval);
-    (DTransformation::new(rot, cd.pos.into()), cd.eval)
+    trace!("CD: {} evals, t: ({:.3}, {:.3}, {:.3}) -> ({:.3}, {:.3}, {:.3}), eval: {:?}",evaluator.n_evals() - n_evals_init, init_pos.0, init_pos.1, init_rot, cd.pos.0, cd.pos.1, cd.rot, cd.eval);
+    (DTransformation::new(cd.rot, cd.pos.into()), cd.eval)
+}
+
+/// Candidate of the coordinate descent: position and rotation (in radians)
+#[derive(Debug, Clone, Copy)]
+struct Candidate(Point, f32);
+
+impl From<Candidate> for DTransformation {
+    fn from(Candidate(p, r): Candidate) -> Self {
+        DTransformation::new(r, p.into())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RotState {
+    Fixed,
+    Continuous { step: f32, limit: f32 },
+    /// A discrete rotation is exhausted when neither neighbouring orientation improved the current position
+    Discrete { exhausted: bool },
+}
+
+impl RotState {
+    fn is_active(&self) -> bool {
+        match self {
+            RotState::Fixed => false,
+            RotState::Continuous { .. } => true,
+            RotState::Discrete { exhausted } => !exhausted,
+        }
+    }
+
+    fn is_converged(&self) -> bool {
+        match self {
+            RotState::Fixed => true,
+            RotState::Continuous { step, limit } => step < limit,
+            RotState::Discrete { exhausted } => *exhausted,
+        }
+    }
 }
 
 #[derive(Debug)]
-struct CoordinateDescent {
+struct CoordinateDescent<'a> {
     pub pos: Point,
+    pub rot: f32,
     pub eval: SampleEval,
     pub axis: CDAxis,
     pub steps: (f32, f32),
     pub step_limit: f32,
+    pub rot_move: RotationMove<'a>,
+    pub rot_state: RotState,
+    pub config: CDConfig,
 }
 
-impl CoordinateDescent {
-    pub fn tell(&mut self, (pos, eval): (Point, SampleEval), rng: &mut impl Rng) {
+impl CoordinateDescent<'_> {
+    pub fn tell(&mut self, (Candidate(pos, rot), eval): (Candidate, SampleEval), rng: &mut impl Rng) {
         let eval_cmp = eval.cmp(&self.eval);
         let better = eval_cmp == Ordering::Less;
         let worse = eval_cmp == Ordering::Greater;
 
         if !worse {
-            (self.pos, self.eval) = (pos, eval);
+            (self.pos, self.rot, self.eval) = (pos, rot, eval);
         }
 
         // Multiply step size of active axis
-        let m = if better { CD_STEP_SUCCESS } else { CD_STEP_FAIL };
+        let m = if better { self.config.step_success } else { self.config.step_fail };
 
         match self.axis {
             CDAxis::Horizontal => self.steps.0 *= m,
@@ -73,31 +147,84 @@ impl CoordinateDescent {
                 self.steps.0 *= m.sqrt();
                 self.steps.1 *= m.sqrt();
             }
+            CDAxis::Rotation => match &mut self.rot_state {
+                RotState::Continuous { step, .. } => *step *= m,
+                RotState::Discrete { exhausted } => *exhausted = !better,
+                RotState::Fixed => unreachable!(),
+            },
+        }
+
+        //a better translation might open up new orientations
+        if better && matches!(self.rot_state, RotState::Discrete { .. }) {
+            self.rot_state = RotState::Discrete { exhausted: false };
         }
 
         if !better {
-            self.axis = CDAxis::random(rng);
+            self.axis = CDAxis::random(rng, self.rot_state.is_active());
         }
     }
 
-    pub fn ask(&self) -> Option<[Point; 2]> {
+    pub fn ask(&self) -> Option<[Candidate; 2]> {
         let (sx, sy) = self.steps;
 
-        if sx < self.step_limit && sy < self.step_limit {
-            // Stop generating candidates if both steps have reached the limit
+        if sx < self.step_limit && sy < self.step_limit && self.rot_state.is_converged() {
+            // Stop generating candidates if all steps have reached their limit
             None
         } else {
             // Generate two candidates on either side of the current position
-            let p = self.pos;
+            let (p, r) = (self.pos, self.rot);
             let c = match self.axis {
-                CDAxis::Horizontal => [Point(p.0 + sx, p.1), Point(p.0 - sx, p.1)],
-                CDAxis::Vertical => [Point(p.0, p.1 + sy), Point(p.0, p.1 - sy)],
-                CDAxis::ForwardDiag => [Point(p.0 + sx, p.1 + sy), Point(p.0 - sx, p.1 - sy)],
-                CDAxis::BackwardDiag => [Point(p.0 - sx, p.1 + sy), Point(p.0 + sx, p.1 - sy)],
+                CDAxis::Horizontal => [Candidate(Point(p.0 + sx, p.1), r), Candidate(Point(p.0 - sx, p.1), r)],
+                CDAxis::Vertical => [Candidate(Point(p.0, p.1 + sy), r), Candidate(Point(p.0, p.1 - sy), r)],
+                CDAxis::ForwardDiag => [Candidate(Point(p.0 + sx, p.1 + sy), r), Candidate(Point(p.0 - sx, p.1 - sy), r)],
+                CDAxis::BackwardDiag => [Candidate(Point(p.0 - sx, p.1 + sy), r), Candidate(Point(p.0 + sx, p.1 - sy), r)],
+                CDAxis::Rotation => self.rotation_candidates(),
             };
             Some(c)
         }
     }
+
+    fn rotation_candidates(&self) -> [Candidate; 2] {
+        let (r_ccw, r_cw, ref_point) = match (self.rot_move, self.rot_state) {
+            (RotationMove::Continuous { ref_point, .. }, RotState::Continuous { step, .. }) => {
+                ((self.rot + step).rem_euclid(2.0 * PI), (self.rot - step).rem_euclid(2.0 * PI), ref_point)
+            }
+            (RotationMove::Discrete { rotations, ref_point }, RotState::Discrete { .. }) => {
+                //the orientations adjacent to the current one
+                let n = rotations.len();
+                let i = closest_rotation(rotations, self.rot);
+                (rotations[(i + 1) % n], rotations[(i + n - 1) % n], ref_point)
+            }
+            _ => unreachable!("rotation axis without rotation move"),
+        };
+        [
+            Candidate(recenter(self.pos, self.rot, r_ccw, ref_point), r_ccw),
+            Candidate(recenter(self.pos, self.rot, r_cw, ref_point), r_cw),
+        ]
+    }
+}
+
+/// Translation which keeps `ref_point` at the same location when changing the rotation from `r` to `r_new`
+fn recenter(pos: Point, r: f32, r_new: f32, ref_point: Point) -> Point {
+    let rotate = |r: f32| {
+        let (sin, cos) = r.sin_cos();
+        Point(cos * ref_point.0 - sin * ref_point.1, sin * ref_point.0 + cos * ref_point.1)
+    };
+    let (curr, new) = (rotate(r), rotate(r_new));
+    Point(pos.0 + curr.0 - new.0, pos.1 + curr.1 - new.1)
+}
+
+/// Index of the rotation closest to `r`, taking the periodicity into account
+fn closest_rotation(rotations: &[f32], r: f32) -> usize {
+    let r = r.rem_euclid(2.0 * PI);
+    let angular_dist = |a: f32| {
+        let d = (a - r).abs();
+        f32::min(d, 2.0 * PI - d)
+    };
+    rotations.iter().enumerate()
+        .min_by(|(_, a), (_, b)| angular_dist(**a).total_cmp(&angular_dist(**b)))
+        .map(|(i, _)| i)
+        .unwrap()
 }
 
 #[derive(Clone, Debug, Copy)]
@@ -110,16 +237,95 @@ enum CDAxis {
     ForwardDiag,
     /// Up-left and down-right
     BackwardDiag,
+    /// Counterclockwise and clockwise around the item's reference point
+    Rotation,
 }
 
 impl CDAxis {
-    fn random(rng: &mut impl Rng) -> Self {
-        match rng.random_range(0..4) {
+    fn random(rng: &mut impl Rng, with_rotation: bool) -> Self {
+        let n_axes = if with_rotation { 5 } else { 4 };
+        match rng.random_range(0..n_axes) {
             0 => CDAxis::Horizontal,
             1 => CDAxis::Vertical,
             2 => CDAxis::ForwardDiag,
             3 => CDAxis::BackwardDiag,
+            4 => CDAxis::Rotation,
             _ => unreachable!(),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CD_CONFIG;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    /// Scores every sample as a collision with the loss computed by `f`
+    struct FnEvaluator<F: FnMut(&DTransformation) -> f32> {
+        f: F,
+        n_evals: usize,
+    }
+
+    impl<F: FnMut(&DTransformation) -> f32> SampleEvaluator for FnEvaluator<F> {
+        fn eval(&mut self, dt: DTransformation, _upper_bound: Option<SampleEval>) -> SampleEval {
+            self.n_evals += 1;
+            SampleEval::Collision { loss: (self.f)(&dt) }
+        }
+
+        fn n_evals(&self) -> usize {
+            self.n_evals
+        }
+    }
+
+    /// Position of a point of the item's local frame after the transformation
+    fn transform(dt: &DTransformation, p: Point) -> Point {
+        let (sin, cos) = dt.rotation().sin_cos();
+        let (tx, ty) = dt.translation();
+        Point(cos * p.0 - sin * p.1 + tx, sin * p.0 + cos * p.1 + ty)
+    }
+
+    #[test]
+    fn discrete_rotation_recenters_on_reference_point() {
+        let rotations = [0.0, PI / 2.0, PI, 3.0 * PI / 2.0];
+        let ref_point = Point(10.0, 5.0);
+        let target = Point(100.0, 50.0);
+        let mut rng = SmallRng::seed_from_u64(0);
+
+        //no loss only for a quarter turn with the reference point on the target
+        let mut evaluated_rotations = vec![];
+        let (final_dt, final_eval) = {
+            let mut evaluator = FnEvaluator {
+                f: |dt: &DTransformation| {
+                    evaluated_rotations.push(dt.rotation());
+                    let p = transform(dt, ref_point);
+                    let rot_loss = if (dt.rotation() - PI / 2.0).abs() < 1e-4 { 0.0 } else { 1.0 };
+                    (p.0 - target.0).hypot(p.1 - target.1) + rot_loss
+                },
+                n_evals: 0,
+            };
+            let init_dt = DTransformation::new(0.0, (target.0 - ref_point.0, target.1 - ref_point.1));
+            let init_eval = evaluator.eval(init_dt, None);
+            let rot_move = RotationMove::Discrete { rotations: &rotations, ref_point };
+            refine_coord_desc((init_dt, init_eval), &mut evaluator, 1.0, 0.01, rot_move, CD_CONFIG, &mut rng)
+        };
+
+        //the rotation was applied around the reference point, which did not move
+        assert!((final_dt.rotation() - PI / 2.0).abs() < 1e-4);
+        let p = transform(&final_dt, ref_point);
+        assert!((p.0 - target.0).hypot(p.1 - target.1) < 1e-2, "reference point moved to {:?}", p);
+        assert!(matches!(final_eval, SampleEval::Collision { loss } if loss < 1e-2));
+
+        //only the allowed rotations were evaluated
+        assert!(evaluated_rotations.iter().all(|r| rotations.iter().any(|a| (r - a).abs() < 1e-5)));
+    }
+
+    #[test]
+    fn closest_rotation_wraps_around() {
+        let rotations = [0.0, PI / 2.0, PI, 3.0 * PI / 2.0];
+        assert_eq!(closest_rotation(&rotations, 2.0 * PI - 0.1), 0);
+        assert_eq!(closest_rotation(&rotations, -PI / 2.0), 3);
+        assert_eq!(closest_rotation(&rotations, PI / 2.0 + 0.1), 1);
+    }
+}
@@ -1,77 +1,141 @@
 use jagua_rs::entities::general::{Item, Layout, PItemKey};
 use jagua_rs::geometry::DTransformation;
-use crate::config::{FIN_REF_CD_RATIOS, PRE_REF_CD_RATIOS, UNIQUE_SAMPLE_THRESHOLD};
+use crate::config::{FIN_REF_CD_RATIOS, FIN_REF_CD_ROT_STEPS, PRE_REF_CD_RATIOS, PRE_REF_CD_ROT_STEPS, UNIQUE_SAMPLE_THRESHOLD};
 use crate::eval::sample_eval::{SampleEval, SampleEvaluator};
 use crate::sample::best_samples::BestSamples;
-use crate::sample::coord_descent::refine_coord_desc;
+use crate::sample::coord_descent::{refine_coord_desc, RotationMove};
 use crate::sample::uniform_sampler::UniformBBoxSampler;
+use jagua_rs::geometry::geo_enums::RotationRange;
 use jagua_rs::geometry::geo_traits::Shape;
+use jagua_rs::geometry::primitives::{Point, Rect};
+use itertools::Itertools;
+use std::f32::consts::PI;
 use log::debug;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::sample::coord_descent::CDConfig;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct SampleConfig {
     pub n_bin_samples: usize,
     pub n_focussed_samples: usize,
     pub n_coord_descents: usize,
+    pub cd_config: CDConfig,
 }
 
-pub fn search_placement(l: &Layout, item: &Item, ref_pk: Option<PItemKey>, mut evaluator: impl SampleEvaluator, sample_config: SampleConfig, rng: &mut impl Rng) -> (Option<(DTransformation, SampleEval)>, usize) {
+pub fn search_placement(l: &Layout, item: &Item, ref_pk: Option<PItemKey>, evaluator: impl SampleEvaluator, sample_config: SampleConfig, rng: &mut impl Rng) -> (Option<(DTransformation, SampleEval)>, usize) {
+    let (best_sample, n_evals) = search_variant_placement(l, vec![(item, evaluator)], ref_pk, sample_config, rng);
+    (best_sample.map(|(_, dt, eval)| (dt, eval)), n_evals)
+}
+
+/// Searches a placement for any of the variants of an item (e.g. the item and its mirrored counterpart), each with its own evaluator.
+/// The samples are spread over the variants, the id of the item the best sample belongs to is returned along with it.
+/// If `ref_pk` is provided, it refers to a placed item of one of the variants.
+pub fn search_variant_placement<E: SampleEvaluator>(l: &Layout, mut variants: Vec<(&Item, E)>, ref_pk: Option<PItemKey>, sample_config: SampleConfig, rng: &mut impl Rng) -> (Option<(usize, DTransformation, SampleEval)>, usize) {
+    let item = variants[0].0;
     let item_min_dim = f32::min(item.shape_cd.bbox().width(), item.shape_cd.bbox().height());
 
     let mut best_samples = BestSamples::new(sample_config.n_coord_descents, item_min_dim * UNIQUE_SAMPLE_THRESHOLD);
 
-    let focussed_sampler = match ref_pk {
+    let focussed_samplers = match ref_pk {
         Some(ref_pk) => {
             //report the current placement (and eval)
-            let dt = l.placed_items[ref_pk].d_transf;
-            let eval = evaluator.eval(dt, Some(best_samples.upper_bound()));
+            let pi = &l.placed_items[ref_pk];
+            let v = variants.iter().position(|(item, _)| item.id == pi.item_id)
+                .expect("reference item is not one of the variants");
+            let eval = variants[v].1.eval(pi.d_transf, Some(best_samples.upper_bound()));
 
-            debug!("[S] Starting from: {:?}", (dt, eval));
-            best_samples.report(dt, eval);
+            debug!("[S] Starting from: {:?}", (pi.item_id, pi.d_transf, eval));
+            best_samples.report(v, pi.d_transf, eval);
 
             //create a sampler around the current placement
-            let pi_bbox = l.placed_items[ref_pk].shape.bbox();
-            UniformBBoxSampler::new(pi_bbox, item, l.bin.outer_cd.bbox())
+            let pi_bbox = pi.shape.bbox();
+            variant_samplers(&variants, pi_bbox, l.bin.outer_cd.bbox())
         }
-        None => None,
+        None => vec![],
     };
 
-    if let Some(focussed_sampler) = focussed_sampler {
-        for _ in 0..sample_config.n_focussed_samples {
-            let dt = focussed_sampler.sample(rng);
-            let eval = evaluator.eval(dt, Some(best_samples.upper_bound()));
-            best_samples.report(dt, eval);
-        }
+    for _ in 0..sample_config.n_focussed_samples {
+        let Some((v, sampler)) = choose_sampler(&focussed_samplers, rng) else { break };
+        let dt = sampler.sample(rng);
+        let eval = variants[v].1.eval(dt, Some(best_samples.upper_bound()));
+        best_samples.report(v, dt, eval);
     }
 
-    let bin_sampler = UniformBBoxSampler::new(l.bin.outer_cd.bbox(), item, l.bin.outer_cd.bbox());
+    let bin_samplers = variant_samplers(&variants, l.bin.outer_cd.bbox(), l.bin.outer_cd.bbox());
 
-    if let Some(bin_sampler) = bin_sampler {
-        for _ in 0..sample_config.n_bin_samples {
-            let dt = bin_sampler.sample(rng).into();
-            let eval = evaluator.eval(dt, Some(best_samples.upper_bound()));
-            best_samples.report(dt, eval);
-        }
+    for _ in 0..sample_config.n_bin_samples {
+        let Some((v, sampler)) = choose_sampler(&bin_samplers, rng) else { break };
+        let dt = sampler.sample(rng).into();
+        let eval = variants[v].1.eval(dt, Some(best_samples.upper_bound()));
+        best_samples.report(v, dt, eval);
     }
 
+    //Rotations are refined around the center of the item's bounding box
+    let rotations = variants.iter()
+        .map(|(item, _)| {
+            let bbox = item.shape_cd.bbox();
+            let ref_point = Point((bbox.x_min + bbox.x_max) / 2.0, (bbox.y_min + bbox.y_max) / 2.0);
+            let continuous = matches!(item.allowed_rotation, RotationRange::Continuous);
+            let discrete_rotations = match &item.allowed_rotation {
+                RotationRange::Discrete(rotations) => rotations.iter()
+                    .map(|r| r.rem_euclid(2.0 * PI))
+                    .sorted_by(f32::total_cmp)
+                    .dedup_by(|a, b| (a - b).abs() < 1e-4)
+                    .collect_vec(),
+                _ => vec![],
+            };
+            (ref_point, continuous, discrete_rotations)
+        })
+        .collect_vec();
+    let rot_move = |v: usize, rot_steps: (f32, f32)| {
+        let (ref_point, continuous, discrete_rotations) = &rotations[v];
+        match continuous {
+            true => RotationMove::Continuous { steps: rot_steps, ref_point: *ref_point },
+            false if discrete_rotations.len() > 1 => RotationMove::Discrete { rotations: discrete_rotations, ref_point: *ref_point },
+            false => RotationMove::Fixed,
+        }
+    };
+
     //Prerefine the best samples
-    for start in best_samples.samples.clone() {
+    for (v, dt, eval) in best_samples.samples.clone() {
+        let rot_move = rot_move(v, PRE_REF_CD_ROT_STEPS);
         let descended = refine_coord_desc(
-            start.clone(),
-            &mut evaluator,
+            (dt, eval),
+            &mut variants[v].1,
             item_min_dim * PRE_REF_CD_RATIOS.0,
             item_min_dim * PRE_REF_CD_RATIOS.1,
+            rot_move,
+            sample_config.cd_config,
             rng);
-        best_samples.report(descended.0, descended.1);
+        best_samples.report(v, descended.0, descended.1);
     }
 
-
     //Do a final refine on the best one
-    let final_sample = best_samples.best().map(|s|
-        refine_coord_desc(s, &mut evaluator, item_min_dim * FIN_REF_CD_RATIOS.0, item_min_dim * FIN_REF_CD_RATIOS.1, rng)
-    );
+    let final_sample = best_samples.best().map(|(v, dt, eval)| {
+        let rot_move = rot_move(v, FIN_REF_CD_ROT_STEPS);
+        let (dt, eval) = refine_coord_desc((dt, eval), &mut variants[v].1, item_min_dim * FIN_REF_CD_RATIOS.0, item_min_dim * FIN_REF_CD_RATIOS.1, rot_move, sample_config.cd_config, rng);
+        (variants[v].0.id, dt, eval)
+    });
+
+    let n_evals = variants.iter().map(|(_, evaluator)| evaluator.n_evals()).sum();
+    debug!("[S] {} samples evaluated, final: {:?}",n_evals,final_sample);
+    (final_sample, n_evals)
+}
 
-    debug!("[S] {} samples evaluated, final: {:?}",evaluator.n_evals(),final_sample);
-    (final_sample, evaluator.n_evals())
+/// A sampler for every variant which fits within the sample bounding box
+fn variant_samplers<E>(variants: &[(&Item, E)], sample_bbox: Rect, bin_bbox: Rect) -> Vec<(usize, UniformBBoxSampler)> {
+    variants.iter().enumerate()
+        .filter_map(|(v, (item, _))| UniformBBoxSampler::new(sample_bbox, item, bin_bbox).map(|s| (v, s)))
+        .collect()
+}
+
+fn choose_sampler<'a>(samplers: &'a [(usize, UniformBBoxSampler)], rng: &mut impl Rng) -> Option<(usize, &'a UniformBBoxSampler)> {
+    let (v, sampler) = match samplers.len() {
+        0 => return None,
+        //the rng is only consumed when there is an actual choice between variants
+        1 => &samplers[0],
+        n => &samplers[rng.random_range(0..n)],
+    };
+    Some((*v, sampler))
 }
@@ -7,6 +7,8 @@ use std::ops::Range;
 use jagua_rs::entities::general::Item;
 use jagua_rs::geometry::primitives::Rect;
 use jagua_rs::geometry::{DTransformation, Transformation};
+use std::f32::consts::PI;
+use crate::config::CONTINUOUS_ROT_BUCKETS;
 
 /// A sampler that creates uniform samples for an item within a bounding box
 #[derive(Clone, Debug)]
@@ -17,17 +19,25 @@ pub struct UniformBBoxSampler {
 
 #[derive(Clone, Debug)]
 struct RotEntry {
+    /// Rotation, or center of the bucket of rotations in case of continuous rotation
     pub r: f32,
+    /// Width of the bucket of rotations around `r` (0.0 for discrete rotations)
+    pub r_width: f32,
     pub x_range: Range<f32>,
     pub y_range: Range<f32>,
 }
 
 impl UniformBBoxSampler {
     pub fn new(sample_bbox: Rect, item: &Item, bin_bbox: Rect) -> Option<Self> {
-        let rotations = match &item.allowed_rotation {
-            RotationRange::None => &vec![0.0],
-            RotationRange::Discrete(r) => r,
-            RotationRange::Continuous => unimplemented!("Continuous rotation not supported"),
+        // continuous rotation is split into buckets, which are sampled from as a whole
+        let (rotations, r_width) = match &item.allowed_rotation {
+            RotationRange::None => (vec![0.0], 0.0),
+            RotationRange::Discrete(r) => (r.clone(), 0.0),
+            RotationRange::Continuous => {
+                let r_width = 2.0 * PI / CONTINUOUS_ROT_BUCKETS as f32;
+                let centers = (0..CONTINUOUS_ROT_BUCKETS).map(|i| (i as f32 + 0.5) * r_width).collect_vec();
+                (centers, r_width)
+            }
         };
 
         let mut shape_buffer = item.shape_cd.as_ref().clone();
@@ -39,11 +49,16 @@ impl UniformBBoxSampler {
         // where the item resides fully inside the bin and is within the sample bounding box
         let rot_entries = rotations.iter()
             .map(|&r| {
-                let r_shape_bbox = shape_buffer.transform_from(item.shape_cd.as_ref(), &Transformation::from_rotation(r)).bbox();
+                //for a bucket, take the bounds enclosing the shape rotated to the center and both edges of the bucket
+                let (x_min, y_min, x_max, y_max) = [r, r - r_width / 2.0, r + r_width / 2.0].iter()
+                    .map(|&r| shape_buffer.transform_from(item.shape_cd.as_ref(), &Transformation::from_rotation(r)).bbox())
+                    .fold((f32::MAX, f32::MAX, f32::MIN, f32::MIN), |(x_min, y_min, x_max, y_max), bb| {
+                        (x_min.min(bb.x_min), y_min.min(bb.y_min), x_max.max(bb.x_max), y_max.max(bb.y_max))
+                    });
 
                 //narrow the bin range to account for the rotated shape
-                let bin_x_range = (bin_bbox.x_min - r_shape_bbox.x_min)..(bin_bbox.x_max - r_shape_bbox.x_max);
-                let bin_y_range = (bin_bbox.y_min - r_shape_bbox.y_min)..(bin_bbox.y_max - r_shape_bbox.y_max);
+                let bin_x_range = (bin_bbox.x_min - x_min)..(bin_bbox.x_max - x_max);
+                let bin_y_range = (bin_bbox.y_min - y_min)..(bin_bbox.y_max - y_max);
 
                 //intersect with the sample bbox
                 let x_range = intersect_range(&bin_x_range, &sample_x_range);
@@ -53,7 +68,7 @@ impl UniformBBoxSampler {
                 if x_range.is_empty() || y_range.is_empty() {
                     None
                 } else {
-                    Some(RotEntry { r, x_range, y_range })
+                    Some(RotEntry { r, r_width, x_range, y_range })
                 }
             }).flatten().collect_vec();
 
@@ -67,8 +82,11 @@ impl UniformBBoxSampler {
         // randomly select a rotation
         let r_entry = self.rot_entries.choose(rng).unwrap();
 
-        // sample a random x and y value within the valid range
-        let r = r_entry.r;
+        // sample a random rotation within the bucket (if any), and x and y value within the valid range
+        let r = match r_entry.r_width > 0.0 {
+            true => r_entry.r + rng.random_range(-0.5..0.5) * r_entry.r_width,
+            false => r_entry.r,
+        };
         let x_sample = rng.random_range(r_entry.x_range.clone());
         let y_sample = rng.random_range(r_entry.y_range.clone());
 
@@ -1,6 +1,6 @@
 use crate::eval::specialized_jaguars_pipeline::SpecializedHazardDetector;
 use crate::quantify::tracker::CollisionTracker;
-use crate::quantify::{quantify_collision_poly_bin, quantify_collision_poly_poly};
+use crate::quantify::{quantify_collision_poly_bin_hazard, quantify_collision_poly_poly, remove_irrelevant_hazards};
 use crate::util::io::svg_util::SvgDrawOptions;
 use float_cmp::{approx_eq, assert_approx_eq};
 use itertools::Itertools;
@@ -21,6 +21,7 @@ pub fn tracker_matches_layout(ct: &CollisionTracker, l: &Layout) -> bool {
         let mut detector = BasicHazardDetector::new();
         l.cde().collect_poly_collisions(&pi1.shape, &mut detector);
         detector.remove(&HazardEntity::from((pk1, pi1)));
+        remove_irrelevant_hazards(&mut detector, pi1.hazard_filter.as_ref());
         assert_eq!(ct.get_pair_loss(pk1, pk1), 0.0);
         for (pk2, pi2) in l.placed_items.iter().filter(|(k, _)| *k != pk1) {
             let stored_loss = ct.get_pair_loss(pk1, pk2);
@@ -116,12 +117,14 @@ pub fn tracker_matches_layout(ct: &CollisionTracker, l: &Layout) -> bool {
                 }
             }
         }
-        if detector.contains(&HazardEntity::BinExterior) {
-            let stored_loss = ct.get_bin_loss(pk1);
-            let calc_loss = quantify_collision_poly_bin(&pi1.shape, l.bin.outer_cd.bbox());
-            assert_approx_eq!(f32, stored_loss, calc_loss, ulps = 5);
-        } else {
-            assert_eq!(ct.get_bin_loss(pk1), 0.0);
+        for bin_haz in ct.bin_hazards.iter() {
+            if detector.contains(bin_haz) {
+                let stored_loss = ct.get_bin_loss(pk1, bin_haz);
+                let calc_loss = quantify_collision_poly_bin_hazard(&pi1.shape, bin_haz, &l.bin);
+                assert_approx_eq!(f32, stored_loss, calc_loss, ulps = 5);
+            } else {
+                assert_eq!(ct.get_bin_loss(pk1, bin_haz), 0.0);
+            }
         }
     }
 
@@ -137,6 +140,10 @@ pub fn custom_pipeline_matches_jaguars(shape: &SPolygon, det: &SpecializedHazard
         let mut detector = BasicHazardDetector::new();
         cde.collect_poly_collisions(shape, &mut detector);
         detector.remove(&HazardEntity::from((pk, pi)));
+        remove_irrelevant_hazards(&mut detector, det.hazard_filter.as_ref());
+        for &ignored_pk in det.ignored_pks.iter() {
+            detector.remove(&HazardEntity::from((ignored_pk, &det.layout.placed_items[ignored_pk])));
+        }
         detector
     };
 
@@ -1,4 +1,14 @@
-use clap::Parser;
+use crate::optimizer::strip::OpenObjective;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+/// Format in which intermediate solutions are exported
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum IntermediateExport {
+    Svg,
+    Json,
+    None,
+}
+
 #[derive(Parser)]
 pub struct MainCli {
     /// Path to input file (mandatory)
@@ -16,4 +26,168 @@ pub struct MainCli {
     /// Compression time limit in seconds (requires exploration time)
     #[arg(short = 'c', long, requires = "exploration", help = "Set the compression phase time limit (in seconds)")]
     pub compression: Option<u64>,
+
+    /// Total number of sample evaluations, split across the phases (optional)
+    #[arg(long, help = "Terminate after a number of sample evaluations (deterministic if no time limit is set)")]
+    pub eval_budget: Option<usize>,
+
+    /// Total number of separator iterations, split across the phases (optional)
+    #[arg(long, help = "Terminate after a number of separator iterations (deterministic if no time limit is set)")]
+    pub iter_budget: Option<usize>,
+
+    /// Target strip width (optional)
+    #[arg(long, conflicts_with = "target_density", help = "Stop as soon as a feasible solution at or below this strip width (height for vertical strips) is found")]
+    pub target_width: Option<f32>,
+
+    /// Target density (optional)
+    #[arg(long, help = "Stop as soon as a feasible solution at or above this density (in range [0, 1]) is found")]
+    pub target_density: Option<f32>,
+
+    /// Seed for the random number generator (optional)
+    #[arg(short = 's', long, help = "Seed for the random number generator (overrides the configuration)")]
+    pub seed: Option<u64>,
+
+    /// Format of the intermediate solutions
+    #[arg(long, value_enum, default_value_t = IntermediateExport::Svg, help = "Format to export intermediate solutions in")]
+    pub intermediate: IntermediateExport,
+
+    /// Continuously export the current solution for the visualizer
+    #[arg(long, help = "Continuously write the current solution to a live SVG file (for the visualizer)")]
+    pub live: bool,
+
+    /// Width of the sheets for bin packing (optional)
+    #[arg(long, conflicts_with_all = &["exploration", "compression", "target_width", "target_density", "initial_solution", "checkpoint", "resume"],
+        help = "Pack the items onto as few sheets of this width (and the strip height) as possible, instead of minimizing the strip width")]
+    pub sheet_width: Option<f32>,
+
+    /// Height of the sheets for bin packing (optional)
+    #[arg(long, requires = "sheet_width", help = "Height of the sheets for bin packing (the strip height of the instance if not set)")]
+    pub sheet_height: Option<f32>,
+
+    /// Width of the container for knapsack (optional)
+    #[arg(long, conflicts_with_all = &["sheet_width", "exploration", "compression", "target_width", "target_density", "initial_solution", "checkpoint", "resume"],
+        help = "Place as much item value (Value of every item, its area if not given) as possible (up to DemandMax of every item) in a container of this width (and the strip height)")]
+    pub container_width: Option<f32>,
+
+    /// Fill the container of the instance file for knapsack
+    #[arg(long, conflicts_with_all = &["sheet_width", "container_width", "exploration", "compression", "target_width", "target_density", "initial_solution", "checkpoint", "resume"],
+        help = "Place as much item value as possible in the Container of the input file (any shape, with holes and quality zones)")]
+    pub fill_container: bool,
+
+    /// Vertical strip orientation
+    #[arg(long, conflicts_with_all = &["sheet_width", "container_width", "fill_container", "open_dimension", "initial_solution", "checkpoint", "resume"],
+        help = "Use the strip height of the instance as a fixed width and minimize the height of the strip instead")]
+    pub vertical: bool,
+
+    /// Minimize both dimensions of the strip (optional)
+    #[arg(long, value_enum, conflicts_with_all = &["sheet_width", "container_width", "fill_container", "initial_solution", "checkpoint", "resume"],
+        help = "Minimize the area or perimeter of the strip by shrinking both its width and height, instead of only the width")]
+    pub open_dimension: Option<OpenObjective>,
+
+    /// Maximum aspect ratio of the strip in open dimension mode (optional)
+    #[arg(long, requires = "open_dimension", help = "Maximum ratio between the longest and shortest side of the strip")]
+    pub max_aspect_ratio: Option<f32>,
+
+    /// Minimum distance between items (optional)
+    #[arg(long, help = "Minimum distance between any two items, e.g. the kerf of the cutting process (overrides the configuration)")]
+    pub spacing: Option<f32>,
+
+    /// Minimum distance between items and the edges of the strip (optional)
+    #[arg(long, conflicts_with_all = &["sheet_width", "container_width", "fill_container"], help = "Minimum distance between the items and the edges of the strip (overrides the configuration)")]
+    pub margin: Option<f32>,
+
+    /// Number of concurrent runs of a portfolio (optional)
+    #[arg(long, num_args = 0..=1, default_missing_value = "0", conflicts_with_all = &["sheet_width", "container_width", "fill_container", "live", "checkpoint", "resume"],
+        help = "Run a number of independently seeded optimizations concurrently and keep the best (as many as the physical cores allow for the number of workers if no number is given)")]
+    pub portfolio: Option<usize>,
+
+    /// Share the best solution between the runs of the portfolio
+    #[arg(long, requires = "portfolio", help = "Let runs of the portfolio which fall behind restart from the best solution found by any run")]
+    pub share_best: bool,
+
+    /// Path to a JSON or TOML configuration file (optional)
+    #[arg(long = "config", help = "Path to a JSON or TOML file overriding the default configuration")]
+    pub config_file: Option<String>,
+
+    /// Path to a solution to start from (optional)
+    #[arg(long, conflicts_with = "resume", help = "Path to a JSON or SVG solution to warm start from (instead of constructing one)")]
+    pub initial_solution: Option<String>,
+
+    /// Path to periodically write checkpoints to (optional)
+    #[arg(long, help = "Path to periodically write a checkpoint of the run to")]
+    pub checkpoint: Option<String>,
+
+    /// Interval between checkpoints in seconds
+    #[arg(long, default_value_t = 300, help = "Interval between two checkpoints (in seconds)")]
+    pub checkpoint_interval: u64,
+
+    /// Path to a checkpoint to resume from (optional)
+    #[arg(long, help = "Resume a run from a checkpoint (time limits are taken from the checkpoint)")]
+    pub resume: Option<String>,
+}
+
+#[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct BenchCli {
+    #[command(subcommand)]
+    pub command: Option<BenchCommand>,
+
+    #[command(flatten)]
+    pub run: BenchRunArgs,
+}
+
+#[derive(Subcommand)]
+pub enum BenchCommand {
+    /// Compares the runs of two bench reports, instance by instance
+    Compare {
+        /// Path to the JSON report of the baseline
+        baseline: String,
+
+        /// Path to the JSON report of the candidate
+        candidate: String,
+
+        /// Significance level of the Mann-Whitney U test
+        #[arg(long, default_value_t = 0.05, help = "Significance level of the Mann-Whitney U test")]
+        alpha: f32,
+    },
+}
+
+#[derive(Args)]
+pub struct BenchRunArgs {
+    /// Instances to benchmark: JSON files, glob patterns, directories or manifest files
+    #[arg(required_unless_present = "bundled",
+        help = "Instances to benchmark: JSON files, glob patterns (e.g. 'data/input/*.json'), directories or manifest files (.txt, one source per line)")]
+    pub instances: Vec<String>,
+
+    /// Benchmark the instances bundled with the repository
+    #[arg(long, help = "Add all instances bundled with the repository (data/input) to the benchmark")]
+    pub bundled: bool,
+
+    /// Time limit per run in seconds
+    #[arg(short = 't', long, default_value_t = 600, help = "Time limit of every run (in seconds)")]
+    pub time_limit: u64,
+
+    /// Number of runs per instance
+    #[arg(short = 'r', long, default_value_t = 1, help = "Number of runs per instance")]
+    pub runs: usize,
+
+    /// Seed from which the seeds of all runs are derived (optional)
+    #[arg(short = 's', long, help = "Seed for the random number generator, the seed of every run is derived from it (overrides the configuration)")]
+    pub seed: Option<u64>,
+
+    /// Number of separator workers per run (optional)
+    #[arg(short = 'w', long, help = "Number of separator workers of every run (overrides the configuration)")]
+    pub workers: Option<usize>,
+
+    /// Path to a JSON or TOML configuration file (optional)
+    #[arg(long = "config", help = "Path to a JSON or TOML file overriding the default configuration")]
+    pub config_file: Option<String>,
+
+    /// Folder to write the solutions and reports to
+    #[arg(short = 'o', long, default_value = crate::config::OUTPUT_DIR, help = "Folder to write the solutions and the report to")]
+    pub output_dir: String,
+
+    /// Format of the intermediate solutions of every run, the final solutions are always exported as SVG
+    #[arg(long, value_enum, default_value_t = IntermediateExport::Svg, help = "Format to export intermediate solutions in")]
+    pub intermediate: IntermediateExport,
 }
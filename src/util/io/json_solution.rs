@@ -0,0 +1,173 @@
+use crate::optimizer::mirror::MirrorVariants;
+use crate::EPOCH;
+use jagua_rs::collision_detection::CDEConfig;
+use jagua_rs::entities::general::{Instance, Layout, PItemKey};
+use jagua_rs::entities::strip_packing::{SPInstance, SPPlacement, SPProblem, SPSolution};
+use jagua_rs::geometry::DTransformation;
+use jagua_rs::geometry::geo_traits::Shape;
+use jagua_rs::io::parser;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// Representation of a strip packing solution, following the output schema of [`jagua_rs`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct JsonSolution {
+    /// Name of the instance the solution belongs to
+    pub name: String,
+    /// Layouts of the solution, exactly one for strip packing and one per sheet for bin packing
+    pub layouts: Vec<JsonLayout>,
+    /// Ratio of the total item area to the area of the strip (or all sheets)
+    pub density: f32,
+    /// Seconds elapsed since the start of the program when the solution was found
+    pub run_time_sec: f32,
+    /// Wall-clock time at which the solution was exported
+    pub timestamp: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct JsonLayout {
+    pub container: JsonContainer,
+    pub placed_items: Vec<JsonPlacedItem>,
+    pub statistics: JsonLayoutStats,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "Type", content = "Params")]
+pub enum JsonContainer {
+    #[serde(rename_all = "PascalCase")]
+    Strip { width: f32, height: f32 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct JsonPlacedItem {
+    /// Index of the item in the instance
+    pub index: usize,
+    /// Whether the original shape is reflected across the y-axis (x -> -x), before the transformation is applied
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub mirrored: bool,
+    pub transformation: JsonTransformation,
+}
+
+/// Transformation applied to the original shape of the item: first rotated, then translated.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct JsonTransformation {
+    /// Rotation in degrees
+    pub rotation: f32,
+    pub translation: (f32, f32),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct JsonLayoutStats {
+    pub density: f32,
+}
+
+/// Mirrored variants of items are reported as their original item, with the `Mirrored` flag set.
+pub fn compose_json_solution(solution: &SPSolution, instance: &SPInstance, mirrors: &MirrorVariants, name: &str) -> JsonSolution {
+    let layout = Layout::from_snapshot(&solution.layout_snapshot);
+    compose_json_solution_from_layout(&layout, solution.strip_width, instance, mirrors, name, solution.time_stamp)
+}
+
+/// Same as [`compose_json_solution`], but directly from a [`Layout`].
+/// The placed items are listed in the same order as they are stored in the layout.
+pub fn compose_json_solution_from_layout(layout: &Layout, strip_width: f32, instance: &SPInstance, mirrors: &MirrorVariants, name: &str, time_stamp: Instant) -> JsonSolution {
+    let density = layout.density(instance);
+
+    let placed_items = layout.placed_items().values()
+        .map(|pi| {
+            let item = instance.item(pi.item_id);
+            // convert the internal transformation to one applicable to the original shape
+            let abs_transf = parser::internal_to_absolute_transform(&pi.d_transf, &item.shape_orig.pre_transform);
+            // the shape of a variant is the reflected shape of the original, so the transformation applies to both
+            JsonPlacedItem {
+                index: mirrors.original(pi.item_id),
+                mirrored: mirrors.is_variant(pi.item_id),
+                transformation: JsonTransformation {
+                    rotation: abs_transf.rotation().to_degrees(),
+                    translation: abs_transf.translation(),
+                },
+            }
+        })
+        .collect();
+
+    JsonSolution {
+        name: name.to_string(),
+        layouts: vec![JsonLayout {
+            container: JsonContainer::Strip {
+                width: strip_width,
+                height: layout.bin.outer_cd.bbox().height(),
+            },
+            placed_items,
+            statistics: JsonLayoutStats { density },
+        }],
+        density,
+        run_time_sec: time_stamp.duration_since(*EPOCH).as_secs_f32(),
+        timestamp: jiff::Timestamp::now().to_string(),
+    }
+}
+
+/// Grows the containers of the solution by `margin` on all sides and moves the items along,
+/// converting a solution of the usable area of a strip back to the full strip.
+pub fn expand_by_margin(json_sol: &mut JsonSolution, margin: f32) {
+    let mut item_area = 0.0;
+    let mut total_area = 0.0;
+    for json_layout in json_sol.layouts.iter_mut() {
+        let JsonContainer::Strip { width, height } = json_layout.container;
+        let (new_width, new_height) = (width + 2.0 * margin, height + 2.0 * margin);
+        json_layout.container = JsonContainer::Strip { width: new_width, height: new_height };
+        for json_pi in json_layout.placed_items.iter_mut() {
+            let (x, y) = json_pi.transformation.translation;
+            json_pi.transformation.translation = (x + margin, y + margin);
+        }
+        item_area += json_layout.statistics.density * width * height;
+        total_area += new_width * new_height;
+        json_layout.statistics.density *= (width * height) / (new_width * new_height);
+    }
+    json_sol.density = item_area / total_area;
+}
+
+/// Restores a [`JsonSolution`] into a new [`SPProblem`] at the strip width of the solution.
+/// Items are placed in the order they are listed in the solution.
+/// Items of the solution which are not (or no longer) part of the instance are skipped, as are mirrored items which do not allow mirroring.
+pub fn build_sp_problem(json_sol: &JsonSolution, instance: &SPInstance, mirrors: &MirrorVariants, cde_config: CDEConfig) -> SPProblem {
+    build_sp_problem_with_keys(json_sol, instance, mirrors, cde_config).0
+}
+
+/// Same as [`build_sp_problem`], also returning the key of every placed item of the solution in the order they are listed (`None` if it was skipped).
+pub fn build_sp_problem_with_keys(json_sol: &JsonSolution, instance: &SPInstance, mirrors: &MirrorVariants, cde_config: CDEConfig) -> (SPProblem, Vec<Option<PItemKey>>) {
+    let json_layout = json_sol.layouts.first().expect("solution does not contain a layout");
+    let JsonContainer::Strip { width, height } = json_layout.container;
+    if height != instance.strip_height {
+        warn!("[IO] solution strip height ({height}) differs from instance strip height ({})", instance.strip_height);
+    }
+
+    let mut prob = SPProblem::new(instance.clone(), width, cde_config);
+
+    let keys = json_layout.placed_items.iter()
+        .map(|json_pi| {
+            if json_pi.index >= instance.items().len() || mirrors.is_variant(json_pi.index) {
+                warn!("[IO] skipping item with unknown id {} in solution", json_pi.index);
+                return None;
+            }
+            let Some(item_id) = mirrors.variant(json_pi.index, json_pi.mirrored) else {
+                warn!("[IO] skipping mirrored item with id {} in solution, it does not allow mirroring", json_pi.index);
+                return None;
+            };
+            let item = instance.item(item_id);
+            let abs_transf = DTransformation::new(
+                json_pi.transformation.rotation.to_radians(),
+                json_pi.transformation.translation,
+            );
+            // convert the transformation of the original shape to one applicable to the internal shape
+            let d_transf = parser::absolute_to_internal_transform(&abs_transf, &item.shape_orig.pre_transform);
+            Some(prob.place_item(SPPlacement { item_id: item.id, d_transf }))
+        })
+        .collect();
+
+    (prob, keys)
+}
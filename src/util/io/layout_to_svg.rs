@@ -1,7 +1,8 @@
+use crate::optimizer::mirror::MirrorVariants;
+use crate::quantify::remove_irrelevant_hazards;
 use crate::util::io::svg_util::SvgDrawOptions;
 use crate::util::io::{svg_export, svg_util};
 use jagua_rs::io::parser;
-use log::warn;
 use std::hash::{DefaultHasher, Hash, Hasher};
 use jagua_rs::collision_detection::hazards::detector::{BasicHazardDetector, HazardDetector};
 use jagua_rs::collision_detection::hazards::filter::NoHazardFilter;
@@ -16,16 +17,19 @@ use svg::Document;
 pub fn s_layout_to_svg(
     s_layout: &LayoutSnapshot,
     instance: &impl Instance,
+    mirrors: &MirrorVariants,
     options: SvgDrawOptions,
     title: &str,
 ) -> Document {
     let layout = Layout::from_snapshot(s_layout);
-    layout_to_svg(&layout, instance, options, title)
+    layout_to_svg(&layout, instance, mirrors, options, title)
 }
 
+/// Mirrored variants of items are defined by their own (reflected) shape, but referred to by the id of their original item (`item_{id}_mirrored`).
 pub fn layout_to_svg(
     layout: &Layout,
     instance: &impl Instance,
+    mirrors: &MirrorVariants,
     options: SvgDrawOptions,
     title: &str,
 ) -> Document {
@@ -132,7 +136,7 @@ pub fn layout_to_svg(
                 None => theme.item_fill.to_owned(),
                 Some(q) => svg_util::blend_colors(theme.item_fill, theme.qz_fill[q]),
             };
-            item_defs = item_defs.add(Group::new().set("id", format!("item_{}", item.id)).add(
+            item_defs = item_defs.add(Group::new().set("id", item_def_id(item.id, mirrors)).add(
                 svg_export::data_to_path(
                     svg_export::original_shape_data(
                         &item.shape_orig,
@@ -237,10 +241,13 @@ pub fn layout_to_svg(
                     )
                 }
             };
-            let title = Title::new(format!("item, id: {}, transf: [{}]", pi.item_id, dtransf));
+            let title = match mirrors.is_variant(pi.item_id) {
+                true => Title::new(format!("item, id: {}, mirrored, transf: [{}]", mirrors.original(pi.item_id), dtransf)),
+                false => Title::new(format!("item, id: {}, transf: [{}]", pi.item_id, dtransf)),
+            };
             let pi_ref = Use::new()
                 .set("transform", transform_to_svg(dtransf))
-                .set("xlink:href", format!("#item_{}", pi.item_id))
+                .set("xlink:href", format!("#{}", item_def_id(pi.item_id, mirrors)))
                 .add(title);
 
             items_group = items_group.add(pi_ref);
@@ -314,6 +321,7 @@ pub fn layout_to_svg(
                     let mut detector = BasicHazardDetector::new();
                     layout.cde().collect_poly_collisions(pi.shape.as_ref(), &mut detector);
                     detector.remove(&HazardEntity::from((pk, pi)));
+                    remove_irrelevant_hazards(&mut detector, pi.hazard_filter.as_ref());
                     detector
                 };
                 for haz_entity in detector.iter() {
@@ -347,16 +355,14 @@ pub fn layout_to_svg(
                                 ));
                             }
                         }
-                        HazardEntity::BinExterior => {
+                        _ => {
+                            //collision with a hazard of the bin (exterior or quality zone)
                             collision_group = collision_group.add(svg_export::point(
                                 pi.shape.poi.center,
                                 Some(&*format!("{}", theme.collision_highlight_color)),
                                 Some(3.0 * stroke_width),
                             ));
                         }
-                        _ => {
-                            warn!("unexpected hazard entity");
-                        }
                     }
                 }
             }
@@ -380,6 +386,13 @@ pub fn layout_to_svg(
         .add(optionals)
         .add(label)
 }
+fn item_def_id(item_id: usize, mirrors: &MirrorVariants) -> String {
+    match mirrors.is_variant(item_id) {
+        true => format!("item_{}_mirrored", mirrors.original(item_id)),
+        false => format!("item_{}", item_id),
+    }
+}
+
 fn transform_to_svg(dt: DTransformation) -> String {
     //https://developer.mozilla.org/en-US/docs/Web/SVG/Attribute/transform
     //operations are effectively applied from right to left
@@ -2,31 +2,161 @@ use std::any::Any;
 use log::{log, Level, LevelFilter};
 use std::fs;
 use std::fs::File;
-use std::io::BufReader;
-use std::path::Path;
-use jagua_rs::entities::general::Instance;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use jagua_rs::entities::bin_packing::BPInstance;
+use jagua_rs::entities::general::{Bin, Instance};
 use jagua_rs::entities::strip_packing::SPInstance;
+use serde::Serialize;
 use svg::Document;
 
-use crate::config::OUTPUT_DIR;
+use crate::config::{SparrowConfig, DEFAULT_SPARROW_CONFIG, OUTPUT_DIR};
+use crate::optimizer::groups::JsonItemGroup;
 use crate::EPOCH;
+use crate::util::io::json_solution::JsonSolution;
+use crate::util::io::svg_import::svg_to_json_solution;
 use jagua_rs::io::json_instance::JsonInstance;
+use jagua_rs::io::parser::Parser;
 
 pub mod layout_to_svg;
 pub mod svg_export;
 pub mod svg_util;
 pub mod cli;
+pub mod json_solution;
+pub mod svg_import;
+pub mod sink;
+pub mod report;
 
 pub fn to_sp_instance(instance: &dyn Instance) -> Option<SPInstance>{
     (instance as &dyn Any).downcast_ref::<SPInstance>().cloned()
 }
 
 pub fn read_json_instance(path: &Path) -> JsonInstance {
+    read_instance_file(path).json_instance
+}
+
+/// Instance file, including the attributes sparrow reads on top of the [`JsonInstance`] of jagua-rs
+#[derive(Clone)]
+pub struct InstanceFile {
+    pub json_instance: JsonInstance,
+    /// Ids of the items which may also be placed mirrored (`"AllowMirror": true`), see [`MirrorVariants`](crate::optimizer::mirror::MirrorVariants)
+    pub mirrored_items: Vec<usize>,
+    /// Value of every item (`"Value"`), if given, which is maximized in knapsack mode
+    pub item_values: Vec<Option<f32>>,
+    /// Container to fill in knapsack mode (`"Container"`), a bin in the format of jagua-rs: its `Shape` (which may have holes) and inferior quality `Zones`
+    pub container: Option<serde_json::Value>,
+    /// Items which are placed as a single unit (`"Groups"`), every group lists its items like the placed items of a solution (`Index` and `Transformation`),
+    /// the transformations define their relative placement
+    pub groups: Vec<JsonItemGroup>,
+}
+
+pub fn read_instance_file(path: &Path) -> InstanceFile {
     let file = File::open(path)
         .unwrap_or_else(|err| panic!("could not open instance file: {}, {}", path.display(), err));
     let reader = BufReader::new(file);
-    serde_json::from_reader(reader)
-        .unwrap_or_else(|err| panic!("could not parse instance file: {}, {}", path.display(), err))
+    let value: serde_json::Value = serde_json::from_reader(reader)
+        .unwrap_or_else(|err| panic!("could not parse instance file: {}, {}", path.display(), err));
+    let mirrored_items = value["Items"].as_array().into_iter().flatten()
+        .enumerate()
+        .filter(|(_, item)| item["AllowMirror"].as_bool() == Some(true))
+        .map(|(i, _)| i)
+        .collect();
+    let item_values = value["Items"].as_array().into_iter().flatten()
+        .map(|item| item["Value"].as_f64().map(|v| v as f32))
+        .collect();
+    let container = value.get("Container").cloned();
+    let groups = match value.get("Groups") {
+        Some(groups) => serde_json::from_value(groups.clone())
+            .unwrap_or_else(|err| panic!("could not parse groups of instance file: {}, {}", path.display(), err)),
+        None => vec![],
+    };
+    let json_instance = serde_json::from_value(value)
+        .unwrap_or_else(|err| panic!("could not parse instance file: {}, {}", path.display(), err));
+    InstanceFile { json_instance, mirrored_items, item_values, container, groups }
+}
+
+/// Parses the container of an instance file (see [`InstanceFile::container`]) into a bin of any shape, with holes and inferior quality zones.
+/// jagua-rs parses the container as the only bin of a bin packing instance with the same items.
+pub fn parse_container(json_instance: &JsonInstance, container: &serde_json::Value, parser: &Parser) -> Bin {
+    let mut value = serde_json::to_value(json_instance)
+        .unwrap_or_else(|err| panic!("could not serialize instance: {}", err));
+    let mut json_bin = container.clone();
+    json_bin["Cost"] = serde_json::Value::from(0);
+    json_bin["Stock"] = serde_json::Value::from(1);
+    if let Some(fields) = value.as_object_mut() {
+        fields.remove("Strip");
+        fields.insert("Objects".to_string(), serde_json::Value::Array(vec![json_bin]));
+    }
+    let json_bp_instance: JsonInstance = serde_json::from_value(value)
+        .unwrap_or_else(|err| panic!("could not parse container: {}", err));
+
+    let any_instance = parser.parse(&json_bp_instance);
+    let bp_instance = (any_instance.as_ref() as &dyn Any).downcast_ref::<BPInstance>()
+        .expect("Expected BPInstance");
+    bp_instance.bins[0].0.clone()
+}
+
+/// Reads a solution from either a JSON file (see [`json_solution`]) or an SVG previously exported by sparrow.
+pub fn read_solution(path: &Path) -> JsonSolution {
+    let content = fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("could not open solution file: {}, {}", path.display(), err));
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("svg") => {
+            let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+            svg_to_json_solution(&content, name)
+        }
+        _ => serde_json::from_str(&content)
+            .unwrap_or_else(|err| panic!("could not parse solution file: {}, {}", path.display(), err)),
+    }
+}
+
+/// Format of a configuration file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+}
+
+/// Reads a [`SparrowConfig`] from a JSON or TOML file (determined by the file extension).
+pub fn read_sparrow_config(path: &Path) -> SparrowConfig {
+    let content = fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("could not open config file: {}, {}", path.display(), err));
+    let format = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => ConfigFormat::Toml,
+        _ => ConfigFormat::Json,
+    };
+    parse_sparrow_config(&content, format)
+        .unwrap_or_else(|err| panic!("could not parse config file: {}, {}", path.display(), err))
+}
+
+/// Parses a (partial) [`SparrowConfig`], every missing field falls back to its value in [`DEFAULT_SPARROW_CONFIG`],
+/// also within nested tables (e.g. a partial `cmpr_cfg.separator_config` falls back to [`SEP_CFG_COMPRESS`](crate::config::SEP_CFG_COMPRESS)).
+pub fn parse_sparrow_config(content: &str, format: ConfigFormat) -> Result<SparrowConfig, String> {
+    let partial = match format {
+        ConfigFormat::Toml => toml::from_str::<toml::Value>(content).map_err(|err| err.to_string())
+            .and_then(|value| serde_json::to_value(value).map_err(|err| err.to_string()))?,
+        ConfigFormat::Json => serde_json::from_str::<serde_json::Value>(content).map_err(|err| err.to_string())?,
+    };
+    let mut config = serde_json::to_value(DEFAULT_SPARROW_CONFIG).map_err(|err| err.to_string())?;
+    merge_json(&mut config, partial);
+    serde_json::from_value(config).map_err(|err| err.to_string())
+}
+
+/// Overwrites the fields of `base` with the ones present in `partial`, recursing into nested objects
+fn merge_json(base: &mut serde_json::Value, partial: serde_json::Value) {
+    match (base, partial) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(partial)) => {
+            for (key, value) in partial {
+                match base.get_mut(&key) {
+                    Some(base_value) => merge_json(base_value, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, partial) => *base = partial,
+    }
 }
 
 pub fn init_logger(level_filter: LevelFilter) {
@@ -77,4 +207,41 @@ pub fn write_svg(document: &Document, path: &Path, log_lvl: Level) {
             .to_str()
             .unwrap()
     );
-}
\ No newline at end of file
+}
+
+/// Same as [`write_json`], but the file at `path` is only replaced once the json is completely written (to `{path}.tmp`).
+/// A write which is interrupted or fails leaves the previous file intact.
+pub fn write_json_atomic(json: &impl Serialize, path: &Path, log_lvl: Level) {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+    let mut writer = BufWriter::new(File::create(&tmp_path)
+        .unwrap_or_else(|err| panic!("could not create json file: {}, {}", tmp_path.display(), err)));
+    serde_json::to_writer_pretty(&mut writer, json).expect("failed to write json file");
+    writer.into_inner()
+        .unwrap_or_else(|err| panic!("could not write json file: {}, {}", tmp_path.display(), err))
+        .sync_all()
+        .unwrap_or_else(|err| panic!("could not write json file: {}, {}", tmp_path.display(), err));
+    fs::rename(&tmp_path, path)
+        .unwrap_or_else(|err| panic!("could not replace json file: {}, {}", path.display(), err));
+    log!(log_lvl,
+        "[IO] json exported to file://{}",
+        fs::canonicalize(&path)
+            .expect("could not canonicalize path")
+            .to_str()
+            .unwrap()
+    );
+}
+
+pub fn write_json(json: &impl Serialize, path: &Path, log_lvl: Level) {
+    let file = File::create(path)
+        .unwrap_or_else(|err| panic!("could not create json file: {}, {}", path.display(), err));
+    serde_json::to_writer_pretty(file, json).expect("failed to write json file");
+    log!(log_lvl,
+        "[IO] json exported to file://{}",
+        fs::canonicalize(&path)
+            .expect("could not canonicalize path")
+            .to_str()
+            .unwrap()
+    );
+}
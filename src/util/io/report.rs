@@ -0,0 +1,98 @@
+use crate::api::SparrowResult;
+use crate::util::io;
+use itertools::Itertools;
+use log::Level;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Outcome of a single benchmark run
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RunRecord {
+    pub instance: String,
+    /// Index of the run for this instance
+    pub run: usize,
+    pub seed: u64,
+    pub width: f32,
+    pub density: f32,
+    /// Density of the best solution at the end of the exploration phase
+    pub explore_density: f32,
+    pub explore_secs: f32,
+    pub compress_secs: f32,
+    pub explore_evals: usize,
+    pub compress_evals: usize,
+    pub evals_per_sec: f32,
+}
+
+impl RunRecord {
+    pub fn new(run: usize, result: &SparrowResult) -> Self {
+        let stats = &result.stats;
+        let total_secs = (stats.explore_time + stats.compress_time).as_secs_f32();
+        Self {
+            instance: result.name.clone(),
+            run,
+            seed: result.seed,
+            width: result.solution.strip_width,
+            density: stats.final_density,
+            explore_density: stats.explore_density,
+            explore_secs: stats.explore_time.as_secs_f32(),
+            compress_secs: stats.compress_time.as_secs_f32(),
+            explore_evals: stats.explore_evals,
+            compress_evals: stats.compress_evals,
+            evals_per_sec: (stats.explore_evals + stats.compress_evals) as f32 / total_secs,
+        }
+    }
+}
+
+/// Results of a benchmark, written as JSON (to be read back) and CSV (to be diffed or plotted)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BenchReport {
+    /// Commit the benchmark was run on
+    pub git_hash: String,
+    pub timestamp: String,
+    pub time_limit_secs: u64,
+    /// Number of separator workers of every run
+    pub n_workers: usize,
+    pub runs: Vec<RunRecord>,
+}
+
+impl BenchReport {
+    /// One row per run, the git hash is repeated on every row so reports of different commits can be concatenated
+    pub fn to_csv(&self) -> String {
+        let header = "git_hash,instance,run,seed,width,density,explore_density,explore_secs,compress_secs,explore_evals,compress_evals,evals_per_sec";
+        let rows = self.runs.iter().map(|r| {
+            format!("{},{},{},{},{:.4},{:.6},{:.6},{:.3},{:.3},{},{},{:.1}",
+                self.git_hash, r.instance, r.run, r.seed, r.width, r.density, r.explore_density,
+                r.explore_secs, r.compress_secs, r.explore_evals, r.compress_evals, r.evals_per_sec)
+        });
+        std::iter::once(header.to_string()).chain(rows).join("\n") + "\n"
+    }
+
+    pub fn read(path: &Path) -> Self {
+        let content = fs::read_to_string(path)
+            .unwrap_or_else(|err| panic!("could not open report file: {}, {}", path.display(), err));
+        serde_json::from_str(&content)
+            .unwrap_or_else(|err| panic!("could not parse report file: {}, {}", path.display(), err))
+    }
+
+    /// Densities of all runs of an instance
+    pub fn densities(&self, instance: &str) -> Vec<f32> {
+        self.runs.iter()
+            .filter(|r| r.instance == instance)
+            .map(|r| r.density)
+            .collect()
+    }
+
+    /// Names of the instances in the report, in order of their first run
+    pub fn instances(&self) -> Vec<String> {
+        self.runs.iter().map(|r| r.instance.clone()).unique().collect()
+    }
+
+    /// Writes the report to `{name}.json` and `{name}.csv` in the folder
+    pub fn write(&self, folder: &Path, name: &str) {
+        io::write_json(self, &folder.join(format!("{name}.json")), Level::Info);
+        let csv_path = folder.join(format!("{name}.csv"));
+        fs::write(&csv_path, self.to_csv())
+            .unwrap_or_else(|err| panic!("could not write csv file: {}, {}", csv_path.display(), err));
+    }
+}
@@ -0,0 +1,189 @@
+use crate::optimizer::mirror::MirrorVariants;
+use crate::util::io;
+use crate::util::io::json_solution::compose_json_solution;
+use crate::util::io::layout_to_svg::s_layout_to_svg;
+use crate::util::io::svg_util::SvgDrawOptions;
+use jagua_rs::entities::strip_packing::{SPInstance, SPSolution};
+use log::Level;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Kind of solution passed to a [`SolutionSink`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportKind {
+    /// Layout in the middle of a separation, only relevant for live viewing
+    Live,
+    /// Solution reached at a step of the optimization (not necessarily feasible)
+    Intermediate,
+    /// Final solution of the run
+    Final,
+}
+
+/// Destination for the solutions produced during an optimization run.
+pub trait SolutionSink: Send {
+    /// Whether the sink is interested in solutions of this kind, nothing is composed for it otherwise
+    fn accepts(&self, kind: ExportKind) -> bool;
+
+    /// `mirrors` relates the mirrored variants in the instance to their original items.
+    /// `log_lvl` is the level at which the run is logging, for sinks reporting what they wrote
+    fn export(&mut self, solution: &SPSolution, instance: &SPInstance, mirrors: &MirrorVariants, label: &str, kind: ExportKind, log_lvl: Level);
+}
+
+/// Discards all solutions
+pub struct NoSink;
+
+impl SolutionSink for NoSink {
+    fn accepts(&self, _kind: ExportKind) -> bool {
+        false
+    }
+
+    fn export(&mut self, _solution: &SPSolution, _instance: &SPInstance, _mirrors: &MirrorVariants, _label: &str, _kind: ExportKind, _log_lvl: Level) {}
+}
+
+/// Forwards solutions to each of the sinks accepting them
+impl SolutionSink for Vec<Box<dyn SolutionSink>> {
+    fn accepts(&self, kind: ExportKind) -> bool {
+        self.iter().any(|s| s.accepts(kind))
+    }
+
+    fn export(&mut self, solution: &SPSolution, instance: &SPInstance, mirrors: &MirrorVariants, label: &str, kind: ExportKind, log_lvl: Level) {
+        self.iter_mut()
+            .filter(|s| s.accepts(kind))
+            .for_each(|s| s.export(solution, instance, mirrors, label, kind, log_lvl));
+    }
+}
+
+/// Writes every intermediate solution as a numbered SVG to a folder.
+/// Existing SVGs in the folder are removed upon the first export.
+pub struct SvgDirSink {
+    folder: PathBuf,
+    counter: usize,
+    draw_options: SvgDrawOptions,
+}
+
+impl SvgDirSink {
+    pub fn new(folder: impl Into<PathBuf>, draw_options: SvgDrawOptions) -> Self {
+        Self {
+            folder: folder.into(),
+            counter: 0,
+            draw_options,
+        }
+    }
+}
+
+impl SolutionSink for SvgDirSink {
+    fn accepts(&self, kind: ExportKind) -> bool {
+        kind == ExportKind::Intermediate
+    }
+
+    fn export(&mut self, solution: &SPSolution, instance: &SPInstance, mirrors: &MirrorVariants, label: &str, _kind: ExportKind, log_lvl: Level) {
+        if self.counter == 0 {
+            prepare_folder(&self.folder, "svg");
+        }
+        let file_name = format!("{}_{:.3}_{label}", self.counter, solution.strip_width);
+        let svg = s_layout_to_svg(&solution.layout_snapshot, instance, mirrors, self.draw_options, file_name.as_str());
+        io::write_svg(&svg, &self.folder.join(format!("{file_name}.svg")), log_lvl);
+        self.counter += 1;
+    }
+}
+
+/// Continuously overwrites a single SVG file with the latest solution, for the visualizer
+pub struct LiveSvgSink {
+    path: PathBuf,
+    draw_options: SvgDrawOptions,
+}
+
+impl LiveSvgSink {
+    pub fn new(path: impl Into<PathBuf>, draw_options: SvgDrawOptions) -> Self {
+        Self {
+            path: path.into(),
+            draw_options,
+        }
+    }
+}
+
+impl SolutionSink for LiveSvgSink {
+    fn accepts(&self, _kind: ExportKind) -> bool {
+        true
+    }
+
+    fn export(&mut self, solution: &SPSolution, instance: &SPInstance, mirrors: &MirrorVariants, label: &str, _kind: ExportKind, _log_lvl: Level) {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        let svg = s_layout_to_svg(&solution.layout_snapshot, instance, mirrors, self.draw_options, label);
+        io::write_svg(&svg, &self.path, Level::Trace);
+    }
+}
+
+/// Writes every intermediate solution as a numbered JSON solution to a folder.
+/// Existing JSON files in the folder are removed upon the first export.
+pub struct JsonDirSink {
+    folder: PathBuf,
+    counter: usize,
+    instance_name: String,
+}
+
+impl JsonDirSink {
+    pub fn new(folder: impl Into<PathBuf>, instance_name: impl Into<String>) -> Self {
+        Self {
+            folder: folder.into(),
+            counter: 0,
+            instance_name: instance_name.into(),
+        }
+    }
+}
+
+impl SolutionSink for JsonDirSink {
+    fn accepts(&self, kind: ExportKind) -> bool {
+        kind == ExportKind::Intermediate
+    }
+
+    fn export(&mut self, solution: &SPSolution, instance: &SPInstance, mirrors: &MirrorVariants, label: &str, _kind: ExportKind, log_lvl: Level) {
+        if self.counter == 0 {
+            prepare_folder(&self.folder, "json");
+        }
+        let json_sol = compose_json_solution(solution, instance, mirrors, &self.instance_name);
+        let file_name = format!("{}_{:.3}_{label}.json", self.counter, solution.strip_width);
+        io::write_json(&json_sol, &self.folder.join(file_name), log_lvl);
+        self.counter += 1;
+    }
+}
+
+/// Keeps all intermediate and final solutions in memory.
+/// Clones share the same storage, keep one to access the solutions after the run.
+#[derive(Clone, Default)]
+pub struct MemorySink {
+    solutions: Arc<Mutex<Vec<(String, SPSolution)>>>,
+}
+
+impl MemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Removes and returns all solutions collected so far (with their labels)
+    pub fn take(&self) -> Vec<(String, SPSolution)> {
+        std::mem::take(&mut *self.solutions.lock().unwrap())
+    }
+}
+
+impl SolutionSink for MemorySink {
+    fn accepts(&self, kind: ExportKind) -> bool {
+        kind != ExportKind::Live
+    }
+
+    fn export(&mut self, solution: &SPSolution, _instance: &SPInstance, _mirrors: &MirrorVariants, label: &str, _kind: ExportKind, _log_lvl: Level) {
+        self.solutions.lock().unwrap().push((label.to_string(), solution.clone()));
+    }
+}
+
+/// Creates the folder if needed and removes all files with the extension from it. ONLY THOSE FILES
+fn prepare_folder(folder: &Path, extension: &str) {
+    std::fs::create_dir_all(folder).unwrap();
+    for file in std::fs::read_dir(folder).unwrap().flatten() {
+        if file.path().extension().unwrap_or_default() == extension {
+            std::fs::remove_file(file.path()).unwrap();
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::util::io::json_solution::{JsonContainer, JsonLayout, JsonLayoutStats, JsonPlacedItem, JsonSolution, JsonTransformation};
+use svg::parser::Event;
+
+/// Reconstructs a [`JsonSolution`] from an SVG exported by sparrow (see [`crate::util::io::layout_to_svg`]).
+/// Relies on the `<use transform=... xlink:href="#item_{id}">` elements (`#item_{id}_mirrored` for mirrored items) and the label with the strip dimensions.
+pub fn svg_to_json_solution(svg_content: &str, name: &str) -> JsonSolution {
+    let mut placed_items = vec![];
+    let mut dims = None;
+
+    for event in svg::read(svg_content).expect("could not parse svg") {
+        match event {
+            Event::Tag("use", _, attributes) => {
+                let href = attributes.get("xlink:href").map(|v| v.to_string()).unwrap_or_default();
+                // only the items themselves, not their surrogates or cd shapes
+                if let Some(item_id) = href.strip_prefix("#item_") {
+                    let (item_id, mirrored) = match item_id.strip_suffix("_mirrored") {
+                        Some(item_id) => (item_id, true),
+                        None => (item_id, false),
+                    };
+                    let index = item_id.parse().expect("invalid item id in svg");
+                    let transform = attributes.get("transform").expect("item without transform in svg");
+                    placed_items.push(JsonPlacedItem {
+                        index,
+                        mirrored,
+                        transformation: parse_svg_transform(transform),
+                    });
+                }
+            }
+            Event::Text(text) if text.trim_start().starts_with("height:") => {
+                dims = Some(parse_label_dims(text));
+            }
+            _ => {}
+        }
+    }
+
+    let (height, width) = dims.expect("svg does not contain a label with the strip dimensions");
+
+    JsonSolution {
+        name: name.to_string(),
+        layouts: vec![JsonLayout {
+            container: JsonContainer::Strip { width, height },
+            placed_items,
+            statistics: JsonLayoutStats { density: 0.0 },
+        }],
+        density: 0.0,
+        run_time_sec: 0.0,
+        timestamp: jiff::Timestamp::now().to_string(),
+    }
+}
+
+/// Parses a transform of the form `translate(tx ty), rotate(r)`
+fn parse_svg_transform(transform: &str) -> JsonTransformation {
+    let args_of = |op: &str| -> Vec<f32> {
+        let start = transform.find(op).map(|i| i + op.len() + 1)
+            .unwrap_or_else(|| panic!("no {op} in svg transform: {transform}"));
+        let end = start + transform[start..].find(')').expect("unterminated svg transform");
+        transform[start..end].split(|c: char| c == ' ' || c == ',')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse().expect("invalid number in svg transform"))
+            .collect()
+    };
+    let translation = args_of("translate");
+    let rotation = args_of("rotate");
+
+    JsonTransformation {
+        rotation: rotation[0],
+        translation: (translation[0], translation[1]),
+    }
+}
+
+/// Parses the label of the form `height: {h} | width: {w} | ...`
+fn parse_label_dims(label: &str) -> (f32, f32) {
+    let value_of = |key: &str| -> f32 {
+        label.split('|')
+            .find_map(|part| part.trim().strip_prefix(key))
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or_else(|| panic!("could not parse {key} from svg label: {label}"))
+    };
+    (value_of("height:"), value_of("width:"))
+}
@@ -2,3 +2,4 @@ pub mod assertions;
 
 pub mod io;
 pub mod bit_reversal_iterator;
+pub mod validation;
@@ -0,0 +1,206 @@
+use crate::config::VALIDATION_TOLERANCE_RATIO;
+use crate::util::io::json_solution::{JsonContainer, JsonLayout, JsonSolution};
+use itertools::Itertools;
+use jagua_rs::entities::general::Instance;
+use jagua_rs::entities::strip_packing::SPInstance;
+use std::fmt::{Display, Formatter};
+
+/// Violation of the spacing or margin requirements found by [`validate_solution`].
+/// Items are referred to by their position in the placed items of the layout.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Violation {
+    /// Two items overlap
+    Overlap { layout: usize, items: (usize, usize) },
+    /// Two items are closer to each other than the required spacing
+    Spacing { layout: usize, items: (usize, usize), distance: f64 },
+    /// An item is closer to the boundary of the container than the required margin (negative if it lies partially outside)
+    Margin { layout: usize, item: usize, distance: f64 },
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::Overlap { layout, items } => write!(f, "layout {layout}: items {} and {} overlap", items.0, items.1),
+            Violation::Spacing { layout, items, distance } => write!(f, "layout {layout}: items {} and {} are {distance:.6} apart", items.0, items.1),
+            Violation::Margin { layout, item, distance } => write!(f, "layout {layout}: item {item} is {distance:.6} from the edge"),
+        }
+    }
+}
+
+type Poly = Vec<(f64, f64)>;
+
+/// Exact check of an exported solution against the original polygons of the items (not the simplified or inflated ones).
+/// Every pair of items must be at least `spacing` apart and every item at least `margin` from the boundary of the container (any edge, not only its vertices).
+/// Computations are done in double precision, distances are allowed to fall short by [`VALIDATION_TOLERANCE_RATIO`] of the container.
+pub fn validate_solution(json_sol: &JsonSolution, instance: &SPInstance, spacing: f32, margin: f32) -> Vec<Violation> {
+    json_sol.layouts.iter().enumerate()
+        .flat_map(|(i, json_layout)| validate_layout(i, json_layout, instance, spacing as f64, margin as f64))
+        .collect()
+}
+
+fn validate_layout(layout_idx: usize, json_layout: &JsonLayout, instance: &SPInstance, spacing: f64, margin: f64) -> Vec<Violation> {
+    let container = container_poly(&json_layout.container);
+    let (x_min, y_min, x_max, y_max) = bbox(&container);
+    let tolerance = (x_max - x_min).max(y_max - y_min) * VALIDATION_TOLERANCE_RATIO as f64;
+
+    let polys = json_layout.placed_items.iter()
+        .map(|json_pi| {
+            let original = &instance.item(json_pi.index).shape_orig.shape;
+            let (sin, cos) = (json_pi.transformation.rotation as f64).to_radians().sin_cos();
+            let (tx, ty) = (json_pi.transformation.translation.0 as f64, json_pi.transformation.translation.1 as f64);
+            //mirrored items are reflected across the y-axis before they are transformed
+            let reflection = if json_pi.mirrored { -1.0 } else { 1.0 };
+            (0..original.n_vertices())
+                .map(|i| {
+                    let v = original.vertex(i);
+                    let (x, y) = (reflection * v.0 as f64, v.1 as f64);
+                    (cos * x - sin * y + tx, sin * x + cos * y + ty)
+                })
+                .collect_vec()
+        })
+        .collect_vec();
+
+    let mut violations = vec![];
+
+    for (i, poly) in polys.iter().enumerate() {
+        let distance = boundary_distance(poly, &container);
+        if distance < margin - tolerance {
+            violations.push(Violation::Margin { layout: layout_idx, item: i, distance });
+        }
+    }
+
+    let bboxes = polys.iter().map(|p| bbox(p)).collect_vec();
+    for (i, j) in (0..polys.len()).tuple_combinations() {
+        if bbox_distance(bboxes[i], bboxes[j]) >= spacing {
+            continue;
+        }
+        match poly_distance(&polys[i], &polys[j]) {
+            None => violations.push(Violation::Overlap { layout: layout_idx, items: (i, j) }),
+            Some(distance) if distance < spacing - tolerance => {
+                violations.push(Violation::Spacing { layout: layout_idx, items: (i, j), distance })
+            }
+            Some(_) => {}
+        }
+    }
+
+    violations
+}
+
+/// Outline of the container as a simple polygon, the checks below do not assume it is rectangular (or convex)
+fn container_poly(container: &JsonContainer) -> Poly {
+    match *container {
+        JsonContainer::Strip { width, height } => {
+            let (width, height) = (width as f64, height as f64);
+            vec![(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)]
+        }
+    }
+}
+
+/// Distance between a polygon and the boundary of the container it should lie in.
+/// Negative if a vertex lies outside the container: minus the distance of the vertex furthest outside.
+fn boundary_distance(poly: &Poly, container: &Poly) -> f64 {
+    let container_edges = edges(container);
+    let edge_distance = |v: (f64, f64)| container_edges.iter()
+        .map(|&(a, b)| point_segment_distance(v, a, b))
+        .fold(f64::INFINITY, f64::min);
+
+    let max_depth = poly.iter()
+        .filter(|&&v| !point_in_poly(v, container))
+        .map(|&v| edge_distance(v))
+        .max_by(f64::total_cmp);
+    if let Some(depth) = max_depth {
+        return -depth;
+    }
+
+    //all vertices are inside, but an edge can still leave a non-convex container in between
+    edges(poly).into_iter()
+        .cartesian_product(container_edges.iter())
+        .map(|(e1, &e2)| segment_distance(e1, e2))
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Distance between two simple polygons, `None` if they overlap
+fn poly_distance(p: &Poly, q: &Poly) -> Option<f64> {
+    let (p_edges, q_edges) = (edges(p), edges(q));
+
+    if p_edges.iter().cartesian_product(q_edges.iter()).any(|(e1, e2)| segments_intersect(*e1, *e2)) {
+        return None;
+    }
+    //no edges intersect, so either one polygon contains the other, or they are disjoint
+    if point_in_poly(p[0], q) || point_in_poly(q[0], p) {
+        return None;
+    }
+
+    //the closest points of two disjoint polygons always include a vertex of one of them
+    let min_dist = |vertices: &Poly, edges: &[((f64, f64), (f64, f64))]| {
+        vertices.iter()
+            .flat_map(|&v| edges.iter().map(move |&(a, b)| point_segment_distance(v, a, b)))
+            .fold(f64::INFINITY, f64::min)
+    };
+    Some(min_dist(p, &q_edges).min(min_dist(q, &p_edges)))
+}
+
+fn edges(poly: &Poly) -> Vec<((f64, f64), (f64, f64))> {
+    (0..poly.len()).map(|i| (poly[i], poly[(i + 1) % poly.len()])).collect_vec()
+}
+
+fn cross(o: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+}
+
+fn segments_intersect((a, b): ((f64, f64), (f64, f64)), (c, d): ((f64, f64), (f64, f64))) -> bool {
+    let (d1, d2) = (cross(c, d, a), cross(c, d, b));
+    let (d3, d4) = (cross(a, b, c), cross(a, b, d));
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0)) && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0)) {
+        return true;
+    }
+    //collinear or touching cases
+    let on_segment = |p: (f64, f64), q: (f64, f64), r: (f64, f64)| {
+        r.0 >= p.0.min(q.0) && r.0 <= p.0.max(q.0) && r.1 >= p.1.min(q.1) && r.1 <= p.1.max(q.1)
+    };
+    (d1 == 0.0 && on_segment(c, d, a)) || (d2 == 0.0 && on_segment(c, d, b)) ||
+        (d3 == 0.0 && on_segment(a, b, c)) || (d4 == 0.0 && on_segment(a, b, d))
+}
+
+fn point_in_poly(p: (f64, f64), poly: &Poly) -> bool {
+    //ray casting
+    let mut inside = false;
+    for i in 0..poly.len() {
+        let (a, b) = (poly[i], poly[(i + 1) % poly.len()]);
+        if (a.1 > p.1) != (b.1 > p.1) && p.0 < (b.0 - a.0) * (p.1 - a.1) / (b.1 - a.1) + a.0 {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn point_segment_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len_sq = dx * dx + dy * dy;
+    let t = match len_sq > 0.0 {
+        true => (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len_sq).clamp(0.0, 1.0),
+        false => 0.0,
+    };
+    let (cx, cy) = (a.0 + t * dx, a.1 + t * dy);
+    ((p.0 - cx).powi(2) + (p.1 - cy).powi(2)).sqrt()
+}
+
+fn segment_distance((a, b): ((f64, f64), (f64, f64)), (c, d): ((f64, f64), (f64, f64))) -> f64 {
+    match segments_intersect((a, b), (c, d)) {
+        true => 0.0,
+        false => point_segment_distance(a, c, d).min(point_segment_distance(b, c, d))
+            .min(point_segment_distance(c, a, b)).min(point_segment_distance(d, a, b)),
+    }
+}
+
+fn bbox(poly: &Poly) -> (f64, f64, f64, f64) {
+    poly.iter().fold((f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY), |(x_min, y_min, x_max, y_max), &(x, y)| {
+        (x_min.min(x), y_min.min(y), x_max.max(x), y_max.max(y))
+    })
+}
+
+fn bbox_distance(a: (f64, f64, f64, f64), b: (f64, f64, f64, f64)) -> f64 {
+    let dx = (b.0 - a.2).max(a.0 - b.2).max(0.0);
+    let dy = (b.1 - a.3).max(a.1 - b.3).max(0.0);
+    (dx * dx + dy * dy).sqrt()
+}
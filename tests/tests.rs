@@ -1,33 +1,80 @@
 #[cfg(test)]
 mod integration_tests {
+    use jagua_rs::entities::general::{Instance, Layout};
+    use jagua_rs::entities::strip_packing::{SPInstance, SPPlacement, SPProblem, SPSolution};
+    use jagua_rs::collision_detection::hazards::HazardEntity;
+    use jagua_rs::collision_detection::hazards::detector::{BasicHazardDetector, HazardDetector};
+    use jagua_rs::geometry::DTransformation;
+    use sparrow::quantify::remove_irrelevant_hazards;
+    use sparrow::quantify::tracker::CollisionTracker;
+    use jagua_rs::io::json_instance::JsonInstance;
     use jagua_rs::io::parser::Parser;
     use rand::prelude::SmallRng;
     use rand::SeedableRng;
-    use sparrow::config::{CDE_CONFIG, LBF_SAMPLE_CONFIG, OUTPUT_DIR, SEP_CFG_EXPLORE, SIMPL_TOLERANCE, MIN_ITEM_SEPARATION};
+    use sparrow::config::{SparrowConfig, CDE_CONFIG, COMPRESS_SHRINK_RANGE, DEFAULT_SPARROW_CONFIG, EXPLORE_SHRINK_STEP, OUTPUT_DIR, SEP_CFG_COMPRESS, SEP_CFG_EXPLORE, WEIGHT_MAX_INC_RATIO};
     use sparrow::optimizer::lbf::LBFBuilder;
     use sparrow::optimizer::separator::Separator;
-    use sparrow::optimizer::{compression_phase, exploration_phase, Terminator};
+    use sparrow::api::{run_portfolio, BuildError, Sparrow, SparrowBuilder};
+    use sparrow::optimizer::checkpoint::{read_checkpoint, Checkpointer};
+    use sparrow::optimizer::listener::OptimizationListener;
+    use sparrow::optimizer::bin_packing::{optimize_bin_packing, BinPackingError};
+    use sparrow::optimizer::knapsack::{optimize_knapsack, KnapsackContainer};
+    use sparrow::optimizer::strip::{solution_height, OpenObjective, StripMode};
+    use sparrow::optimizer::groups::{GroupInstances, ItemGroup, JsonItemGroup};
+    use sparrow::util::io::json_solution::{build_sp_problem, build_sp_problem_with_keys, compose_json_solution, JsonContainer, JsonPlacedItem, JsonSolution, JsonTransformation};
+    use sparrow::util::io::layout_to_svg::s_layout_to_svg;
+    use sparrow::util::io::svg_import::svg_to_json_solution;
+    use sparrow::optimizer::mirror::MirrorVariants;
+    use jagua_rs::geometry::geo_traits::Shape;
+    use sparrow::optimizer::{compression_phase, exploration_phase, Budget, ExplorationState, OptimizationResult, Phase, SepStats, Target, Terminator};
     use sparrow::util::io;
+    use sparrow::util::io::ConfigFormat;
     use std::path::Path;
+    use std::sync::{Arc, Mutex};
     use std::time::Duration;
     use test_case::test_case;
     use sparrow::util::io::to_sp_instance;
+    use sparrow::util::io::sink::{JsonDirSink, MemorySink, NoSink, SvgDirSink};
+    use sparrow::util::validation::{validate_solution, Violation};
+    use itertools::Itertools;
+    use std::collections::HashMap;
 
     const EXPLORE_TIMEOUT: Duration = Duration::from_secs(10);
     const COMPRESS_TIMEOUT: Duration = Duration::from_secs(10);
     const INSTANCE_BASE_PATH: &str = "data/input";
     const RNG_SEED: Option<usize> = Some(0); // fix seed for reproducibility
+    const EVAL_BUDGET: usize = 2_000_000;
+
+    fn read_instance(path: &str) -> JsonInstance {
+        io::read_json_instance(Path::new(&format!("{INSTANCE_BASE_PATH}/{path}")))
+    }
+
+    fn parse_instance(json_instance: &JsonInstance, config: &SparrowConfig) -> SPInstance {
+        let parser = Parser::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let any_instance = parser.parse(json_instance);
+        to_sp_instance(any_instance.as_ref()).expect("Expected SPInstance")
+    }
+
+    /// Distance between two rotations in degrees, taking the periodicity into account
+    fn angular_distance(a: f32, b: f32) -> f32 {
+        let d = (a - b).rem_euclid(360.0);
+        d.min(360.0 - d)
+    }
+
+    /// Builder of a run of the instance with the fixed seed, the budget is left to the test
+    fn seeded_run(json_instance: &JsonInstance) -> SparrowBuilder {
+        Sparrow::builder()
+            .json_instance(json_instance.clone())
+            .seed(RNG_SEED.unwrap() as u64)
+    }
     
     #[test_case("swim.json"; "swim")]
     #[test_case("shirts.json"; "shirts")]
     #[test_case("trousers.json"; "trousers")]
     fn simulate_optimization(path: &str) {
-        let input_file_path = format!("{INSTANCE_BASE_PATH}/{path}");
-        let json_instance = io::read_json_instance(Path::new(&input_file_path));
-
-        let parser = Parser::new(CDE_CONFIG, SIMPL_TOLERANCE, MIN_ITEM_SEPARATION);
-        let any_instance = parser.parse(&json_instance);
-        let instance = to_sp_instance(any_instance.as_ref()).expect("Expected SPInstance");
+        let json_instance = read_instance(path);
+        let config = DEFAULT_SPARROW_CONFIG;
+        let instance = parse_instance(&json_instance, &config);
 
         println!("[TEST] loaded instance: {}", json_instance.name);
 
@@ -48,13 +95,890 @@ mod integration_tests {
         let mut terminator = Terminator::new_without_ctrlc();
         terminator.set_timeout_from_now(EXPLORE_TIMEOUT);
 
-        let builder = LBFBuilder::new(instance.clone(), CDE_CONFIG, rng, LBF_SAMPLE_CONFIG).construct();
-        let mut separator = Separator::new(builder.instance, builder.prob, builder.rng, output_folder_path, 0, SEP_CFG_EXPLORE);
+        let builder = LBFBuilder::new(instance.clone(), config.cde_config, rng, config.lbf_sample_config).construct();
+        let mut separator = Separator::new(builder.instance, builder.prob, builder.rng, Box::new(SvgDirSink::new(output_folder_path, config.draw_options)), config.expl_cfg.separator_config);
 
-        let sols = exploration_phase(&instance, &mut separator, &terminator);
+        let sols = exploration_phase(&instance, &mut separator, &terminator, &config.expl_cfg);
         let final_explore_sol = sols.last().expect("no solutions found during exploration");
 
         terminator.set_timeout_from_now(COMPRESS_TIMEOUT);
-        compression_phase(&instance, &mut separator, final_explore_sol, &terminator);
+        compression_phase(&instance, &mut separator, final_explore_sol, &terminator, &config.cmpr_cfg);
+    }
+
+    /// Every run places all items without violations and does not lose density, whatever the strip mode
+    #[test_case("swim.json", |builder| builder; "swim")]
+    #[test_case("swim.json", |builder| builder.config(SparrowConfig { strip_mode: StripMode::Vertical, ..DEFAULT_SPARROW_CONFIG }); "swim_vertical")]
+    #[test_case("swim.json", |builder| builder.config(SparrowConfig { strip_mode: StripMode::Open { objective: OpenObjective::Area, max_aspect_ratio: Some(2.0) }, ..DEFAULT_SPARROW_CONFIG }); "swim_open_area")]
+    #[test_case("swim.json", |builder| builder.config(SparrowConfig { strip_mode: StripMode::Open { objective: OpenObjective::Perimeter, max_aspect_ratio: None }, ..DEFAULT_SPARROW_CONFIG }); "swim_open_perimeter")]
+    fn runs_place_all_items(path: &str, configure: fn(SparrowBuilder) -> SparrowBuilder) {
+        let json_instance = read_instance(path);
+
+        let result = configure(seeded_run(&json_instance))
+            .iter_budget(1000)
+            .build().unwrap()
+            .run();
+
+        let layout = Layout::from_snapshot(&result.solution.layout_snapshot);
+        assert_eq!(layout.placed_items().len(), result.instance.total_item_qty());
+        assert_eq!(result.validate(), vec![]);
+        assert!(result.stats.final_density >= result.stats.initial_density);
+    }
+
+    #[test_case("swim.json"; "swim")]
+    fn eval_budget_runs_are_reproducible(path: &str) {
+        let json_instance = read_instance(path);
+
+        let run = || {
+            let result = seeded_run(&json_instance)
+                .eval_budget(EVAL_BUDGET)
+                .build().unwrap()
+                .run();
+            serde_json::to_string(&result.json_solution().layouts).unwrap()
+        };
+
+        assert_eq!(run(), run(), "runs with the same seed and evaluation budget should produce identical solutions");
+    }
+
+    #[test_case("swim.json"; "swim")]
+    fn target_density_stops_run(path: &str) {
+        let json_instance = read_instance(path);
+
+        //the time limit is far beyond the duration of the test, only the target can stop the run in time
+        let result = seeded_run(&json_instance)
+            .time_limit(Duration::from_secs(3600))
+            .target(Target::Density(0.5))
+            .build().unwrap()
+            .run();
+
+        assert!(result.target_met);
+        assert!(result.stats.final_density >= 0.5);
+    }
+
+    #[test_case("swim.json"; "swim")]
+    fn trace_records_improvements(path: &str) {
+        let json_instance = read_instance(path);
+
+        let result = seeded_run(&json_instance)
+            .eval_budget(EVAL_BUDGET)
+            .build().unwrap()
+            .run();
+
+        let points = &result.trace.points;
+        assert!(points.first().is_some_and(|p| p.improvement && p.phase == Phase::Exploration));
+        assert!(points.windows(2).all(|w| w[0].elapsed <= w[1].elapsed && w[0].evals <= w[1].evals));
+        assert!(points.windows(2).all(|w| w[0].phase == w[1].phase || (w[0].phase, w[1].phase) == (Phase::Exploration, Phase::Compression)));
+
+        //improvements are feasible and monotonically shrink the strip
+        let improvements = points.iter().filter(|p| p.improvement).collect_vec();
+        assert!(improvements.iter().all(|p| p.total_loss == 0.0));
+        assert!(improvements.windows(2).all(|w| w[1].strip_width <= w[0].strip_width && w[1].density >= w[0].density));
+
+        //the last improvement is the final solution
+        let last_improvement = improvements.last().unwrap();
+        assert_eq!(last_improvement.strip_width, result.solution.strip_width);
+        assert!((last_improvement.density - result.stats.final_density).abs() < 1e-6);
+
+        let csv = result.trace.to_csv();
+        assert_eq!(csv.lines().count(), points.len() + 1);
+        assert!(csv.lines().skip(1).all(|line| line.split(',').count() == 7));
+    }
+
+    #[test_case("swim.json"; "swim")]
+    fn listener_receives_events(path: &str) {
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        enum Event {
+            PhaseChange(Phase),
+            Solution(Phase, f32),
+            Separation,
+            Termination(f32),
+        }
+
+        struct EventLog(Arc<Mutex<Vec<Event>>>);
+
+        impl OptimizationListener for EventLog {
+            fn on_phase_change(&mut self, phase: Phase) {
+                self.0.lock().unwrap().push(Event::PhaseChange(phase));
+            }
+            fn on_solution(&mut self, solution: &SPSolution, phase: Phase) {
+                self.0.lock().unwrap().push(Event::Solution(phase, solution.strip_width));
+            }
+            fn on_separation(&mut self, _stats: &SepStats, _loss: f32) {
+                self.0.lock().unwrap().push(Event::Separation);
+            }
+            fn on_termination(&mut self, result: &OptimizationResult) {
+                self.0.lock().unwrap().push(Event::Termination(result.solution.strip_width));
+            }
+        }
+
+        let json_instance = read_instance(path);
+
+        let events = Arc::new(Mutex::new(vec![]));
+        let result = seeded_run(&json_instance)
+            .iter_budget(500)
+            .listener(EventLog(events.clone()))
+            .build().unwrap()
+            .run();
+        let events = events.lock().unwrap();
+
+        //exploration, then compression, then termination with the final solution
+        let phase_changes = events.iter().positions(|e| matches!(e, Event::PhaseChange(_))).collect_vec();
+        assert_eq!(phase_changes.len(), 2);
+        assert_eq!(events[phase_changes[0]], Event::PhaseChange(Phase::Exploration));
+        assert_eq!(events[phase_changes[1]], Event::PhaseChange(Phase::Compression));
+        assert_eq!(phase_changes[0], 0);
+        assert_eq!(events.last(), Some(&Event::Termination(result.solution.strip_width)));
+        assert_eq!(events.iter().filter(|e| matches!(e, Event::Termination(_))).count(), 1);
+        assert!(events[..phase_changes[1]].contains(&Event::Separation));
+
+        //solutions are reported in the phase they were found in, every one narrower than the last
+        let solutions = events.iter().enumerate()
+            .filter_map(|(i, e)| match e {
+                Event::Solution(phase, width) => Some((i, *phase, *width)),
+                _ => None,
+            })
+            .collect_vec();
+        assert!(!solutions.is_empty());
+        for (i, phase, _) in solutions.iter() {
+            let expected = match *i > phase_changes[1] {
+                true => Phase::Compression,
+                false => Phase::Exploration,
+            };
+            assert_eq!(*phase, expected);
+        }
+        assert!(solutions.windows(2).all(|w| w[1].2 < w[0].2));
+        assert!(solutions.last().unwrap().2 >= result.solution.strip_width);
+    }
+
+    #[test_case("swim.json"; "swim")]
+    fn sinks_receive_solutions(path: &str) {
+        let json_instance = read_instance(path);
+        let json_folder = format!("{OUTPUT_DIR}/tests_json_sink_{}", json_instance.name);
+
+        let sink = MemorySink::new();
+        let result = seeded_run(&json_instance)
+            .iter_budget(500)
+            .sink(sink.clone())
+            .sink(JsonDirSink::new(&json_folder, json_instance.name.clone()))
+            .build().unwrap()
+            .run();
+
+        let solutions = sink.take();
+        assert_eq!(solutions.first().map(|(label, _)| label.as_str()), Some("init"));
+        let (label, final_sol) = solutions.last().expect("no solutions exported");
+        assert_eq!(label, "final");
+        assert_eq!(final_sol.strip_width, result.solution.strip_width);
+        assert!(solutions.iter().all(|(label, _)| ["init", "expl_f", "expl_nf", "cmpr", "final"].contains(&label.as_str())));
+
+        //the JSON sink wrote every intermediate solution (not the final one), numbered in order of export
+        let intermediates = &solutions[..solutions.len() - 1];
+        let json_files = std::fs::read_dir(&json_folder).unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .sorted_by_key(|path| {
+                let file_name = path.file_name().unwrap().to_str().unwrap();
+                file_name.split('_').next().unwrap().parse::<usize>().unwrap()
+            })
+            .collect_vec();
+        assert_eq!(json_files.len(), intermediates.len());
+        for (json_file, (label, sol)) in json_files.iter().zip(intermediates.iter()) {
+            assert!(json_file.to_str().unwrap().ends_with(&format!("_{label}.json")));
+            let json_sol = io::read_solution(json_file);
+            let JsonContainer::Strip { width, .. } = json_sol.layouts[0].container;
+            assert_eq!(width, sol.strip_width);
+            let layout = Layout::from_snapshot(&sol.layout_snapshot);
+            assert_eq!(json_sol.layouts[0].placed_items.len(), layout.placed_items().len());
+        }
+    }
+
+    #[test_case("swim.json", 2500.0, 2500.0; "swim")]
+    fn bin_packing_places_all_items(path: &str, sheet_width: f32, sheet_height: f32) {
+        let json_instance = read_instance(path);
+        let config = DEFAULT_SPARROW_CONFIG;
+        let instance = parse_instance(&json_instance, &config);
+
+        let mut terminator = Terminator::new_without_ctrlc();
+        terminator.set_budget(Budget::iterations(500));
+
+        let rng = SmallRng::seed_from_u64(RNG_SEED.unwrap() as u64);
+        let result = optimize_bin_packing(&instance, sheet_width, sheet_height, rng, &terminator, &config)
+            .unwrap_or_else(|err| panic!("bin packing failed: {}", err));
+
+        let n_placed: usize = result.sheets.iter().map(|s| Layout::from_snapshot(&s.layout_snapshot).placed_items().len()).sum();
+        assert!(result.unplaced.is_empty());
+        assert_eq!(n_placed, instance.total_item_qty());
+        assert!(result.sheets.len() >= result.lower_bound);
+        for sheet in result.sheets.iter() {
+            let layout = Layout::from_snapshot(&sheet.layout_snapshot);
+            assert_eq!(layout.bin.outer_cd.bbox().height(), sheet_height);
+        }
+    }
+
+    #[test]
+    fn bin_packing_reaches_lower_bound() {
+        //4 squares fit on a sheet, with little room to spare
+        let json_instance: JsonInstance = serde_json::from_value(serde_json::json!({
+            "Name": "squares",
+            "Items": [{
+                "Demand": 8,
+                "Shape": { "Type": "SimplePolygon", "Data": [[0.0, 0.0], [48.0, 0.0], [48.0, 48.0], [0.0, 48.0]] }
+            }],
+            "Strip": { "Height": 100.0 }
+        })).unwrap();
+        let config = DEFAULT_SPARROW_CONFIG;
+        let instance = parse_instance(&json_instance, &config);
+
+        let mut terminator = Terminator::new_without_ctrlc();
+        terminator.set_budget(Budget::iterations(2000));
+
+        let rng = SmallRng::seed_from_u64(RNG_SEED.unwrap() as u64);
+        let result = optimize_bin_packing(&instance, 100.0, 100.0, rng, &terminator, &config).unwrap();
+        assert_eq!(result.lower_bound, 2);
+        assert_eq!(result.sheets.len(), result.lower_bound);
+        assert!(result.unplaced.is_empty());
+    }
+
+    #[test_case("swim.json", 10.0; "swim")]
+    fn bin_packing_rejects_small_sheets(path: &str, sheet_width: f32) {
+        let json_instance = read_instance(path);
+        let config = DEFAULT_SPARROW_CONFIG;
+        let instance = parse_instance(&json_instance, &config);
+
+        let terminator = Terminator::new_without_ctrlc();
+        let rng = SmallRng::seed_from_u64(RNG_SEED.unwrap() as u64);
+        let result = optimize_bin_packing(&instance, sheet_width, instance.strip_height, rng, &terminator, &config);
+        assert!(matches!(result, Err(BinPackingError::ItemExceedsSheet { .. })));
+    }
+
+    #[test_case("swim.json", 2000.0; "swim")]
+    fn knapsack_respects_demand_max(path: &str, container_width: f32) {
+        let json_instance = read_instance(path);
+        let config = DEFAULT_SPARROW_CONFIG;
+        let instance = parse_instance(&json_instance, &config);
+
+        let max_qtys = json_instance.items.iter()
+            .map(|item| item.demand_max.unwrap_or(item.demand) as usize)
+            .collect::<Vec<_>>();
+
+        let mut terminator = Terminator::new_without_ctrlc();
+        terminator.set_budget(Budget::iterations(500));
+
+        let values = instance.items().iter().map(|(item, _)| item.shape_cd.area()).collect_vec();
+
+        let rng = SmallRng::seed_from_u64(RNG_SEED.unwrap() as u64);
+        let result = optimize_knapsack(&instance, &KnapsackContainer::Rectangle { width: container_width }, &max_qtys, &values, rng, &terminator, &config);
+
+        let layout = Layout::from_snapshot(&result.solution.layout_snapshot);
+        let mut placed_qtys = vec![0; max_qtys.len()];
+        layout.placed_items().values().for_each(|pi| placed_qtys[pi.item_id] += 1);
+
+        assert!(result.n_placed > 0);
+        assert!(placed_qtys.iter().zip(max_qtys.iter()).all(|(placed, max)| placed <= max));
+        assert!(result.density <= 1.0);
+    }
+
+    #[test]
+    fn knapsack_maximizes_value_over_area() {
+        //a square filling the whole container, worth less than the three smaller squares which fit next to each other
+        let square = |size: f32, demand_max: usize| serde_json::json!({
+            "Demand": 1,
+            "DemandMax": demand_max,
+            "Shape": { "Type": "SimplePolygon", "Data": [[0.0, 0.0], [size, 0.0], [size, size], [0.0, size]] }
+        });
+        let json_instance: JsonInstance = serde_json::from_value(serde_json::json!({
+            "Name": "value_over_area",
+            "Items": [square(100.0, 1), square(45.0, 3)],
+            "Strip": { "Height": 100.0 }
+        })).unwrap();
+        let config = DEFAULT_SPARROW_CONFIG;
+        let instance = parse_instance(&json_instance, &config);
+        let values = [1.0, 10.0];
+
+        let mut terminator = Terminator::new_without_ctrlc();
+        terminator.set_budget(Budget::iterations(200));
+
+        let rng = SmallRng::seed_from_u64(RNG_SEED.unwrap() as u64);
+        let result = optimize_knapsack(&instance, &KnapsackContainer::Rectangle { width: 100.0 }, &[1, 3], &values, rng, &terminator, &config);
+
+        let layout = Layout::from_snapshot(&result.solution.layout_snapshot);
+        assert!(layout.placed_items().values().all(|pi| pi.item_id == 1));
+        assert_eq!(result.n_placed, 3);
+        assert_eq!(result.value, 30.0);
+    }
+
+    /// L-shaped container (missing its top right quadrant) with a zone of quality 1,
+    /// item 0 requires full quality, item 1 may be placed in the zone
+    fn hide_instance() -> (JsonInstance, serde_json::Value) {
+        let square = |base_quality: Option<usize>| serde_json::json!({
+            "Demand": 1,
+            "DemandMax": 8,
+            "BaseQuality": base_quality,
+            "Shape": { "Type": "SimplePolygon", "Data": [[0.0, 0.0], [30.0, 0.0], [30.0, 30.0], [0.0, 30.0]] }
+        });
+        let json_instance = serde_json::from_value(serde_json::json!({
+            "Name": "hide",
+            "Items": [square(None), square(Some(1))],
+            "Strip": { "Height": 200.0 }
+        })).unwrap();
+        let container = serde_json::json!({
+            "Shape": { "Type": "SimplePolygon", "Data": [[0.0, 0.0], [200.0, 0.0], [200.0, 100.0], [100.0, 100.0], [100.0, 200.0], [0.0, 200.0]] },
+            "Zones": [{ "Quality": 1, "Shape": { "Type": "SimplePolygon", "Data": [[20.0, 20.0], [100.0, 20.0], [100.0, 80.0], [20.0, 80.0]] } }]
+        });
+        (json_instance, container)
+    }
+
+    #[test]
+    fn tracker_quantifies_irregular_bin_and_zones() {
+        let (json_instance, container) = hide_instance();
+        let config = DEFAULT_SPARROW_CONFIG;
+        let parser = Parser::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = parse_instance(&json_instance, &config);
+        let bin = io::parse_container(&json_instance, &container, &parser);
+
+        let mut prob = SPProblem::new(instance.clone(), 200.0, config.cde_config);
+        prob.layout = Layout::new(bin);
+        let place = |prob: &mut SPProblem, item_id: usize, pos: (f32, f32)| prob.place_item(SPPlacement { item_id, d_transf: DTransformation::new(0.0, pos) });
+        let in_zone = place(&mut prob, 0, (40.0, 50.0));
+        let allowed_in_zone = place(&mut prob, 1, (80.0, 50.0));
+        let outside = place(&mut prob, 0, (150.0, 150.0));
+
+        let ct = CollisionTracker::new(&prob.layout);
+        let zone = ct.bin_hazards.iter().find(|h| matches!(h, HazardEntity::InferiorQualityZone { .. })).expect("no zone hazard");
+        let exterior = &HazardEntity::BinExterior;
+
+        assert!(ct.get_bin_loss(in_zone, zone) > 0.0);
+        assert_eq!(ct.get_bin_loss(in_zone, exterior), 0.0);
+        assert_eq!(ct.get_bin_loss(allowed_in_zone, zone), 0.0);
+        assert!(ct.get_bin_loss(outside, exterior) > 0.0);
+    }
+
+    #[test]
+    fn knapsack_fills_irregular_container_around_zone() {
+        let (json_instance, container) = hide_instance();
+        let config = DEFAULT_SPARROW_CONFIG;
+        let parser = Parser::new(config.cde_config, config.poly_simpl_tolerance, config.min_item_separation);
+        let instance = parse_instance(&json_instance, &config);
+        let bin = io::parse_container(&json_instance, &container, &parser);
+
+        let mut terminator = Terminator::new_without_ctrlc();
+        terminator.set_budget(Budget::iterations(300));
+
+        let rng = SmallRng::seed_from_u64(RNG_SEED.unwrap() as u64);
+        let values = [1.0, 1.0];
+        let result = optimize_knapsack(&instance, &KnapsackContainer::Bin(bin), &[8, 8], &values, rng, &terminator, &config);
+
+        let layout = Layout::from_snapshot(&result.solution.layout_snapshot);
+        assert!(result.n_placed > 0);
+        for (pk, pi) in layout.placed_items().iter() {
+            let mut detector = BasicHazardDetector::new();
+            layout.cde().collect_poly_collisions(pi.shape.as_ref(), &mut detector);
+            detector.remove(&HazardEntity::from((pk, pi)));
+            remove_irrelevant_hazards(&mut detector, pi.hazard_filter.as_ref());
+            assert!(detector.is_empty(), "item {} collides with {:?}", pi.item_id, detector.iter().collect_vec());
+        }
+    }
+
+    #[test_case("swim.json"; "swim")]
+    fn separator_keeps_weights_on_insert_and_removal(path: &str) {
+        let json_instance = read_instance(path);
+        let config = DEFAULT_SPARROW_CONFIG;
+        let instance = parse_instance(&json_instance, &config);
+
+        let rng = SmallRng::seed_from_u64(RNG_SEED.unwrap() as u64);
+        let builder = LBFBuilder::new(instance.clone(), config.cde_config, rng, config.lbf_sample_config).construct();
+        let mut sep = Separator::new(builder.instance, builder.prob, builder.rng, Box::new(NoSink), config.expl_cfg.separator_config);
+
+        //shrink the strip to cause collisions and increase their weights
+        let strip_width = sep.prob.strip_width();
+        sep.change_strip_width(strip_width * 0.8, None);
+        sep.ct.increment_weights(&config.expl_cfg.separator_config.weight_config);
+
+        let pks = sep.prob.layout.placed_items().keys().collect_vec();
+        let (removed, kept) = pks.split_first().unwrap();
+        let weights = kept.iter().tuple_combinations()
+            .map(|(pk_1, pk_2)| sep.ct.get_pair_weight(*pk_1, *pk_2))
+            .collect_vec();
+        assert!(weights.iter().any(|w| *w > 1.0));
+
+        let placement = SPPlacement { item_id: sep.prob.layout.placed_items()[*removed].item_id, d_transf: sep.prob.layout.placed_items()[*removed].d_transf };
+        sep.remove_item(*removed);
+        let inserted = sep.place_item(placement);
+
+        let weights_after = kept.iter().tuple_combinations()
+            .map(|(pk_1, pk_2)| sep.ct.get_pair_weight(*pk_1, *pk_2))
+            .collect_vec();
+        assert_eq!(weights, weights_after);
+        assert!(kept.iter().all(|pk| sep.ct.get_pair_weight(*pk, inserted) == 1.0));
+        assert_eq!(sep.ct.size, pks.len());
+    }
+
+    #[test_case("swim.json", 5.0, 10.0; "swim")]
+    fn spacing_and_margin_are_respected(path: &str, spacing: f32, margin: f32) {
+        let json_instance = read_instance(path);
+
+        let mut config = DEFAULT_SPARROW_CONFIG;
+        config.min_item_separation = Some(spacing);
+        config.bin_margin = Some(margin);
+
+        let result = seeded_run(&json_instance)
+            .config(config)
+            .iter_budget(1000)
+            .build().unwrap()
+            .run();
+
+        assert_eq!(result.validate(), vec![]);
+
+        //the strip was shrunk by the part of the margin not covered by the spacing, the exported solution covers the full strip again
+        let strip_height = parse_instance(&json_instance, &config).strip_height;
+        assert!((result.margin_inset - (margin - spacing / 2.0)).abs() < 1e-4);
+        assert!((result.instance.strip_height - (strip_height - 2.0 * result.margin_inset)).abs() < 1e-4);
+        let json_sol = result.json_solution();
+        let JsonContainer::Strip { height, .. } = json_sol.layouts[0].container;
+        assert!((height - strip_height).abs() < 1e-4);
+
+        //an unreasonable spacing is reported, so the check above is not vacuous
+        assert!(!validate_solution(&json_sol, &result.instance, spacing * 100.0, margin).is_empty());
+
+        //an item pushed out of the container is reported with a negative distance
+        let mut outside_sol = json_sol.clone();
+        let JsonContainer::Strip { width, .. } = outside_sol.layouts[0].container;
+        outside_sol.layouts[0].placed_items[0].transformation.translation.0 += width;
+        let violations = validate_solution(&outside_sol, &result.instance, spacing, margin);
+        assert!(violations.iter().any(|v| matches!(v, Violation::Margin { item: 0, distance, .. } if *distance < 0.0)));
+    }
+
+    #[test_case("swim.json"; "swim")]
+    fn vertical_strip_keeps_width(path: &str) {
+        let json_instance = read_instance(path);
+
+        //counts the (width, height) changes of the strip
+        struct StripChanges(Arc<Mutex<(usize, usize)>>);
+
+        impl OptimizationListener for StripChanges {
+            fn on_width_change(&mut self, _old_width: f32, _new_width: f32, _phase: Phase) {
+                self.0.lock().unwrap().0 += 1;
+            }
+            fn on_height_change(&mut self, old_height: f32, new_height: f32, _phase: Phase) {
+                assert!(new_height < old_height);
+                self.0.lock().unwrap().1 += 1;
+            }
+        }
+
+        let mut config = DEFAULT_SPARROW_CONFIG;
+        config.strip_mode = StripMode::Vertical;
+
+        let changes = Arc::new(Mutex::new((0, 0)));
+        let result = seeded_run(&json_instance)
+            .config(config)
+            .iter_budget(1000)
+            .listener(StripChanges(changes.clone()))
+            .build().unwrap()
+            .run();
+
+        //the strip height of the instance is the fixed width of the vertical strip
+        assert_eq!(result.solution.strip_width, result.instance.strip_height);
+
+        //only the height of a vertical strip is shrunk
+        let (width_changes, height_changes) = *changes.lock().unwrap();
+        assert_eq!(width_changes, 0);
+        assert!(height_changes > 0);
+    }
+
+    #[test_case("swim.json", OpenObjective::Area, Some(2.0); "swim_area")]
+    #[test_case("swim.json", OpenObjective::Perimeter, Some(2.0); "swim_perimeter")]
+    #[test_case("swim.json", OpenObjective::Area, None; "swim_area_unbounded")]
+    fn open_dimension_shrinks_strip(path: &str, objective: OpenObjective, max_aspect_ratio: Option<f32>) {
+        let json_instance = read_instance(path);
+
+        let mut config = DEFAULT_SPARROW_CONFIG;
+        config.strip_mode = StripMode::Open { objective, max_aspect_ratio };
+
+        let sink = MemorySink::new();
+        let result = seeded_run(&json_instance)
+            .config(config)
+            .iter_budget(1000)
+            .sink(sink.clone())
+            .build().unwrap()
+            .run();
+
+        //neither dimension grows, while the objective decreases
+        let (_, init_sol) = sink.take().into_iter()
+            .find(|(label, _)| label == "init")
+            .expect("initial solution not exported");
+        let (init_width, init_height) = (init_sol.strip_width, solution_height(&init_sol));
+        let (width, height) = (result.solution.strip_width, solution_height(&result.solution));
+        assert!(width <= init_width, "width grew from {init_width} to {width}");
+        assert!(height <= init_height, "height grew from {init_height} to {height}");
+        assert!(config.strip_mode.objective(width, height) < config.strip_mode.objective(init_width, init_height));
+
+        if let Some(max_aspect_ratio) = max_aspect_ratio {
+            let aspect_ratio = width.max(height) / width.min(height);
+            assert!(aspect_ratio <= max_aspect_ratio + 1e-4, "aspect ratio {aspect_ratio} exceeds {max_aspect_ratio}");
+        }
+    }
+
+    #[test_case("swim.json"; "swim")]
+    fn strip_height_change_round_trips(path: &str) {
+        let json_instance = read_instance(path);
+        let config = DEFAULT_SPARROW_CONFIG;
+        let instance = parse_instance(&json_instance, &config);
+
+        let rng = SmallRng::seed_from_u64(RNG_SEED.unwrap() as u64);
+        let builder = LBFBuilder::new(instance.clone(), config.cde_config, rng, config.lbf_sample_config).construct();
+        let mut sep = Separator::new(builder.instance, builder.prob, builder.rng, Box::new(NoSink), config.expl_cfg.separator_config);
+        let initial = sep.prob.save();
+        let (width, height) = (sep.prob.strip_width(), sep.strip_height());
+        let bin_bbox = |sep: &Separator| {
+            let bbox = sep.prob.layout.bin.outer_cd.bbox();
+            (bbox.x_min, bbox.y_min, bbox.x_max, bbox.y_max)
+        };
+        let initial_bbox = bin_bbox(&sep);
+
+        //the bin and every copy of the instance follow the new height
+        let new_height = height * 0.9;
+        sep.change_strip_height(new_height, None);
+        assert_eq!(sep.strip_height(), new_height);
+        assert_eq!(sep.prob.strip_width(), width);
+        assert_eq!(sep.instance.strip_height, new_height);
+        assert_eq!(sep.prob.instance.strip_height, new_height);
+
+        sep.restore_strip(&initial);
+        assert_eq!(bin_bbox(&sep), initial_bbox);
+        assert_eq!(sep.instance.strip_height, height);
+        assert_eq!(sep.prob.instance.strip_height, height);
+        assert_eq!(sep.prob.layout.placed_items().len(), instance.total_item_qty());
+        assert_eq!(sep.prob.density(), initial.density(&instance));
+    }
+
+    #[test_case("swim.json"; "swim")]
+    fn groups_keep_relative_placement(path: &str) {
+        let json_instance = read_instance(path);
+        let config = DEFAULT_SPARROW_CONFIG;
+        let instance = parse_instance(&json_instance, &config);
+
+        //item 1 is placed right next to item 0
+        let (bbox_0, bbox_1) = (instance.item(0).shape_orig.shape.bbox(), instance.item(1).shape_orig.shape.bbox());
+        let json_group = JsonItemGroup {
+            items: vec![
+                JsonPlacedItem { index: 0, mirrored: false, transformation: JsonTransformation { rotation: 0.0, translation: (0.0, 0.0) } },
+                JsonPlacedItem { index: 1, mirrored: false, transformation: JsonTransformation { rotation: 0.0, translation: (bbox_0.x_max - bbox_1.x_min + 1.0, 0.0) } },
+            ],
+        };
+
+        let result = seeded_run(&json_instance)
+            .config(config)
+            .groups(vec![json_group.clone()])
+            .iter_budget(1000)
+            .build().unwrap()
+            .run();
+
+        let layout = Layout::from_snapshot(&result.solution.layout_snapshot);
+        assert_eq!(layout.placed_items().len(), result.instance.total_item_qty());
+
+        //every copy the demand allows for is still intact
+        let group = ItemGroup::from_json(&json_group, &result.instance);
+        let n_copies = group.n_copies(&result.instance.items().iter().map(|(_, qty)| *qty as isize).collect::<Vec<_>>());
+        assert!(n_copies > 0);
+        assert_eq!(GroupInstances::from_layout(&[group.clone()], &layout).instances.len(), n_copies);
+
+        //the copies tracked by the separator stay in sync with the layout while it is shrunk and separated
+        let rng = SmallRng::seed_from_u64(RNG_SEED.unwrap() as u64);
+        let builder = LBFBuilder::new(instance.clone(), config.cde_config, rng, config.lbf_sample_config)
+            .with_groups(vec![group.clone()])
+            .construct();
+        let mut sep = Separator::new(builder.instance, builder.prob, builder.rng, Box::new(NoSink), config.expl_cfg.separator_config);
+        sep.set_groups(vec![group.clone()]);
+        assert_eq!(sep.group_instances.instances.len(), n_copies);
+        let strip_width = sep.prob.strip_width();
+        sep.change_strip_width(strip_width * 0.9, None);
+        sep.separate(&Terminator::new_without_ctrlc());
+        let rebuilt = GroupInstances::from_layout(&[group], &sep.prob.layout);
+        let members = |gi: &GroupInstances| gi.instances.iter().map(|(_, pks)| pks.clone()).sorted().collect_vec();
+        assert_eq!(members(&sep.group_instances), members(&rebuilt));
+    }
+
+    #[test_case("swim.json", &[0, 1]; "swim")]
+    fn mirrored_items_share_demand(path: &str, mirrored_items: &[usize]) {
+        let json_instance = read_instance(path);
+        let result = seeded_run(&json_instance)
+            .mirrored_items(mirrored_items.to_vec())
+            .iter_budget(1000)
+            .build().unwrap()
+            .run();
+        let (instance, mirrors) = (&result.instance, &result.mirrors);
+
+        //the variants are the reflected originals
+        for &id in mirrored_items {
+            let variant = mirrors.counterpart(id).unwrap();
+            assert!(mirrors.is_variant(variant));
+            assert_eq!(mirrors.original(variant), id);
+            assert_eq!(instance.items()[variant].1, 0);
+            let (orig, refl) = (instance.item(id).shape_orig.shape.bbox(), instance.item(variant).shape_orig.shape.bbox());
+            assert!((orig.x_min + refl.x_max).abs() < 1e-3 && (orig.x_max + refl.x_min).abs() < 1e-3);
+            assert!((orig.y_min - refl.y_min).abs() < 1e-3 && (orig.y_max - refl.y_max).abs() < 1e-3);
+        }
+
+        //every item is placed as often as demanded, mirrored or not
+        let json_sol = result.json_solution();
+        let placed_items = &json_sol.layouts[0].placed_items;
+        let counts = placed_items.iter().counts_by(|json_pi| json_pi.index);
+        for (id, json_item) in json_instance.items.iter().enumerate() {
+            assert_eq!(counts.get(&id).copied().unwrap_or(0), json_item.demand as usize);
+        }
+        assert!(placed_items.iter().filter(|json_pi| json_pi.mirrored).all(|json_pi| mirrored_items.contains(&json_pi.index)));
+        assert!(result.validate().is_empty(), "mirrored placements are validated as reflected shapes");
+
+        //mirrored placements survive the round trip through the JSON and SVG solution formats
+        let mut flipped = json_sol.clone();
+        flipped.layouts[0].placed_items.iter_mut()
+            .filter(|json_pi| mirrored_items.contains(&json_pi.index))
+            .for_each(|json_pi| json_pi.mirrored = !json_pi.mirrored);
+        let flipped_sol = build_sp_problem(&flipped, instance, mirrors, DEFAULT_SPARROW_CONFIG.cde_config).save();
+        let svg = s_layout_to_svg(&flipped_sol.layout_snapshot, instance, mirrors, DEFAULT_SPARROW_CONFIG.draw_options, "flipped");
+        let flags = |sol: &JsonSolution| sol.layouts[0].placed_items.iter()
+            .map(|json_pi| (json_pi.index, json_pi.mirrored))
+            .sorted()
+            .collect_vec();
+        for restored in [compose_json_solution(&flipped_sol, instance, mirrors, "flipped"), svg_to_json_solution(&svg.to_string(), "flipped")] {
+            assert_eq!(flags(&restored), flags(&flipped));
+        }
+    }
+
+    #[test_case("swim.json"; "swim")]
+    fn portfolio_returns_best_run(path: &str) {
+        struct FinalWidths(Arc<Mutex<Vec<f32>>>);
+
+        impl OptimizationListener for FinalWidths {
+            fn on_termination(&mut self, result: &OptimizationResult) {
+                self.0.lock().unwrap().push(result.solution.strip_width);
+            }
+        }
+
+        let json_instance = read_instance(path);
+
+        let seeds = [0, 1, 2];
+        let final_widths = Arc::new(Mutex::new(vec![]));
+        let runs = seeds.iter()
+            .map(|&seed| seeded_run(&json_instance)
+                .seed(seed)
+                .iter_budget(1000)
+                .listener(FinalWidths(final_widths.clone()))
+                .build().unwrap())
+            .collect();
+        let result = run_portfolio(runs, true);
+
+        //the result is the best solution of all runs of the portfolio
+        let final_widths = final_widths.lock().unwrap();
+        assert_eq!(final_widths.len(), seeds.len());
+        assert!(final_widths.iter().all(|width| result.solution.strip_width <= *width));
+        assert!(seeds.contains(&result.seed));
+        let layout = Layout::from_snapshot(&result.solution.layout_snapshot);
+        assert_eq!(layout.placed_items().len(), result.instance.total_item_qty());
+        assert_eq!(result.validate(), vec![]);
+    }
+
+    #[test_case("swim.json"; "swim")]
+    fn continuous_rotation_leaves_discrete_orientations(path: &str) {
+        let mut json_instance = read_instance(path);
+        let discrete_orientations = json_instance.items.iter()
+            .map(|item| item.allowed_orientations.clone().unwrap_or(vec![0.0]))
+            .collect_vec();
+        json_instance.items.iter_mut().for_each(|item| item.allowed_orientations = None);
+
+        let result = seeded_run(&json_instance)
+            .iter_budget(500)
+            .build().unwrap()
+            .run();
+
+        let json_sol = result.json_solution();
+        let placed_items = &json_sol.layouts[0].placed_items;
+        assert_eq!(placed_items.len(), result.instance.total_item_qty());
+
+        //some items are placed at a rotation the original orientations do not allow
+        let is_discrete = |pi: &JsonPlacedItem| discrete_orientations[pi.index].iter()
+            .any(|r| angular_distance(pi.transformation.rotation, *r) < 0.01);
+        assert!(placed_items.iter().any(|pi| !is_discrete(pi)), "all items are placed at discrete orientations");
+        assert_eq!(result.validate(), vec![]);
+    }
+
+    #[test_case("swim.json"; "swim")]
+    fn only_allowed_orientations_are_placed(path: &str) {
+        let mut json_instance = read_instance(path);
+        let orientations = vec![0.0, 90.0, 180.0, 270.0];
+        json_instance.items.iter_mut().for_each(|item| item.allowed_orientations = Some(orientations.clone()));
+
+        let result = seeded_run(&json_instance)
+            .iter_budget(500)
+            .build().unwrap()
+            .run();
+
+        let json_sol = result.json_solution();
+        for pi in json_sol.layouts[0].placed_items.iter() {
+            assert!(orientations.iter().any(|r| angular_distance(pi.transformation.rotation, *r) < 0.01),
+                "item {} placed at a disallowed rotation: {}", pi.index, pi.transformation.rotation);
+        }
+        assert_eq!(result.validate(), vec![]);
+    }
+
+    #[test_case("swim.json"; "swim")]
+    fn checkpoint_restores_rng_and_weights(path: &str) {
+        let json_instance = read_instance(path);
+        let config = DEFAULT_SPARROW_CONFIG;
+        let instance = parse_instance(&json_instance, &config);
+
+        let rng = SmallRng::seed_from_u64(RNG_SEED.unwrap() as u64);
+        let builder = LBFBuilder::new(instance.clone(), config.cde_config, rng, config.lbf_sample_config).construct();
+        let mut sep = Separator::new(builder.instance, builder.prob, builder.rng, Box::new(NoSink), config.expl_cfg.separator_config);
+
+        //shrink the strip to cause collisions and increase their weights
+        let strip_width = sep.prob.strip_width();
+        sep.change_strip_width(strip_width * 0.8, None);
+        sep.ct.increment_weights(&config.expl_cfg.separator_config.weight_config);
+
+        std::fs::create_dir_all(OUTPUT_DIR).unwrap();
+        let checkpoint_path = format!("{OUTPUT_DIR}/tests_checkpoint_{}.json", json_instance.name);
+        let mut checkpointer = Checkpointer::new(checkpoint_path.clone().into(), Duration::ZERO, json_instance.name.clone());
+        let rng_before = sep.rng.clone();
+        checkpointer.save_exploration(&instance, &sep, &ExplorationState::new(&sep), &Terminator::new_without_ctrlc());
+        assert_eq!(sep.rng, rng_before, "saving a checkpoint should not change the rng");
+
+        let checkpoint = read_checkpoint(Path::new(&checkpoint_path));
+        let (prob, resumed_pks) = build_sp_problem_with_keys(&checkpoint.current, &instance, &MirrorVariants::default(), config.cde_config);
+        let mut resumed = Separator::new(instance.clone(), prob, SmallRng::seed_from_u64(1), Box::new(NoSink), config.expl_cfg.separator_config);
+        checkpoint.restore_separator(&mut resumed, &resumed_pks);
+        assert_eq!(resumed.rng, sep.rng);
+
+        //the placed items of the checkpoint are listed in the order they are stored in the layout
+        let pks = sep.prob.layout.placed_items().keys().collect_vec();
+        let resumed_pks = resumed_pks.into_iter().map(|pk| pk.unwrap()).collect_vec();
+        assert_eq!(pks.len(), resumed_pks.len());
+        for ((pk_1, r_pk_1), (pk_2, r_pk_2)) in pks.iter().zip(resumed_pks.iter()).tuple_combinations() {
+            assert_eq!(sep.ct.get_pair_weight(*pk_1, *pk_2), resumed.ct.get_pair_weight(*r_pk_1, *r_pk_2));
+        }
+        for ((pk, r_pk), hazard) in pks.iter().zip(resumed_pks.iter()).cartesian_product(sep.ct.bin_hazards.iter()) {
+            assert_eq!(sep.ct.get_bin_weight(*pk, hazard), resumed.ct.get_bin_weight(*r_pk, hazard));
+            assert_eq!(sep.prob.layout.placed_items()[*pk].item_id, resumed.prob.layout.placed_items()[*r_pk].item_id);
+        }
+    }
+
+    #[test_case("swim.json"; "swim")]
+    fn interrupted_checkpoint_write_keeps_previous_checkpoint(path: &str) {
+        let json_instance = read_instance(path);
+        let config = DEFAULT_SPARROW_CONFIG;
+        let instance = parse_instance(&json_instance, &config);
+
+        let rng = SmallRng::seed_from_u64(RNG_SEED.unwrap() as u64);
+        let builder = LBFBuilder::new(instance.clone(), config.cde_config, rng, config.lbf_sample_config).construct();
+        let sep = Separator::new(builder.instance, builder.prob, builder.rng, Box::new(NoSink), config.expl_cfg.separator_config);
+
+        std::fs::create_dir_all(OUTPUT_DIR).unwrap();
+        let checkpoint_path = format!("{OUTPUT_DIR}/tests_atomic_checkpoint_{}.json", json_instance.name);
+        let mut checkpointer = Checkpointer::new(checkpoint_path.clone().into(), Duration::ZERO, json_instance.name.clone());
+        checkpointer.save_exploration(&instance, &sep, &ExplorationState::new(&sep), &Terminator::new_without_ctrlc());
+        let saved = std::fs::read_to_string(&checkpoint_path).unwrap();
+        assert!(!Path::new(&format!("{checkpoint_path}.tmp")).exists());
+
+        //a write which fails halfway (maps with non-string keys cannot be serialized) leaves a partial temporary file
+        let unserializable = HashMap::from([((0, 0), 0)]);
+        let write = std::panic::catch_unwind(|| io::write_json_atomic(&unserializable, Path::new(&checkpoint_path), log::Level::Debug));
+        assert!(write.is_err());
+        assert!(Path::new(&format!("{checkpoint_path}.tmp")).exists());
+        assert_eq!(std::fs::read_to_string(&checkpoint_path).unwrap(), saved);
+        read_checkpoint(Path::new(&checkpoint_path));
+
+        //the next checkpoint replaces both
+        checkpointer.save_exploration(&instance, &sep, &ExplorationState::new(&sep), &Terminator::new_without_ctrlc());
+        assert!(!Path::new(&format!("{checkpoint_path}.tmp")).exists());
+        read_checkpoint(Path::new(&checkpoint_path));
+    }
+
+    #[test_case("swim.json"; "swim")]
+    fn build_rejects_invalid_configurations(path: &str) {
+        let json_instance = read_instance(path);
+
+        assert_eq!(Sparrow::builder().seed(0).build().err(), Some(BuildError::MissingInstance));
+
+        let checkpoint_path = format!("{OUTPUT_DIR}/tests_unused_checkpoint.json");
+        let open = StripMode::Open { objective: OpenObjective::Area, max_aspect_ratio: None };
+        for strip_mode in [StripMode::Vertical, open] {
+            let config = SparrowConfig { strip_mode, ..DEFAULT_SPARROW_CONFIG };
+            let result = seeded_run(&json_instance)
+                .config(config)
+                .checkpoint(checkpoint_path.clone(), Duration::from_secs(1))
+                .build();
+            assert!(matches!(result.err(), Some(BuildError::UnsupportedStripMode { .. })), "checkpoints accepted in {:?}", strip_mode);
+
+            //a warm start would be restored at the strip height of the instance, not the one of the solution
+            let json_sol = JsonSolution { name: json_instance.name.clone(), layouts: vec![], density: 0.0, run_time_sec: 0.0, timestamp: String::new() };
+            let result = seeded_run(&json_instance)
+                .config(config)
+                .initial_solution(json_sol)
+                .build();
+            assert!(matches!(result.err(), Some(BuildError::UnsupportedStripMode { .. })), "warm starts accepted in {:?}", strip_mode);
+        }
+    }
+
+    #[test]
+    fn partial_config_falls_back_to_defaults() {
+        let json_config = io::parse_sparrow_config(r#"{ "rng_seed": 42 }"#, ConfigFormat::Json).unwrap();
+        assert_eq!(json_config.rng_seed, Some(42));
+        assert_eq!(json_config.expl_cfg.shrink_step, DEFAULT_SPARROW_CONFIG.expl_cfg.shrink_step);
+
+        let toml_config = io::parse_sparrow_config(r#"
+            rng_seed = 42
+            [cmpr_cfg]
+            time_ratio = 0.5
+            shrink_range = [0.001, 0.0001]
+            [cmpr_cfg.separator_config]
+            iter_no_imprv_limit = 100
+            strike_limit = 5
+            n_workers = 2
+            log_level = "DEBUG"
+            weight_config = { max_inc_ratio = 2.0, min_inc_ratio = 1.2, decay = 0.95 }
+            sample_config = { n_bin_samples = 50, n_focussed_samples = 25, n_coord_descents = 3, cd_config = { step_success = 1.1, step_fail = 0.5 } }
+        "#, ConfigFormat::Toml).unwrap();
+        assert_eq!(toml_config.cmpr_cfg.separator_config.n_workers, 2);
+        assert_eq!(toml_config.expl_cfg.shrink_step, DEFAULT_SPARROW_CONFIG.expl_cfg.shrink_step);
+
+        //the defaults are only filled in by the parser, plain serde requires a complete config
+        assert!(serde_json::from_str::<SparrowConfig>(r#"{ "rng_seed": 42 }"#).is_err());
+    }
+
+    #[test]
+    fn partial_nested_config_falls_back_to_defaults() {
+        //nested tables missing fields, or missing entirely
+        let toml_config = io::parse_sparrow_config(r#"
+            [expl_cfg.separator_config]
+            n_workers = 5
+            [cmpr_cfg]
+            time_ratio = 0.5
+        "#, ConfigFormat::Toml).unwrap();
+        assert_eq!(toml_config.expl_cfg.separator_config.n_workers, 5);
+        assert_eq!(toml_config.expl_cfg.separator_config.strike_limit, SEP_CFG_EXPLORE.strike_limit);
+        assert_eq!(toml_config.expl_cfg.shrink_step, EXPLORE_SHRINK_STEP);
+        assert_eq!(toml_config.cmpr_cfg.time_ratio, 0.5);
+        assert_eq!(toml_config.cmpr_cfg.shrink_range, COMPRESS_SHRINK_RANGE);
+        assert_eq!(toml_config.cmpr_cfg.separator_config.strike_limit, SEP_CFG_COMPRESS.strike_limit);
+
+        //config files fall back to the defaults of the phase, even for partial separator configs
+        let file_config = io::parse_sparrow_config(r#"
+            [cmpr_cfg.separator_config]
+            n_workers = 2
+            [cmpr_cfg.separator_config.sample_config]
+            n_bin_samples = 10
+            [cde_config]
+            quadtree_depth = 3
+        "#, ConfigFormat::Toml).unwrap();
+        let sep_config = file_config.cmpr_cfg.separator_config;
+        assert_eq!(sep_config.n_workers, 2);
+        assert_eq!(sep_config.strike_limit, SEP_CFG_COMPRESS.strike_limit);
+        assert_eq!(sep_config.iter_no_imprv_limit, SEP_CFG_COMPRESS.iter_no_imprv_limit);
+        assert_eq!(sep_config.sample_config.n_bin_samples, 10);
+        assert_eq!(sep_config.sample_config.n_focussed_samples, SEP_CFG_COMPRESS.sample_config.n_focussed_samples);
+        assert_eq!(file_config.cde_config.quadtree_depth, 3);
+        assert_eq!(file_config.cde_config.item_surrogate_config.n_ff_poles, CDE_CONFIG.item_surrogate_config.n_ff_poles);
+
+        let json_config = io::parse_sparrow_config(r#"{ "expl_cfg": { "separator_config": { "weight_config": { "decay": 0.9 } } } }"#, ConfigFormat::Json).unwrap();
+        assert_eq!(json_config.expl_cfg.separator_config.weight_config.decay, 0.9);
+        assert_eq!(json_config.expl_cfg.separator_config.weight_config.max_inc_ratio, WEIGHT_MAX_INC_RATIO);
     }
 }
\ No newline at end of file